@@ -0,0 +1,57 @@
+//! The static runtime of the ahead-of-time compiled programs.
+//!
+//! It's linked with the object file of the program, by `trazodone build`, and
+//! contains the `hvm__*` functions of the runtime, and the HVM heap and reducer.
+//! The program is reduced by the generated reduce function of the object file,
+//! `hvm__reduce`, that calls the compiled functions directly.
+
+#![feature(slice_pattern)]
+
+use std::ffi::{c_char, CStr};
+
+use hvm::runtime::Program;
+
+use crate::reducer::Reducer;
+
+#[path = "runtime.rs"]
+pub mod runtime;
+
+#[path = "hvm/reducer.rs"]
+pub mod reducer;
+
+extern "C" {
+    static hvm__main: u64;
+    static hvm__symbols_len: u64;
+    static hvm__symbol_ids: u64;
+    static hvm__symbol_arities: u64;
+    static hvm__symbol_names: *const c_char;
+
+    fn hvm__reduce(ctx: *mut libc::c_void) -> bool;
+}
+
+/// The entry point of the program, it reduces `Main` and prints its normal form,
+/// returning the exit status.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__aot_main() -> i32 {
+    let len = hvm__symbols_len as usize;
+    let ids = std::slice::from_raw_parts(&hvm__symbol_ids, len);
+    let arities = std::slice::from_raw_parts(&hvm__symbol_arities, len);
+    let names = std::slice::from_raw_parts(&hvm__symbol_names, len);
+
+    // The compiled functions are called by the reduce function, so the program
+    // only has the names and the arities of the rule book.
+    let mut prog = Program::new();
+    for ((id, arity), name) in ids.iter().zip(arities).zip(names) {
+        prog.aris.insert(*id, *arity);
+        prog.nams.insert(*id, CStr::from_ptr(*name).to_string_lossy().into_owned());
+    }
+
+    let heap_size = hvm::runtime::default_heap_size();
+    let thread_ids = hvm::runtime::default_heap_tids();
+    let reducer = Reducer::new(prog, heap_size, thread_ids).with_reduce(hvm__reduce);
+    let (norm, _, _) = reducer.eval(hvm__main);
+    println!("{norm}");
+
+    0
+}
@@ -1,5 +1,10 @@
 use clap::{Args, Parser, Subcommand};
 
+use crate::llvm::aot::Emit;
+
+pub mod build;
+pub mod check;
+pub mod compile;
 pub mod eval;
 pub mod repl;
 
@@ -58,6 +63,94 @@ pub struct EvalArgs {
     /// The expression to run.
     #[clap(short = 'm', long)]
     main: Option<String>,
+
+    /// The optimization passes to run over the IR, in order, or `none`. By
+    /// default: const-fold, unreachable-blocks, dead-let, switch-dispatch.
+    #[clap(long, value_delimiter = ',')]
+    passes: Option<Vec<String>>,
+
+    /// Dumps the IR after every optimization pass.
+    #[clap(long, default_value = "false", default_missing_value = "true")]
+    dump_passes: bool,
+
+    /// The optimization level of the LLVM module, from 0 to 3.
+    #[clap(short = 'O', long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=3))]
+    opt_level: u8,
+
+    /// The LLVM passes to run over the module, in order, or `none`, instead of
+    /// the passes of the optimization level.
+    #[clap(long, value_delimiter = ',')]
+    llvm_passes: Option<Vec<String>>,
+
+    /// Dumps the LLVM module before and after the LLVM passes.
+    #[clap(long, default_value = "false", default_missing_value = "true")]
+    dump_llvm: bool,
+
+    /// Reuses the matched nodes of the left-hand side, instead of allocating new ones.
+    #[clap(long, default_value = "false", default_missing_value = "true")]
+    transmute: bool,
+
+    /// Prints every rule that is applied, in both the JIT and the evaluation modes.
+    #[clap(long, default_value = "false", default_missing_value = "true")]
+    trace: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+#[clap(about = "Check a file for errors, non-exhaustive matches and unreachable rules")]
+#[clap(aliases = &["c"])]
+pub struct CheckArgs {
+    /// A "file.hvm" to check.
+    #[clap(short = 'f', long)]
+    file: String,
+}
+
+#[derive(Args, Debug, Clone)]
+#[clap(about = "Compile a file ahead-of-time to a native executable, that reduces `Main`")]
+#[clap(aliases = &["b"])]
+pub struct BuildArgs {
+    /// A "file.hvm" to compile.
+    #[clap(short = 'f', long)]
+    file: String,
+
+    /// The path of the executable. By default, the name of the file without
+    /// its extension.
+    #[clap(short = 'o', long)]
+    output: Option<String>,
+
+    /// The optimization level of the LLVM module, from 0 to 3.
+    #[clap(short = 'O', long, default_value = "2", value_parser = clap::value_parser!(u8).range(0..=3))]
+    opt_level: u8,
+
+    /// The static runtime library to link with. By default, the
+    /// `libtrazodone_runtime.a` next to the `trazodone` executable.
+    #[clap(long)]
+    runtime: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+#[clap(about = "Compile a file to LLVM IR, bitcode, assembly or an object file")]
+pub struct CompileArgs {
+    /// A "file.hvm" to compile.
+    #[clap(short = 'f', long)]
+    file: String,
+
+    /// The path of the emitted file. By default, the name of the file with the
+    /// extension of the format.
+    #[clap(short = 'o', long)]
+    output: Option<String>,
+
+    /// The format to emit: llvm-ir, bc, asm or obj.
+    #[clap(long, default_value = "llvm-ir", value_parser = parse_emit)]
+    emit: Emit,
+
+    /// The optimization level of the LLVM module, from 0 to 3.
+    #[clap(short = 'O', long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=3))]
+    opt_level: u8,
+
+    /// The LLVM passes to run over the module, in order, or `none`, instead of
+    /// the passes of the optimization level.
+    #[clap(long, value_delimiter = ',')]
+    llvm_passes: Option<Vec<String>>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -66,6 +159,9 @@ pub enum Command {
     #[clap(about = "Joins the HVM Repl")]
     Repl,
     Eval(EvalArgs),
+    Check(CheckArgs),
+    Build(BuildArgs),
+    Compile(CompileArgs),
 }
 
 pub fn run_cli() {
@@ -74,6 +170,9 @@ pub fn run_cli() {
     match cli.command {
         Command::Repl => repl::start_repl(),
         Command::Eval(args) => eval::run_eval(args),
+        Command::Check(args) => check::run_check(args),
+        Command::Build(args) => build::run_build(args),
+        Command::Compile(args) => compile::run_compile(args),
     }
 }
 
@@ -90,3 +189,13 @@ fn parse_thread_ids(text: &str) -> Result<usize, String> {
         _ => text.parse::<usize>().map_err(|x| format!("{}", x)),
     }
 }
+
+fn parse_emit(text: &str) -> Result<Emit, String> {
+    match text {
+        "llvm-ir" => Ok(Emit::LlvmIr),
+        "bc" => Ok(Emit::Bitcode),
+        "asm" => Ok(Emit::Assembly),
+        "obj" => Ok(Emit::Object),
+        _ => Err(format!("unknown format `{text}`, the formats are: llvm-ir, bc, asm, obj")),
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+use clap::error::ErrorKind::InvalidValue;
+use clap::CommandFactory;
+use colored::Colorize;
+
+use crate::cli::compile::compile_module;
+use crate::cli::eval::report_diagnostics;
+use crate::cli::{BuildArgs, Cli};
+use crate::diagnostic::{Diagnostic, ErrorCode, Source};
+use crate::llvm::aot::{emit, link, Emit};
+use crate::llvm::optimize::Optimizer;
+
+/// Compiles the file to a native executable, linked with the static runtime.
+pub fn run_build(args: BuildArgs) {
+    let mut cli = Cli::command();
+
+    let code = std::fs::read_to_string(&args.file).unwrap_or_else(|_| {
+        cli.error(InvalidValue, "Failed to read file.").exit();
+    });
+    let source = Source::new(&args.file, &code);
+
+    let optimizer = Optimizer::new(args.opt_level, None).unwrap_or_else(|err| {
+        cli.error(InvalidValue, err).exit();
+    });
+    let output = match &args.output {
+        Some(output) => PathBuf::from(output),
+        None => Path::new(&args.file).with_extension(""),
+    };
+    if output == Path::new(&args.file) {
+        cli.error(InvalidValue, "The executable would overwrite the file, use --output.")
+            .exit();
+    }
+    let runtime = match &args.runtime {
+        Some(runtime) => PathBuf::from(runtime),
+        None => default_runtime().unwrap_or_else(|err| {
+            cli.error(InvalidValue, err).exit();
+        }),
+    };
+
+    let context = inkwell::context::Context::create();
+    let build = || -> Result<(), String> {
+        let module = compile_module(&context, &source, &optimizer, true)?;
+
+        let object = output.with_extension(Emit::Object.extension());
+        emit(&module, Emit::Object, optimizer.level, &object)?;
+        link(&object, &runtime, &output)?;
+        std::fs::remove_file(&object).map_err(|e| format!("Could not remove the object file: {e}"))
+    };
+    build().unwrap_or_else(|err| {
+        report_diagnostics(&[Diagnostic::error(ErrorCode::Backend, err)], &source);
+    });
+
+    let message = format!("`{}` compiled to `{}`", args.file, output.display());
+    println!("{}", message.bright_blue());
+}
+
+/// The static runtime, built by cargo in the same directory of the `trazodone`
+/// executable.
+fn default_runtime() -> Result<PathBuf, String> {
+    let executable = std::env::current_exe().map_err(|e| format!("Could not find the executable: {e}"))?;
+    let runtime = executable.with_file_name("libtrazodone_runtime.a");
+
+    match runtime.exists() {
+        true => Ok(runtime),
+        false => Err(format!("Could not find the runtime `{}`, use --runtime", runtime.display())),
+    }
+}
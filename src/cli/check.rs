@@ -0,0 +1,82 @@
+use clap::error::ErrorKind::InvalidValue;
+use clap::CommandFactory;
+use colored::Colorize;
+
+use crate::cli::eval::{ir_codegen_book, parse_book, report_diagnostics, setup_global_context};
+use crate::cli::{CheckArgs, Cli};
+use crate::codegen::check::{check_group, Families};
+use crate::codegen::syntax::Transform;
+use crate::diagnostic::{self, Diagnostic, Source};
+use crate::passes::PassManager;
+
+/// Checks the file, without evaluating it. Reports the compiler errors, and
+/// the non-exhaustive and unreachable rules as warnings.
+pub fn run_check(args: CheckArgs) {
+    let mut cli = Cli::command();
+
+    let code = std::fs::read_to_string(&args.file).unwrap_or_else(|_| {
+        cli.error(InvalidValue, "Failed to read file.").exit();
+    });
+    let source = Source::new(&args.file, &code);
+
+    let (errors, warnings): (Vec<_>, Vec<_>) = check_source(&source)
+        .into_iter()
+        .partition(Diagnostic::is_error);
+
+    if !warnings.is_empty() {
+        eprintln!("{}", diagnostic::render_all(&warnings, &source));
+    }
+
+    if !errors.is_empty() {
+        report_diagnostics(&errors, &source);
+    }
+
+    let message = format!("`{}` checked with {} warning(s)", args.file, warnings.len());
+    println!("{}", message.bright_blue());
+}
+
+/// Checks the rule groups of the source, as they're written, returning the
+/// warnings, and then the errors of the code generation.
+pub(crate) fn check_source(source: &Source) -> Vec<Diagnostic> {
+    let book = parse_book(source);
+    let global = setup_global_context(&book);
+
+    let groups = match book.clone().transform() {
+        Ok(groups) => groups,
+        Err(diagnostic) => return vec![diagnostic],
+    };
+    let families = Families::new(&groups, &global);
+
+    let mut diagnostics = groups
+        .iter()
+        .flat_map(|group| check_group(group, &families, &global))
+        .collect::<Vec<_>>();
+
+    if let Err(errors) = ir_codegen_book(&book, global, &PassManager::default()) {
+        diagnostics.extend(errors);
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_checks_the_nested_patterns_as_written() {
+        let code = r#"
+            (Pred (Succ (Succ n))) = (Succ n)
+            (Pred (Succ (Zero)))   = (Zero)
+            (Pred (Zero))          = (Zero)
+            (Half (Succ (Succ n))) = (Succ (Half n))
+            (Half n)               = (Zero)
+            (Main) = (Pair (Pred (Succ (Zero))) (Half (Zero)))
+            "#;
+        let source = Source::new("main.hvm", code);
+
+        let diagnostics = check_source(&source);
+
+        assert!(diagnostics.is_empty(), "{}", diagnostic::render_all(&diagnostics, &source));
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use clap::error::ErrorKind::InvalidValue;
+use clap::CommandFactory;
+use colored::Colorize;
+use inkwell::context::Context;
+use inkwell::module::Module;
+
+use crate::cli::eval::{ir_codegen_book, parse_book, report_diagnostics, setup_global_context};
+use crate::cli::{Cli, CompileArgs};
+use crate::diagnostic::{Diagnostic, ErrorCode, Source};
+use crate::llvm::aot::emit;
+use crate::llvm::apply::Codegen;
+use crate::llvm::optimize::Optimizer;
+use crate::passes::PassManager;
+
+/// Compiles the file, and writes the module of every rule group in the format
+/// of `--emit`, without the entry point of the executables.
+pub fn run_compile(args: CompileArgs) {
+    let mut cli = Cli::command();
+
+    let code = std::fs::read_to_string(&args.file).unwrap_or_else(|_| {
+        cli.error(InvalidValue, "Failed to read file.").exit();
+    });
+    let source = Source::new(&args.file, &code);
+
+    let optimizer = Optimizer::new(args.opt_level, args.llvm_passes.as_deref()).unwrap_or_else(|err| {
+        cli.error(InvalidValue, err).exit();
+    });
+    let output = match &args.output {
+        Some(output) => PathBuf::from(output),
+        None => Path::new(&args.file).with_extension(args.emit.extension()),
+    };
+
+    let context = Context::create();
+    let compile = || -> Result<(), String> {
+        let module = compile_module(&context, &source, &optimizer, false)?;
+        emit(&module, args.emit, optimizer.level, &output)
+    };
+    compile().unwrap_or_else(|err| {
+        report_diagnostics(&[Diagnostic::error(ErrorCode::Backend, err)], &source);
+    });
+
+    let message = format!("`{}` compiled to `{}`", args.file, output.display());
+    println!("{}", message.bright_blue());
+}
+
+/// Compiles the source to the module of every rule group, optimized by the
+/// [Optimizer], with the entry point of the executables, if `entry` is set. The
+/// diagnostics of the source are reported before, and exit the program.
+pub(crate) fn compile_module<'a>(
+    context: &'a Context,
+    source: &Source,
+    optimizer: &Optimizer,
+    entry: bool,
+) -> Result<Module<'a>, String> {
+    let book = parse_book(source);
+    let global = setup_global_context(&book);
+    let groups = ir_codegen_book(&book, global, &PassManager::default()).unwrap_or_else(|diagnostics| {
+        report_diagnostics(&diagnostics, source);
+    });
+
+    let mut codegen = Codegen::new(context).map_err(|e| format!("Could not create codegen: {e}"))?;
+    codegen.build_program(&book, &groups)?;
+    if entry {
+        codegen.build_entry(&book)?;
+    }
+
+    optimizer.run(&codegen.module);
+
+    Ok(codegen.module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llvm::aot::Emit;
+
+    #[test]
+    fn it_emits_the_module() {
+        let source = Source::new("main.hvm", "(Add a b) = (+ a b)\n(Main) = (Add 1 2)");
+        let optimizer = Optimizer::new(0, None).unwrap();
+        let context = Context::create();
+        let module = compile_module(&context, &source, &optimizer, false).unwrap();
+
+        let directory = std::env::temp_dir().join(format!("trazodone-emit-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for format in [Emit::LlvmIr, Emit::Bitcode] {
+            let output = directory.join("main").with_extension(format.extension());
+            emit(&module, format, optimizer.level, &output).unwrap();
+
+            let metadata = std::fs::metadata(&output).unwrap();
+            assert!(metadata.len() > 0, "`{}` is empty", output.display());
+        }
+
+        let ir = std::fs::read_to_string(directory.join("main.ll")).unwrap();
+        assert!(ir.contains("hvm__reduce"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -8,38 +8,49 @@ use ErrorKind::InvalidValue;
 use crate::cli::{Cli, EvalArgs};
 use crate::codegen::syntax::Transform;
 use crate::codegen::GlobalContext;
+use crate::diagnostic::{self, Diagnostic, ErrorCode, Source};
+use crate::hvm::reducer::Reducer;
 use crate::ir::rule::RuleGroup;
+use crate::llvm::optimize::Optimizer;
+use crate::passes::PassManager;
 
 pub fn run_eval(args: EvalArgs) {
     let mut cli = Cli::command();
 
-    let heap_size = args.heap_size;
-    let thread_ids = args.thread_ids;
     let debug = args.debug;
 
-    let code = args.file.unwrap_or_else(|| {
+    let code = args.file.clone().unwrap_or_else(|| {
         cli.error(InvalidValue, "No expression or file provided!")
             .exit();
     });
-    let code = std::fs::read_to_string(code).unwrap_or_else(|_| {
+    let file = code;
+    let code = std::fs::read_to_string(&file).unwrap_or_else(|_| {
         cli.error(InvalidValue, "Failed to read file.").exit();
     });
     let main = args.main.clone().unwrap_or("Main".into());
 
-    setup_eval_environment(&code, !args.use_eval);
-
-    let native_functions = Vec::new();
-    let (norm, cost, time) =
-        hvm::api::eval(&code, &main, native_functions, heap_size, thread_ids, debug)
-            .unwrap_or_else(|err| {
-                eprintln!("Failed to eval: {}", code);
-                if args.debug {
-                    panic!("{err}");
-                } else {
-                    cli.error(InvalidValue, "To get the backtrace, run with --debug or -d")
-                        .exit();
-                }
-            });
+    let passes = match &args.passes {
+        Some(names) => PassManager::new(names).unwrap_or_else(|err| {
+            cli.error(InvalidValue, err).exit();
+        }),
+        None => PassManager::default(),
+    };
+    let passes = passes.with_dump(args.dump_passes);
+
+    let optimizer = Optimizer::new(args.opt_level, args.llvm_passes.as_deref()).unwrap_or_else(|err| {
+        cli.error(InvalidValue, err).exit();
+    });
+    let optimizer = optimizer.with_dump(args.dump_llvm);
+
+    let source = Source::new(&file, &code);
+    let book = parse_book(&source);
+    let main = book.name_to_id.get(&main).copied().unwrap_or_else(|| {
+        cli.error(InvalidValue, format!("Could not find the function `{main}`."))
+            .exit();
+    });
+
+    let reducer = setup_eval_environment(&source, &book, &args, &passes, &optimizer).with_debug(debug);
+    let (norm, cost, time) = reducer.eval(main);
 
     println!("{norm}");
 
@@ -55,47 +66,102 @@ pub fn run_eval(args: EvalArgs) {
 
 pub(crate) fn setup_global_context(book: &RuleBook) -> Box<GlobalContext> {
     let mut id_to_name = book.id_to_name.clone();
-    id_to_name.remove(book.name_to_id.get("Main").unwrap());
+    if let Some(id) = book.name_to_id.get("Main") {
+        id_to_name.remove(id);
+    }
 
     let mut global: Box<GlobalContext> = Box::default();
     for (id, name) in itertools::sorted(id_to_name.iter()) {
         global.constructors.insert(name.clone(), *id);
+        if let Some(smap) = book.id_to_smap.get(id) {
+            global.arities.insert(name.clone(), smap.len() as u64);
+        }
     }
 
     global
 }
 
-pub(crate) fn ir_codegen_book(book: &RuleBook, global: Box<GlobalContext>) -> FxHashMap<String, RuleGroup> {
-    book.clone()
-        .transform()
-        .unwrap()
-        .iter()
-        .map(|group| {
-            let name = group.name.clone();
-            (name, group.clone().ir_codegen(global.clone()).unwrap())
-        })
-        .collect()
+/// Generates the IR of every rule group of the book, collecting the
+/// diagnostics of all the rule groups.
+pub(crate) fn ir_codegen_book(
+    book: &RuleBook,
+    global: Box<GlobalContext>,
+    passes: &PassManager,
+) -> Result<FxHashMap<String, RuleGroup>, Vec<Diagnostic>> {
+    let groups = book.clone().transform().map_err(|diagnostic| vec![diagnostic])?;
+
+    let mut diagnostics = Vec::new();
+    let mut rule_groups = FxHashMap::default();
+    for group in groups {
+        let name = group.name.clone();
+        match group.ir_codegen(global.clone(), passes) {
+            Ok(group) => {
+                rule_groups.insert(name, group);
+            }
+            Err(errors) => diagnostics.extend(errors),
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(rule_groups)
+    } else {
+        Err(diagnostics)
+    }
 }
 
-fn setup_eval_environment(code: &str, use_llvm: bool) {
+/// Reports the diagnostics in the standard error, and exits the program.
+pub(crate) fn report_diagnostics(diagnostics: &[Diagnostic], source: &Source) -> ! {
     let mut cli = Cli::command();
-    let file = match hvm::language::syntax::read_file(code) {
-        Ok(file) => file,
-        Err(err) => {
-            eprintln!("Failed to parse: {}", code);
-            eprintln!("{}", err);
-            cli.error(ErrorKind::InvalidValue, "Failed to parse file.")
-                .exit();
-        }
-    };
-    let book = hvm::language::rulebook::gen_rulebook(&file);
 
-    let global = setup_global_context(&book);
-    let groups = ir_codegen_book(&book, global);
+    eprintln!("{}", diagnostic::render_all(diagnostics, source));
+
+    let errors = diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).count();
+    cli.error(InvalidValue, format!("could not compile `{}` due to {errors} previous error(s)", source.name))
+        .exit();
+}
+
+/// Parses the source code, and generates the rule book, reporting a diagnostic
+/// if the source code can't be parsed.
+pub(crate) fn parse_book(source: &Source) -> RuleBook {
+    let file = hvm::language::syntax::read_file(source.code).unwrap_or_else(|err| {
+        let diagnostic = Diagnostic::error(ErrorCode::ParseError, "could not parse the file").with_note(err);
+
+        report_diagnostics(&[diagnostic], source);
+    });
+
+    hvm::language::rulebook::gen_rulebook(&file)
+}
+
+/// Generates the rule groups of the book, and creates the reducer of the program,
+/// that enters through the generated reduce function, when they're compiled with
+/// LLVM, or through the reducer of HVM, when they're evaluated.
+fn setup_eval_environment(
+    source: &Source,
+    book: &RuleBook,
+    args: &EvalArgs,
+    passes: &PassManager,
+    optimizer: &Optimizer,
+) -> Reducer {
+    let use_llvm = !args.use_eval;
+
+    let mut global = setup_global_context(book);
+    global.transmute = args.transmute;
+    global.trace = args.trace;
+    let groups = ir_codegen_book(book, global, passes).unwrap_or_else(|diagnostics| {
+        report_diagnostics(&diagnostics, source);
+    });
 
     if use_llvm {
-        crate::hvm::llvm::setup_llvm_precomp(book, groups).unwrap()
+        let reduce = crate::hvm::llvm::setup_llvm_reduce(book, groups, optimizer).unwrap_or_else(|err| {
+            report_diagnostics(&[Diagnostic::error(ErrorCode::Backend, err)], source);
+        });
+        let prog = crate::hvm::create_program(book);
+
+        Reducer::new(prog, args.heap_size, args.thread_ids).with_reduce(reduce)
     } else {
-        crate::hvm::setup_precomp(book, groups)
+        crate::hvm::setup_precomp(book, groups);
+        let prog = crate::hvm::create_program(book);
+
+        Reducer::new(prog, args.heap_size, args.thread_ids)
     }
 }
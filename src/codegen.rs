@@ -10,9 +10,11 @@
 //!     executing the rule, and returning the result of the rule. The stages are
 //!     the following:
 //!       Syntax [crate::codegen::syntax]
+//!         -> Linearity [crate::codegen::linearity]
 //!         -> IR Codegen [crate::codegen::apply]
 //!         -> Inline Declarations
 //!         -> Control Flow Graph [crate::codegen::apply::graph]
+//!         -> Optimization Passes [crate::passes]
 //!         -> LLVM IR
 //!         -> JIT/AOT // basically executing
 //!     TODO: LLVM IR
@@ -23,35 +25,74 @@
 //!     arguments of a function application.
 //!       Syntax [crate::codegen::syntax]
 //!         -> IR Codegen [crate::codegen::visit]
-//!         -> LLVM IR
+//!         -> LLVM IR [crate::llvm::visit]
 //!         -> JIT/AOT // basically executing
-//!     TODO: LLVM IR
 //!
 //!   Reduce: This stage is the most important stage in terms of performance. This generates
 //!     the `FAST APPLY` and `FAST REDUCE` in the original HVM code. This stage is responsible
-//!     for generating the state machine to applying and visiting the HVM terms.
-//!     TODO: Everything on reduce stage
+//!     for generating the state machine to applying and visiting the HVM terms,
+//!     that dispatches by the function id, directly to the compiled functions.
+//!       Compiled Rule Groups
+//!         -> IR Codegen [crate::codegen::reduce]
+//!         -> LLVM IR [crate::llvm::reduce]
+//!         -> JIT/AOT // both enter through the reduce function
 //!
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
 use fxhash::FxHashMap;
 
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::rule::RuleGroup;
+use crate::ir::verify::{Verify, VerifyError};
+use crate::passes::PassManager;
 
 pub mod apply;
+pub mod check;
+pub mod linearity;
 pub mod reduce;
 pub mod syntax;
 pub mod visit;
 
 impl crate::ir::syntax::RuleGroup {
-    pub fn ir_codegen(self, context: Box<GlobalContext>) -> apply::Result<RuleGroup> {
+    pub fn ir_codegen(self, context: Box<GlobalContext>, passes: &PassManager) -> apply::Result<RuleGroup> {
+        let name = self.name.clone();
+        let hvm_visit = visit::Codegen::default().build_visit(&self);
+        let mut hvm_apply = apply::Codegen::new(context).build_apply(&self)?;
+
+        // Verifies the IR of every stage, before it gets to the backends, the
+        // control flow graph is verified by the pass manager.
+        let errors = [hvm_apply.verify(), hvm_visit.verify()]
+            .into_iter()
+            .filter_map(|result| result.err())
+            .flatten()
+            .map(|error| invalid_ir(&name, error))
+            .collect::<Vec<_>>();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let hvm_apply_graph = passes
+            .run(&name, &mut hvm_apply)
+            .map_err(|errors| errors.into_iter().map(|error| invalid_ir(&name, error)).collect::<Vec<_>>())?;
+
         Ok(RuleGroup {
-            name: self.name.clone(),
-            hvm_visit: visit::Codegen::default().build_visit(&self),
-            hvm_apply: apply::Codegen::new(context).build_apply(&self)?,
+            name,
+            hvm_visit,
+            hvm_apply,
+            hvm_apply_graph,
         })
     }
 }
 
+fn invalid_ir(name: &str, error: VerifyError) -> Diagnostic {
+    Diagnostic::error(ErrorCode::InvalidIr, format!("invalid IR: {}", error.message))
+        .with_rule(name, None)
+        .with_note(format!("found in `{}`", error.location))
+}
+
 pub fn build_name(name: &str) -> String {
     // TODO: this can still cause some name collisions.
     // Note: avoiding the use of `$` because it is not an actually valid
@@ -67,6 +108,47 @@ pub struct GlobalContext {
     pub name_index: u64,
     /// The constructor name -> id, binding map.
     pub constructors: FxHashMap<String, u64>,
+    /// The constructor name -> arity, binding map, with the arities of the rule
+    /// book, that are the ones of the nodes in the runtime.
+    pub arities: FxHashMap<String, u64>,
+    /// The next duplication color. It's shared between every clone of the
+    /// global context, so the colors are unique across all the rule groups.
+    pub color_index: Arc<AtomicU64>,
+    /// The label -> color, binding map of the user-written duplications and
+    /// superpositions, shared like the [GlobalContext::color_index].
+    pub labels: Arc<Mutex<FxHashMap<String, u64>>>,
+    /// Reuses the matched nodes of the left-hand side in the allocations of the
+    /// right-hand side, see [apply::Codegen::alloc].
+    pub transmute: bool,
+    /// Prints the rule group and the rule, every time a rule is applied, in both
+    /// the eval and the LLVM backends.
+    pub trace: bool,
+}
+
+impl GlobalContext {
+    /// Creates a new fresh color, to be used by a duplication or a
+    /// superposition node.
+    pub fn fresh_color(&self) -> u64 {
+        self.color_index.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Gets the color of the label, the same one in every rule group, or a
+    /// fresh color for the nodes without label.
+    pub fn label_color(&self, label: Option<&str>) -> u64 {
+        let Some(label) = label else {
+            return self.fresh_color();
+        };
+
+        let mut labels = self.labels.lock().expect("the labels lock is poisoned");
+        match labels.get(label) {
+            Some(color) => *color,
+            None => {
+                let color = self.fresh_color();
+                labels.insert(label.into(), color);
+                color
+            }
+        }
+    }
 }
 
 impl Default for GlobalContext {
@@ -79,6 +161,11 @@ impl Default for GlobalContext {
             /// on the HVM github repository.
             name_index: 29, // hvm
             constructors: FxHashMap::default(),
+            arities: FxHashMap::default(),
+            color_index: Arc::new(AtomicU64::new(0)),
+            labels: Arc::new(Mutex::new(FxHashMap::default())),
+            transmute: false,
+            trace: false,
         }
     }
 }
@@ -1,6 +1,7 @@
 use fxhash::FxHashMap;
 
 use crate::codegen::GlobalContext;
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::apply::*;
 
 pub type Insertion = Block;
@@ -12,6 +13,8 @@ pub mod argument;
 pub mod binary;
 pub mod call;
 pub mod collect;
+pub mod decision;
+pub mod duplicate;
 pub mod pattern;
 pub mod free;
 pub mod graph;
@@ -21,8 +24,11 @@ pub mod term;
 pub mod variable;
 pub mod lam;
 pub mod atom;
+pub mod superpose;
 
-pub type Result<T> = std::result::Result<T, String>;
+/// The apply codegen doesn't stop in the first error, so it can report
+/// every [Diagnostic] of the rule group.
+pub type Result<T> = std::result::Result<T, Vec<Diagnostic>>;
 
 /// Apply codegen structure
 ///
@@ -51,6 +57,18 @@ pub struct Codegen {
     /// The instructions that are generated by the codegen.
     instructions: Block,
 
+    /// The matched nodes of the current rule, that are freed in the end of
+    /// it, if they aren't reused by [Codegen::alloc].
+    free: Vec<(Term, FreeArity)>,
+
+    /// The rule group name, and the index of the rule that is being
+    /// generated, used to report diagnostics.
+    group_name: String,
+    equation: usize,
+
+    /// The diagnostics that are reported while generating the code.
+    diagnostics: Vec<Diagnostic>,
+
     //>>>Constants section
     /// The extensions that are used in this codegen, to be displayed in the
     /// generated code/pretty print for debugging purposes.
@@ -71,6 +89,10 @@ impl Codegen {
             lambdas: FxHashMap::default(),
             variables: Vec::new(),
             instructions: Block::default(),
+            free: Vec::new(),
+            group_name: String::new(),
+            equation: 0,
+            diagnostics: Vec::new(),
             // constant sections
             constant_tags: FxHashMap::default(),
             constant_extensions: FxHashMap::default(),
@@ -98,13 +120,25 @@ impl Codegen {
         Term::ext(id, name)
     }
 
+    /// Allocates a new node of the given size. With the transmutation enabled, it
+    /// reuses a matched node of the same size from the left-hand side, instead of
+    /// freeing it in the end of the rule.
+    ///
+    /// This avoids calls to alloc(), but it seems to decrease HVM's performance in
+    /// some cases, probably because of added cache misses, so it's optional.
     pub fn alloc(&mut self, size: u64) -> Term {
-        // TODO:
-        // This will avoid calls to alloc() by reusing nodes from the left-hand side. Sadly, this seems
-        // to decrease HVM's performance in some cases, probably because of added cache misses. Perhaps
-        // this should be turned off. I'll decide later.
+        match self.reusable_node(size) {
+            Some(index) => self.free.remove(index).0,
+            None => Term::alloc(size),
+        }
+    }
+
+    fn reusable_node(&self, size: u64) -> Option<usize> {
+        if !self.global.transmute || size == 0 {
+            return None;
+        }
 
-        Term::alloc(size)
+        self.free.iter().position(|(_, arity)| *arity == size)
     }
 
     /// Creates a new [Term::Agent] within a builder function [F], and
@@ -119,6 +153,22 @@ impl Codegen {
         })
     }
 
+    /// Binds the [Term::Agent] to the name. If its node can be reused from the
+    /// left-hand side, the arguments are linked one by one into the reused node.
+    pub fn bind_agent(&mut self, name: &str, agent: Term) {
+        match agent {
+            Term::Agent(Agent { arity, arguments }) if self.reusable_node(arity).is_some() => {
+                let node = self.alloc(arity);
+                self.instr(Instruction::binding(name, node));
+
+                for (index, argument) in arguments.into_iter().enumerate() {
+                    self.instr(Instruction::link(Position::new(name, index as u64), argument));
+                }
+            }
+            agent => self.instr(Instruction::binding(name, agent)),
+        }
+    }
+
     pub fn build_link(&mut self, done: Term) {
         self.instructions
             .push(Instruction::link(Position::Host, done));
@@ -129,16 +179,30 @@ impl Codegen {
         self.instructions.push(instruction);
     }
 
+    /// Reports a new [Diagnostic] in the current rule.
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        let diagnostic = diagnostic.with_rule(&self.group_name, Some(self.equation));
+
+        self.diagnostics.push(diagnostic);
+    }
+
     /// Gets the [NameId] based on the name of the constructor, using
     /// the [GlobalContext] to get the index.
+    ///
+    /// If the constructor doesn't exist, reports a diagnostic, and
+    /// returns the id `0`, so the codegen can continue.
     pub fn get_name_id(&mut self, name: &str) -> NameId {
-        let index = self
-            .global
-            .constructors
-            .get(name)
-            .unwrap_or_else(|| panic!("no constructor for {}", name));
+        match self.global.constructors.get(name) {
+            Some(index) => *index,
+            None => {
+                self.report(
+                    Diagnostic::error(ErrorCode::UnknownConstructor, format!("unknown constructor `{name}`"))
+                        .with_label(name, "not declared in the rule book"),
+                );
 
-        *index
+                0
+            }
+        }
     }
 
     /// Creates a new fresh name
@@ -148,17 +212,93 @@ impl Codegen {
         name
     }
 
-    fn new_block(&self, instruction: Instruction) -> Self {
+    /// Builds a nested [Block] within the builder function [F], the nested
+    /// block shares the variables and the arguments of the current one.
+    pub fn build_block<F: FnOnce(&mut Codegen)>(&mut self, f: F) -> Block {
+        let mut codegen = self.new_block(Block::default());
+        f(&mut codegen);
+        self.merge_block(codegen)
+    }
+
+    fn new_block(&self, instructions: Block) -> Self {
         Self {
             global: self.global.clone(),
             arguments: self.arguments.clone(),
             name_index: self.name_index,
             variables: self.variables.clone(),
             lambdas: self.lambdas.clone(),
-            instructions: Block::with(instruction),
+            instructions,
+            free: self.free.clone(),
+            group_name: self.group_name.clone(),
+            equation: self.equation,
+            diagnostics: Vec::new(),
             // constant clonning
             constant_extensions: self.constant_extensions.clone(),
             constant_tags: self.constant_tags.clone(),
         }
     }
+
+    /// Merges the state of a nested [Codegen] into the current one, to keep
+    /// the names unique, and returns its instructions. The matched nodes reused
+    /// by the nested block aren't freed, nor reused again.
+    fn merge_block(&mut self, codegen: Codegen) -> Block {
+        self.name_index = codegen.name_index;
+        self.free = codegen.free;
+        self.diagnostics.extend(codegen.diagnostics);
+        self.constant_tags.extend(codegen.constant_tags);
+        self.constant_extensions.extend(codegen.constant_extensions);
+
+        codegen.instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::cli::eval::setup_global_context;
+    use crate::ir::syntax::RuleGroup;
+
+    /// Collects the reused nodes, bound from a matched position, and the freed
+    /// positions of the block.
+    fn reused_and_freed(instructions: &[Instruction], reused: &mut Vec<String>, freed: &mut Vec<String>) {
+        for instruction in instructions {
+            match instruction {
+                Instruction::Let(Let {
+                    value: value @ Term::GetPosition(..),
+                    ..
+                }) => reused.push(format!("{value:?}")),
+                Instruction::Free(free) => freed.push(format!("{:?}", free.position)),
+                Instruction::Metadata(metadata) => reused_and_freed(&metadata.instructions, reused, freed),
+                Instruction::If(if_instruction) => {
+                    reused_and_freed(&if_instruction.then.block, reused, freed);
+                    if let Some(otherwise) = &if_instruction.otherwise {
+                        reused_and_freed(&otherwise.block, reused, freed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn it_reuses_the_nodes_in_the_nested_terms() {
+        let file = hvm::language::syntax::read_file("(Foo (Pair a b)) = (Succ (Pair b a))").unwrap();
+        let book = hvm::language::rulebook::gen_rulebook(&file);
+        let group = RuleGroup::specialize("Foo".into(), &book).unwrap();
+
+        let mut global = setup_global_context(&book);
+        global.transmute = true;
+        let block = Codegen::new(global).build_apply(&group).unwrap();
+
+        let mut reused = Vec::new();
+        let mut freed = Vec::new();
+        reused_and_freed(&block.block, &mut reused, &mut freed);
+
+        // The `Pair` node is reused by the nested term, and the `Foo` node by `Succ`
+        assert_eq!(reused.len(), 2);
+        assert!(reused.iter().all_unique());
+        assert!(reused.iter().all(|node| !freed.contains(node)), "{reused:?} are freed in {freed:?}");
+    }
 }
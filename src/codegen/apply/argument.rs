@@ -1,19 +1,32 @@
 use std::ops::Deref;
 
 use crate::codegen::apply::Codegen;
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::apply::Term;
 
 /// An argument to a function call. It can either be a value, or a constructor.
 ///
-/// If it is a constructor, then it can hold multiple arguments.
+/// If it is a constructor, then it can hold multiple arguments, that can be
+/// constructors too, in case of nested patterns.
 #[derive(Debug, Clone)]
-pub struct Argument(pub Term, pub Vec<Term>);
+pub struct Argument(pub Term, pub Vec<Argument>);
 
 impl Codegen {
+    /// Gets the argument of the function. If the rule has more parameters than
+    /// the function's arity, reports a diagnostic, and creates erased arguments,
+    /// so the codegen can continue.
     pub fn get_argument(&mut self, i: usize) -> &mut Argument {
-        self.arguments
-            .get_mut(i)
-            .unwrap_or_else(|| panic!("Argument {} not found", i))
+        if i >= self.arguments.len() {
+            let arity = self.arguments.len();
+            self.report(Diagnostic::error(
+                ErrorCode::InvalidRule,
+                format!("the rule has the parameter {i}, but the function has arity {arity}"),
+            ));
+
+            self.arguments.resize(i + 1, Argument::new(Term::erased()));
+        }
+
+        &mut self.arguments[i]
     }
 }
 
@@ -22,19 +35,21 @@ impl Argument {
         Self(term, vec![])
     }
 
-    pub fn get_field(&self, i: usize) -> Term {
-        self.1
-            .get(i)
-            .unwrap_or_else(|| panic!("Field {} not found", i))
-            .clone()
+    /// Gets the nested field, following the given path of field indexes, if
+    /// every field in the path was bound.
+    pub fn get_path(&self, path: &[u64]) -> Option<Term> {
+        match path {
+            [] => Some(self.unbox()),
+            [i, rest @ ..] => self.1.get(*i as usize)?.get_path(rest),
+        }
     }
 
     pub fn add_field(&mut self, term: Term) {
-        self.1.push(term);
+        self.1.push(Argument::new(term));
     }
 
     pub fn set_field(&mut self, index: usize, term: Term) {
-        self.1.insert(index, term);
+        self.1.insert(index, Argument::new(term));
     }
 
     pub fn unbox(&self) -> Term {
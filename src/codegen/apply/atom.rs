@@ -1,21 +1,18 @@
 use crate::codegen::apply::Codegen;
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::apply::Term;
 use crate::ir::syntax::Atom as IRAtom;
 
 impl Codegen {
     pub fn build_atom(&mut self, expr: IRAtom) -> Term {
-        let IRAtom {
-            name,
-            index,
-            field_index,
-        } = expr;
+        let Some((_, term)) = self.variables.get(expr.index as usize) else {
+            self.report(
+                Diagnostic::error(ErrorCode::UnboundVariable, format!("unbound variable `{}`", expr.name))
+                    .with_label(&expr.name, "not found in this scope"),
+            );
 
-        let (_, term) = self.variables.get(index as usize).unwrap_or_else(|| {
-            panic!(
-                "Variable not found: {:?} (index: {}, field_index: {:?})",
-                &name, index, field_index
-            )
-        });
+            return Term::NotFound(expr);
+        };
 
         term.clone()
     }
@@ -1,8 +1,14 @@
 use crate::codegen::apply::Codegen;
-use crate::ir::apply::{Instruction, Position, Term};
+use crate::ir::apply::{Block, Free, Instruction, Position, Tag, Term};
 use crate::ir::syntax::Binary as IRBinary;
 
 impl Codegen {
+    /// Builds a binary operation. When both operands are numbers of the same kind,
+    /// in runtime, it's computed inline with a [Term::Operate], otherwise an `Op2`
+    /// node is allocated, only in the fallback branch, to be reduced later.
+    ///
+    /// The result is declared before the check, and assigned by both branches,
+    /// so the SSA construction merges them with a phi node.
     pub fn build_binary(&mut self, expr: IRBinary) -> Term {
         let IRBinary {
             op,
@@ -10,19 +16,60 @@ impl Codegen {
             box rhs,
         } = expr;
 
-        let binary = self.fresh_name("binary");
-
         let lhs = self.build_term(lhs);
         let rhs = self.build_term(rhs);
 
-        // TODO: Optimization: do inline operation, avoiding Op2 allocation, when operands are already number
-        let binary_alloc = self.make_agent(|builder| {
-            builder.push(lhs.clone());
-            builder.push(rhs.clone());
+        // The operands are used by both branches, so they're evaluated once
+        let lhs = self.bind_operand(lhs);
+        let rhs = self.bind_operand(rhs);
+
+        let u60 = self.tag(Tag::U60);
+        let f60 = self.tag(Tag::F60);
+        let both = |tag: &Term| {
+            Term::logical_and(
+                Term::equal(lhs.get_tag(), tag.clone()),
+                Term::equal(rhs.get_tag(), tag.clone()),
+            )
+        };
+        let numbers = Term::logical_or(both(&u60), both(&f60));
+
+        let result = self.fresh_name("binary");
+        self.instr(Instruction::binding(&result, Term::erased()));
+
+        // The matched node, that the fallback branch reuses, isn't freed by the
+        // end of the rule anymore, so the inline branch frees it.
+        let reused = self.reusable_node(2).map(|index| self.free[index].clone());
+        let operate = Term::operate(lhs.clone(), op, rhs.clone());
+        let mut then = vec![Instruction::binding(&result, operate)];
+        if let Some((position, arity)) = reused {
+            then.push(Instruction::Free(Free { position, arity }));
+        }
+
+        let otherwise = self.build_block(|codegen| {
+            let binary = codegen.fresh_name("binary");
+            let binary_alloc = codegen.make_agent(|builder| {
+                builder.push(lhs.clone());
+                builder.push(rhs.clone());
+            });
+            codegen.bind_agent(&binary, binary_alloc);
+            codegen.instr(Instruction::binding(
+                &result,
+                Term::create_binary(lhs, op, rhs, Position::initial(&binary)),
+            ));
         });
+        self.instr(Instruction::cond(numbers, Block::new(then), Some(otherwise)));
 
-        self.instr(Instruction::binding(&binary, binary_alloc));
+        Term::reference(&result)
+    }
 
-        Term::create_binary(lhs, op, rhs, Position::initial(&binary))
+    fn bind_operand(&mut self, operand: Term) -> Term {
+        match operand {
+            Term::Ref(..) => operand,
+            operand => {
+                let name = self.fresh_name("operand");
+                self.instr(Instruction::binding(&name, operand));
+                Term::reference(&name)
+            }
+        }
     }
 }
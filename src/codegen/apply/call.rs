@@ -1,5 +1,5 @@
 use crate::codegen::apply::Codegen;
-use crate::ir::apply::{FunctionId, Instruction, Position, Term};
+use crate::ir::apply::{FunctionId, Position, Term};
 use crate::ir::syntax;
 
 impl Codegen {
@@ -12,7 +12,7 @@ impl Codegen {
             arguments.push(callee);
             arguments.push(argument);
         });
-        self.instr(Instruction::binding(&name, done));
+        self.bind_agent(&name, done);
 
         Term::create_app(Position::initial(&name))
     }
@@ -29,7 +29,7 @@ impl Codegen {
             builder.extend(arguments);
         });
 
-        self.instr(Instruction::binding(&name, value));
+        self.bind_agent(&name, value);
 
         Term::create_constructor(FunctionId::new(&global_name, id), Position::initial(&name))
     }
@@ -46,7 +46,7 @@ impl Codegen {
             builder.extend(arguments);
         });
 
-        self.instr(Instruction::binding(&name, value));
+        self.bind_agent(&name, value);
 
         Term::create_function(FunctionId::new(&global_name, id), Position::initial(&name))
     }
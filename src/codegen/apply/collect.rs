@@ -1,11 +1,12 @@
 use crate::codegen::apply::Codegen;
 use crate::ir::apply::{Instruction, Variable};
-use crate::ir::syntax::Rule;
+use crate::ir::syntax::{Pattern, Rule};
 
 impl Codegen {
+    /// Creates the variables bound by the rule's left-hand side, in the same
+    /// order that they are declared in [crate::codegen::syntax].
     pub fn create_collect(&mut self, rule: &Rule) -> Vec<Variable> {
         use crate::ir::syntax::Parameter::*;
-        use crate::ir::syntax::Pattern;
 
         rule.parameters
             .iter()
@@ -14,35 +15,60 @@ impl Codegen {
                 Erased => vec![Variable {
                     erased: true,
                     index: index as u64,
-                    field_index: None,
+                    fields: vec![],
                 }],
                 Atom(..) => vec![Variable {
                     erased: false,
                     index: index as u64,
-                    field_index: None,
+                    fields: vec![],
                 }],
-                Constructor(constructor) => constructor
-                    .flatten_patterns
-                    .iter()
-                    .enumerate()
-                    .map(|(field_index, pattern)| Variable {
-                        erased: matches!(pattern, Pattern::Erased),
-                        index: index as u64,
-                        field_index: Some(field_index as u64),
-                    })
-                    .collect::<Vec<_>>(),
+                Constructor(constructor) => {
+                    create_pattern_collect(index as u64, vec![], &constructor.flatten_patterns)
+                }
                 _ => vec![],
             })
             .collect::<Vec<Variable>>()
     }
 
+    /// Collects the erased variables, the terms are taken from the arguments,
+    /// so it must be called after [Codegen::build_constructor_patterns].
     pub fn build_collect(&mut self, collect: Vec<Variable>) {
-        for term in collect {
-            if term.erased {
-                let argument = term.as_term();
+        for variable in collect {
+            if variable.erased {
+                let (_, argument) = self.variable_as_tuple(&variable);
 
                 self.instructions.push(Instruction::collect(argument));
             }
         }
     }
 }
+
+/// Creates the variables of the flatten patterns, walking recursively
+/// through the nested constructor patterns.
+fn create_pattern_collect(index: u64, fields: Vec<u64>, patterns: &[Pattern]) -> Vec<Variable> {
+    patterns
+        .iter()
+        .enumerate()
+        .flat_map(|(field_index, pattern)| {
+            let mut fields = fields.clone();
+            fields.push(field_index as u64);
+
+            match pattern {
+                Pattern::Erased => vec![Variable {
+                    erased: true,
+                    index,
+                    fields,
+                }],
+                Pattern::Atom(..) => vec![Variable {
+                    erased: false,
+                    index,
+                    fields,
+                }],
+                Pattern::Constructor(constructor) => {
+                    create_pattern_collect(index, fields, &constructor.flatten_patterns)
+                }
+                Pattern::U60(..) | Pattern::F60(..) => vec![],
+            }
+        })
+        .collect()
+}
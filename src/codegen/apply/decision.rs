@@ -0,0 +1,360 @@
+//! Decision tree compilation of the rule groups.
+//!
+//! Instead of testing every rule in turn, the rules are compiled into a tree
+//! that switches once per argument, first on its tag, and then on the constructor
+//! extension, or on the number. The checks are shared across the rules, and each
+//! leaf of the tree holds the rules that can still be matched, in their order.
+//!
+//! Only the top-level parameters are switched on, the nested patterns are checked
+//! in the leaves, by [Codegen::build_fields_match].
+
+use crate::codegen::apply::Codegen;
+use crate::ir::apply::{Instruction, Tag, Term};
+use crate::ir::syntax::{Parameter, RuleGroup};
+
+/// A node of the decision tree.
+#[derive(Debug, Clone)]
+pub enum Decision {
+    /// Tries the rules in the given order, checking their nested patterns. If
+    /// there are no rules, the match fails.
+    Leaf(Vec<usize>),
+
+    /// Switches on the tag of an argument.
+    Switch(Switch),
+}
+
+#[derive(Debug, Clone)]
+pub struct Switch {
+    /// The index of the argument.
+    pub argument: usize,
+
+    /// The constructors that are matched by name, in their declaration order.
+    pub constructors: Vec<(String, Decision)>,
+    pub u60: Vec<(u64, Decision)>,
+    pub f60: Vec<(f64, Decision)>,
+
+    /// Matches the constructors and the numbers that aren't in the cases, with
+    /// the wildcard rows. It's shared by every case, as they fall through to it.
+    pub default: Box<Decision>,
+
+    /// If the argument is strict, the other tags, like lambdas and superpositions,
+    /// don't match the default, otherwise, they're matched by the wildcard rows too.
+    pub strict: bool,
+}
+
+impl Decision {
+    /// Compiles the rule group into a decision tree.
+    pub fn compile(group: &RuleGroup) -> Self {
+        let rows = (0..group.rules.len()).collect();
+        let columns = (0..group.strict_parameters.len()).collect();
+
+        compile_rows(group, rows, columns)
+    }
+
+    /// If the node can't match any rule.
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, Decision::Leaf(rows) if rows.is_empty())
+    }
+}
+
+fn compile_rows(group: &RuleGroup, rows: Vec<usize>, mut columns: Vec<usize>) -> Decision {
+    let parameter = |row: usize, column: usize| group.rules[row].parameters.get(column);
+    let is_wildcard = |row: usize, column: usize| {
+        matches!(
+            parameter(row, column),
+            None | Some(Parameter::Atom(..)) | Some(Parameter::Erased)
+        )
+    };
+
+    // Selects the leftmost argument that must be tested
+    let selected = columns.iter().position(|column| {
+        group.strict_parameters[*column] || rows.iter().any(|row| !is_wildcard(*row, *column))
+    });
+    let Some(selected) = selected.filter(|_| !rows.is_empty()) else {
+        return Decision::Leaf(rows);
+    };
+    let column = columns.remove(selected);
+
+    let wildcards = rows
+        .iter()
+        .copied()
+        .filter(|row| is_wildcard(*row, column))
+        .collect::<Vec<_>>();
+
+    // Specializes the rows, keeping the rows that match the given parameter,
+    // and the wildcard ones.
+    let specialize = |f: &dyn Fn(&Parameter) -> bool| {
+        let rows = rows
+            .iter()
+            .copied()
+            .filter(|row| is_wildcard(*row, column) || parameter(*row, column).map(f).unwrap_or(false))
+            .collect();
+
+        compile_rows(group, rows, columns.clone())
+    };
+
+    let mut constructors = Vec::<(String, Decision)>::new();
+    let mut u60 = Vec::<(u64, Decision)>::new();
+    let mut f60 = Vec::<(f64, Decision)>::new();
+    for row in &rows {
+        match parameter(*row, column) {
+            Some(Parameter::Constructor(constructor))
+                if !constructors.iter().any(|(name, _)| *name == constructor.name) =>
+            {
+                let decision = specialize(&|parameter| {
+                    matches!(parameter, Parameter::Constructor(other) if other.name == constructor.name)
+                });
+                constructors.push((constructor.name.clone(), decision));
+            }
+            Some(Parameter::U60(value)) if !u60.iter().any(|(other, _)| other == value) => {
+                let decision = specialize(&|parameter| matches!(parameter, Parameter::U60(other) if other == value));
+                u60.push((*value, decision));
+            }
+            Some(Parameter::F60(value)) if !f60.iter().any(|(other, _)| other.to_bits() == value.to_bits()) => {
+                let decision = specialize(&|parameter| {
+                    matches!(parameter, Parameter::F60(other) if other.to_bits() == value.to_bits())
+                });
+                f60.push((*value, decision));
+            }
+            _ => {}
+        }
+    }
+
+    Decision::Switch(Switch {
+        argument: column,
+        constructors,
+        u60,
+        f60,
+        default: compile_rows(group, wildcards, columns).into(),
+        strict: group.strict_parameters[column],
+    })
+}
+
+impl Codegen {
+    /// Builds the decision tree, every branch of the tree ends with a
+    /// [Instruction::Return], so the branches don't need an else block.
+    pub fn build_decision(&mut self, group: &RuleGroup, decision: Decision) {
+        match decision {
+            Decision::Leaf(rows) => self.build_leaf(group, rows),
+            Decision::Switch(switch) => self.build_switch(group, switch),
+        }
+    }
+
+    /// Builds the cases of the switch, each one of them returns if it matches, so
+    /// the unmatched ones fall through to the default, that's only built once.
+    fn build_switch(&mut self, group: &RuleGroup, switch: Switch) {
+        let argument = self.get_argument(switch.argument).unbox();
+
+        let tag = self.fresh_name("tag");
+        self.instr(Instruction::binding(&tag, argument.get_tag()));
+        let tag = Term::reference(&tag);
+
+        if !switch.constructors.is_empty() {
+            let then = self.build_block(|this| {
+                let ext = this.fresh_name("ext");
+                this.instr(Instruction::binding(&ext, argument.get_ext()));
+
+                for (name, decision) in switch.constructors {
+                    let id = this.get_name_id(&name);
+                    let condition = Term::equal(Term::reference(&ext), this.ext(id, &name));
+                    let then = this.build_block(|this| this.build_decision(group, decision));
+
+                    this.instr(Instruction::cond(condition, then, None));
+                }
+            });
+
+            let condition = Term::equal(tag.clone(), self.tag(Tag::CONSTRUCTOR));
+            self.instr(Instruction::cond(condition, then, None));
+        }
+
+        // The numbers are compared with the whole pointer, because [Term::create_u60]
+        // and [Term::create_f60] already carries the tag bits.
+        let numbers = [
+            (
+                Tag::U60,
+                switch
+                    .u60
+                    .into_iter()
+                    .map(|(value, decision)| (Term::create_u60(value), decision))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                Tag::F60,
+                switch
+                    .f60
+                    .into_iter()
+                    .map(|(value, decision)| (Term::create_f60(value), decision))
+                    .collect::<Vec<_>>(),
+            ),
+        ];
+
+        for (number_tag, cases) in numbers {
+            if cases.is_empty() {
+                continue;
+            }
+
+            let then = self.build_block(|this| {
+                for (value, decision) in cases {
+                    let condition = Term::equal(argument.clone(), value);
+                    let then = this.build_block(|this| this.build_decision(group, decision));
+
+                    this.instr(Instruction::cond(condition, then, None));
+                }
+            });
+
+            let condition = Term::equal(tag.clone(), self.tag(number_tag));
+            self.instr(Instruction::cond(condition, then, None));
+        }
+
+        if !switch.strict || switch.default.is_unreachable() {
+            self.build_decision(group, *switch.default);
+            return;
+        }
+
+        // The other tags of the strict arguments don't match any rule
+        let condition = [Tag::CONSTRUCTOR, Tag::U60, Tag::F60]
+            .into_iter()
+            .map(|tag_id| Term::equal(tag.clone(), self.tag(tag_id)))
+            .reduce(Term::logical_or)
+            .expect("the tags aren't empty");
+        let then = self.build_block(|this| this.build_decision(group, *switch.default));
+        self.instr(Instruction::cond(condition, then, None));
+        self.instr(Instruction::Return(Term::False));
+    }
+
+    /// Tries the rules in order, the first one without nested patterns
+    /// always matches, so the next ones are not built.
+    fn build_leaf(&mut self, group: &RuleGroup, rows: Vec<usize>) {
+        for row in rows {
+            let rule = &group.rules[row];
+
+            let mut condition = Term::True;
+            for (index, parameter) in rule.parameters.iter().enumerate() {
+                if let Parameter::Constructor(constructor) = parameter {
+                    let argument = self.get_argument(index).unbox();
+                    let check = self.build_fields_match(argument, constructor);
+                    condition = Term::logical_and(condition, check).simplify().clone();
+                }
+            }
+
+            if condition.is_true() {
+                self.build_rule(group, row);
+                return;
+            }
+
+            let then = self.build_block(|this| this.build_rule(group, row));
+            self.instr(Instruction::cond(condition, then, None));
+        }
+
+        self.instr(Instruction::Return(Term::False));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::eval::setup_global_context;
+    use crate::ir::apply::Block;
+
+    fn setup_group(code: &str) -> (hvm::rulebook::RuleBook, RuleGroup) {
+        let file = hvm::language::syntax::read_file(code).unwrap();
+        let book = hvm::language::rulebook::gen_rulebook(&file);
+        let group = RuleGroup::specialize("Foo".into(), &book).unwrap();
+
+        (book, group)
+    }
+
+    /// Counts the rule bodies in the block, each one of them increments the cost.
+    fn count_rules(instructions: &[Instruction]) -> usize {
+        instructions
+            .iter()
+            .map(|instruction| match instruction {
+                Instruction::IncrementCost => 1,
+                Instruction::If(if_instruction) => {
+                    count_rules(&if_instruction.then.block)
+                        + if_instruction
+                            .otherwise
+                            .as_ref()
+                            .map(|otherwise| count_rules(&otherwise.block))
+                            .unwrap_or_default()
+                }
+                Instruction::Metadata(metadata) => count_rules(&metadata.instructions),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    const OVERLAPPING: &str = r#"
+        (Foo (A) (B)) = 1
+        (Foo x (B))   = 2
+        (Foo (A) y)   = 3
+        (Foo x y)     = 4
+    "#;
+
+    #[test]
+    fn it_shares_the_default_subtree() {
+        let (_, group) = setup_group(OVERLAPPING);
+
+        let Decision::Switch(switch) = Decision::compile(&group) else {
+            panic!("expected a switch on the first argument");
+        };
+        assert_eq!(switch.argument, 0);
+        assert!(switch.strict);
+        assert_eq!(switch.constructors.len(), 1);
+        assert!(switch.u60.is_empty() && switch.f60.is_empty());
+
+        // The `(A)` case keeps the wildcard rows, in their order
+        let (name, Decision::Switch(case)) = &switch.constructors[0] else {
+            panic!("expected a switch on the second argument");
+        };
+        assert_eq!(name, "A");
+        assert_eq!(case.argument, 1);
+        assert!(matches!(&case.constructors[..], [(name, Decision::Leaf(rows))] if name == "B" && rows == &[0, 1, 2, 3]));
+        assert!(matches!(&*case.default, Decision::Leaf(rows) if rows == &[2, 3]));
+
+        // The default only has the wildcard rows of the first argument
+        let Decision::Switch(default) = &*switch.default else {
+            panic!("expected a switch on the second argument");
+        };
+        assert!(matches!(&default.constructors[..], [(name, Decision::Leaf(rows))] if name == "B" && rows == &[1, 3]));
+        assert!(matches!(&*default.default, Decision::Leaf(rows) if rows == &[3]));
+    }
+
+    #[test]
+    fn it_builds_each_default_once() {
+        let (book, group) = setup_group(OVERLAPPING);
+
+        let mut codegen = Codegen::new(setup_global_context(&book));
+        let block: Block = codegen.build_apply(&group).unwrap();
+
+        // One rule body per leaf: `(A) (B)`, `(A) _`, `_ (B)` and `_ _`
+        assert_eq!(count_rules(&block.block), 4);
+    }
+
+    #[test]
+    fn it_keeps_the_rule_priority() {
+        fn leaves(decision: &Decision, rows: &mut Vec<Vec<usize>>) {
+            match decision {
+                Decision::Leaf(leaf) => rows.push(leaf.clone()),
+                Decision::Switch(switch) => {
+                    for (_, decision) in &switch.constructors {
+                        leaves(decision, rows);
+                    }
+                    leaves(&switch.default, rows);
+                }
+            }
+        }
+
+        let (_, group) = setup_group(
+            r#"
+            (Foo x y)     = 1
+            (Foo (A) (B)) = 2
+            "#,
+        );
+
+        // The first rule matches everything, so it's the first row of every leaf
+        let mut rows = Vec::new();
+        leaves(&Decision::compile(&group), &mut rows);
+        assert!(rows.iter().all(|leaf| leaf.first() == Some(&0)));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::codegen::apply::Codegen;
+use crate::ir::apply::{Instruction, Position, Term};
+use crate::ir::syntax::Duplicate as IRDuplicate;
+
+impl Codegen {
+    /// Builds a duplication node, that holds the duplicated value in the
+    /// third gate, and binds `from` and `to` to the [Term::create_dp0] and
+    /// [Term::create_dp1] of the node, with the color of its label.
+    pub fn build_duplicate(&mut self, expr: IRDuplicate) -> Term {
+        let IRDuplicate {
+            label,
+            from,
+            to,
+            box value,
+            box body,
+        } = expr;
+
+        let value = self.build_term(value);
+
+        let name = self.fresh_name("dup");
+        let color = self.global.label_color(label.as_deref());
+        let dup_alloc = self.alloc(3);
+        self.instr(Instruction::binding(&name, dup_alloc));
+
+        if from == "*" {
+            self.instr(Instruction::link(Position::initial(&name), Term::erased()));
+        }
+        if to == "*" {
+            self.instr(Instruction::link(Position::new(&name, 1), Term::erased()));
+        }
+        self.instr(Instruction::link(Position::new(&name, 2), value));
+
+        let dp0 = Term::create_dp0(color, Position::initial(&name));
+        let dp1 = Term::create_dp1(color, Position::initial(&name));
+
+        self.variables.push((from, dp0)); // Push to the variable stack
+        self.variables.push((to, dp1));
+        let body = self.build_term(body);
+        self.variables.pop();
+        self.variables.pop();
+
+        body
+    }
+}
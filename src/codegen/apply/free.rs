@@ -1,8 +1,9 @@
-use crate::codegen::apply::{Codegen, FreeVec};
+use crate::codegen::apply::argument::Argument;
+use crate::codegen::apply::{Codegen, FreeArity, FreeVec};
 use crate::ir::apply::Term;
 use crate::ir::apply::{Free, Instruction};
 use crate::ir::syntax;
-use crate::ir::syntax::Rule;
+use crate::ir::syntax::{Pattern, Rule};
 
 impl Codegen {
     pub fn create_free(&mut self, rule: &Rule) -> FreeVec {
@@ -20,23 +21,31 @@ impl Codegen {
             .collect::<Vec<_>>()
     }
 
-    pub fn build_free(&mut self, rule: &Rule, group: &syntax::RuleGroup) {
-        let mut free = self
-            .create_free(rule)
-            .iter()
-            .map(|(index, arity)| {
-                let argument = self.get_argument(*index as usize).unbox();
+    /// Creates the positions of the matched nodes, and the node of the function
+    /// itself, that are freed in the end of the rule.
+    pub fn create_free_positions(&mut self, rule: &Rule, group: &syntax::RuleGroup) -> Vec<(Term, FreeArity)> {
+        let mut free = Vec::new();
+        for (index, arity) in self.create_free(rule) {
+            let argument = self.get_argument(index as usize).clone();
 
-                (Term::get_position(argument, 0), *arity)
-            })
-            .collect::<Vec<_>>();
+            free.push((Term::get_position(argument.unbox(), 0), arity));
+
+            if let syntax::Parameter::Constructor(constructor) = &rule.parameters[index as usize] {
+                free.extend(create_nested_free(&argument, constructor));
+            }
+        }
 
         free.push((
             Term::get_position(Term::Current, 0),
             group.strict_parameters.len() as u64,
         ));
 
-        for must_free in free {
+        free
+    }
+
+    /// Frees the matched nodes of the rule, that weren't reused by [Codegen::alloc].
+    pub fn build_free(&mut self) {
+        for must_free in std::mem::take(&mut self.free) {
             self.instructions.push(Instruction::Free(Free {
                 position: must_free.0,
                 arity: must_free.1,
@@ -44,3 +53,22 @@ impl Codegen {
         }
     }
 }
+
+/// Creates the free positions of the nested constructor patterns, that are
+/// bound by [Codegen::build_constructor_patterns] in the fields of the [Argument].
+fn create_nested_free(argument: &Argument, constructor: &syntax::Constructor) -> Vec<(Term, FreeArity)> {
+    constructor
+        .flatten_patterns
+        .iter()
+        .enumerate()
+        .flat_map(|(index, pattern)| match pattern {
+            Pattern::Constructor(nested) => {
+                let field = &argument.1[index];
+                let mut free = vec![(Term::get_position(field.unbox(), 0), nested.arity)];
+                free.extend(create_nested_free(field, nested));
+                free
+            }
+            _ => vec![],
+        })
+        .collect()
+}
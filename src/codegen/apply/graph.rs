@@ -1,5 +1,3 @@
-use itertools::Itertools;
-
 use crate::ir::apply::{Block, Instruction, Term};
 use crate::ir::graph::{BasicBlock, HasTerm, Label, Terminator};
 
@@ -33,11 +31,15 @@ impl Block {
     /// entry:
     ///   cond a @bb_1 @bb_2
     /// ```
+    ///
+    /// The instructions after an `if` are moved into a join block, that is declared
+    /// next to the branches, and the branches that don't return jump to it, with
+    /// [Terminator::Jump]. So the continuation is never copied into the branches.
     pub fn into_control_flow_graph(self) -> BasicBlock<Instruction> {
         // The entry block is always called `entry`
-        let mut entry_bb = Context::default().control_flow_graph(self);
-        entry_bb.label = "entry".into();
-        entry_bb
+        let mut context = Context::default();
+        let _ = context.fresh_label();
+        context.control_flow_graph("entry".into(), self, None)
     }
 }
 
@@ -84,44 +86,73 @@ struct Context {
 }
 
 impl Context {
-    /// Internal function to convert a block into a control flow graph.
-    ///
-    /// [Block::into_control_flow_graph]
-    fn control_flow_graph(&mut self, apply: Block) -> BasicBlock<Instruction> {
+    fn fresh_label(&mut self) -> String {
         let id = self.name_index;
         self.name_index += 1;
 
-        let mut bb = BasicBlock::new(&format!("bb_{id}"));
-        let instructions = apply.block.iter().cloned().flat_map(flatten_instruction);
+        format!("bb_{id}")
+    }
 
-        for (id, instruction) in instructions.clone().enumerate() {
+    /// Internal function to convert a block into a control flow graph, the block
+    /// jumps to the `continuation` when its instructions end without a return.
+    ///
+    /// [Block::into_control_flow_graph]
+    fn control_flow_graph(&mut self, label: String, apply: Block, continuation: Option<Label>) -> BasicBlock<Instruction> {
+        let mut bb = BasicBlock::new(&label);
+        let mut instructions = apply.block.into_iter().flat_map(flatten_instruction);
+
+        while let Some(instruction) = instructions.next() {
             match instruction {
                 Instruction::Return(value) => {
                     bb.terminator = Terminator::Return(value);
+                    return bb;
                 }
                 Instruction::If(if_instruction) => {
-                    let declared_blocks = &mut bb.declared_blocks;
-                    let then = self.control_flow_graph(if_instruction.then);
+                    let then = self.fresh_label();
+                    let otherwise = if_instruction.otherwise.map(|otherwise| (self.fresh_label(), otherwise));
 
-                    // use `otherwise` as the remaining code
-                    let mut otherwise = if_instruction.otherwise.unwrap_or_default();
-                    otherwise.block.extend(instructions.dropping(id + 1));
+                    // The remaining instructions are moved into a join block, that
+                    // is the continuation of both branches.
+                    let remaining = Block::new(instructions.collect());
+                    let continuation = match remaining.block.is_empty() {
+                        true => continuation,
+                        false => {
+                            let join = self.fresh_label();
+                            let join = self.control_flow_graph(join, remaining, continuation);
+                            let label = Label::new(&join);
+                            bb.declared_blocks.insert(join.label.clone(), join);
+                            Some(label)
+                        }
+                    };
 
-                    let otherwise = self.control_flow_graph(otherwise);
+                    let then = self.control_flow_graph(then, if_instruction.then, continuation.clone());
+                    let then_label = Label::new(&then);
+                    bb.declared_blocks.insert(then.label.clone(), then);
 
-                    bb.terminator = Terminator::Cond(
-                        if_instruction.condition,
-                        Label::new(&then),
-                        Label::new(&otherwise),
-                    );
+                    // Without an `else` branch, the condition jumps directly to the
+                    // continuation, if there's one.
+                    let otherwise_label = match (otherwise, continuation.clone()) {
+                        (None, Some(continuation)) => continuation,
+                        (otherwise, continuation) => {
+                            let (label, block) = otherwise.unwrap_or_else(|| (self.fresh_label(), Block::default()));
+                            let otherwise = self.control_flow_graph(label, block, continuation);
+                            let label = Label::new(&otherwise);
+                            bb.declared_blocks.insert(otherwise.label.clone(), otherwise);
+                            label
+                        }
+                    };
 
-                    declared_blocks.insert(then.label.clone(), then);
-                    declared_blocks.insert(otherwise.label.clone(), otherwise);
-                    break;
+                    bb.terminator = Terminator::Cond(if_instruction.condition, then_label, otherwise_label);
+                    return bb;
                 }
                 instruction => bb.instructions.push(instruction),
             }
         }
+
+        if let Some(continuation) = continuation {
+            bb.terminator = Terminator::Jump(continuation);
+        }
+
         bb
     }
 }
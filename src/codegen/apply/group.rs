@@ -1,17 +1,23 @@
 use itertools::Itertools;
 
 use crate::codegen::apply::argument::Argument;
+use crate::codegen::apply::decision::Decision;
 use crate::codegen::apply::Codegen;
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::apply::{Block, Instruction, Term};
 use crate::ir::syntax;
 
 impl Codegen {
     pub fn build_apply(&mut self, group: &syntax::RuleGroup) -> super::Result<Block> {
-        let rules = group.rules.clone();
         let strict_parameters = group.strict_parameters.clone();
 
-        if rules.is_empty() {
-            return Err("no rules".into());
+        self.group_name = group.name.clone();
+
+        if group.rules.is_empty() {
+            let diagnostic = Diagnostic::error(ErrorCode::EmptyRuleGroup, "the rule group has no rules")
+                .with_rule(&group.name, None);
+
+            return Err(vec![diagnostic]);
         }
 
         for i in 0..strict_parameters.len() {
@@ -25,59 +31,13 @@ impl Codegen {
 
         // TODO: superpose
 
-        // This exists, because we need to keep track of the variables
-        // that are created by the constructor patterns, without any rules.
-        //
-        // If the codes clones directly the `self.variables`, in each rule codegen,
-        // it would end up with the variables from the previous rule.
-        let argument_variables = self.variables.clone();
-
-        for rule in rules {
-            let collect = self.create_collect(&rule);
-
-            let mut match_rule = Term::True;
-            for (i, parameter) in rule.parameters.iter().cloned().enumerate() {
-                match_rule = Term::logical_and(match_rule, self.build_match(group, i, parameter));
-                match_rule = match_rule.simplify().clone();
-            }
-
-            if match_rule.is_true() {
-                self.instructions.push(Instruction::IncrementCost);
-                //>>>Build variables array
-                let mut old_variables = argument_variables.clone();
-                let new_variables = collect
-                    .iter()
-                    .map(|variable| self.variable_as_tuple(variable));
-                old_variables.extend(new_variables);
-                self.variables = old_variables;
-                //<<<
-                let done = self.build_term(rule.value.clone());
-                self.build_link(done);
-                self.build_collect(collect);
-                self.build_free(&rule, group);
-                self.instr(Instruction::Return(Term::True));
-            } else {
-                let mut then: Codegen = self.new_block(Instruction::IncrementCost);
-                then.build_constructor_patterns(&rule);
-                //>>>Build variables array
-                let mut old_variables = argument_variables.clone();
-                let new_variables = collect
-                    .iter()
-                    .map(|variable| then.variable_as_tuple(variable));
-                old_variables.extend(new_variables);
-                then.variables = old_variables;
-                //<<<
-                let done = then.build_term(rule.value.clone());
-                then.build_link(done);
-                then.build_collect(collect);
-                then.build_free(&rule, group);
-                then.instr(Instruction::Return(Term::True));
-
-                self.instr(Instruction::cond(match_rule, then.instructions, None));
-            }
-        }
+        // The decision tree ends every branch with a return, so it's
+        // the last thing of the apply function.
+        self.build_decision(group, Decision::compile(group));
 
-        self.instructions.push(Instruction::Return(Term::False));
+        if !self.diagnostics.is_empty() {
+            return Err(std::mem::take(&mut self.diagnostics));
+        }
 
         self.instructions.tags = self
             .constant_tags
@@ -95,4 +55,36 @@ impl Codegen {
 
         Ok(self.instructions.clone())
     }
+
+    /// Builds the body of the rule, when all of its patterns are already
+    /// matched. It binds the left-hand side variables, links the right-hand
+    /// side, and frees the matched nodes.
+    pub fn build_rule(&mut self, group: &syntax::RuleGroup, equation: usize) {
+        let rule = &group.rules[equation];
+        self.equation = equation;
+
+        let collect = self.create_collect(rule);
+
+        if self.global.trace {
+            self.instr(Instruction::println(&format!("[trace] {}: rule {equation}", group.name)));
+        }
+        self.instr(Instruction::IncrementCost);
+        self.build_constructor_patterns(rule);
+        //>>>Build variables array
+        // The variables are indexed in the same order of the rule's
+        // left-hand side, that's used by [crate::ir::syntax::Atom].
+        self.variables = collect
+            .iter()
+            .map(|variable| self.variable_as_tuple(variable))
+            .collect();
+        //<<<
+        // The matched nodes must be known before the right-hand side is
+        // built, so they can be reused by its allocations.
+        self.free = self.create_free_positions(rule, group);
+        let done = self.build_term(rule.value.clone());
+        self.build_link(done);
+        self.build_collect(collect);
+        self.build_free();
+        self.instr(Instruction::Return(Term::True));
+    }
 }
@@ -33,7 +33,8 @@ impl Codegen {
         }
 
         let name = self.fresh_name("lam");
-        self.instr(Instruction::binding(&name, Term::alloc(2)));
+        let lam_alloc = self.alloc(2);
+        self.instr(Instruction::binding(&name, lam_alloc));
 
         if global_id != 0 {
             // FIXME: sanitizer still can't detect if a scope-less lambda doesn't use its bound
@@ -17,29 +17,14 @@ impl Codegen {
             comments: Vec::new(),
             instructions: Vec::new(),
         };
-        let mut codegen = self.new_empty_block();
+        let mut codegen = self.new_block(Block::default());
         let new_term = f(&mut codegen, current);
-        metadata.instructions = codegen.instructions.block;
         // Update the current name index, with the used name index
         // to maintain the name index consistency
-        self.name_index = codegen.name_index;
+        metadata.instructions = self.merge_block(codegen).block;
 
         self.instr(Instruction::Metadata(metadata));
 
         new_term
     }
-
-    fn new_empty_block(&self) -> Self {
-        Self {
-            global: self.global.clone(),
-            arguments: self.arguments.clone(),
-            name_index: self.name_index,
-            variables: self.variables.clone(),
-            lambdas: self.lambdas.clone(),
-            instructions: Block::default(),
-            // constant clonning
-            constant_extensions: self.constant_extensions.clone(),
-            constant_tags: self.constant_tags.clone(),
-        }
-    }
 }
@@ -1,44 +1,72 @@
+use crate::codegen::apply::argument::Argument;
 use crate::codegen::apply::Codegen;
-use crate::ir::apply::{Instruction, Tag, Term};
+use crate::ir::apply::{Instruction, Position, Tag, Term};
 use crate::ir::syntax;
 use crate::ir::syntax::*;
 
 impl Codegen {
-    pub fn build_match(&mut self, group: &RuleGroup, i: usize, parameter: Parameter) -> Term {
-        use crate::ir::syntax::Parameter::*;
-
-        let argument = self.get_argument(i).unbox();
-
-        match parameter {
-            U60(value) => Term::logical_and(
-                Term::equal(argument.get_tag(), self.tag(Tag::U60)),
-                Term::equal(argument.get_num(), Term::create_u60(value)),
-            ),
-            F60(value) => Term::logical_and(
-                Term::equal(argument.get_tag(), self.tag(Tag::F60)),
-                Term::equal(argument.get_num(), Term::create_f60(value)),
-            ),
-            Constructor(syntax::Constructor { name, .. }) => {
-                let id = self.get_name_id(&name);
-
-                Term::logical_and(
-                    Term::equal(argument.get_tag(), self.tag(Tag::CONSTRUCTOR)),
-                    Term::equal(argument.get_ext(), self.ext(id, &name)),
-                )
-            }
-            Atom(..) if group.strict_parameters[i] => {
-                // TODO: hoas for kind2
-
-                Term::logical_or(
-                    Term::equal(argument.get_tag(), self.tag(Tag::CONSTRUCTOR)),
-                    Term::logical_or(
-                        Term::equal(argument.get_tag(), self.tag(Tag::U60)),
-                        Term::equal(argument.get_tag(), self.tag(Tag::F60)),
-                    ),
-                )
+    /// Builds the condition to match the [syntax::Constructor] pattern, checking the
+    /// tag and the extension of the term, and then the nested patterns of its fields.
+    ///
+    /// The fields are only loaded after the constructor itself is matched, because
+    /// [Term::LogicalAnd] only evaluates the right-hand side when the left-hand side
+    /// is true. The nested fields aren't reduced, like in HVM, so they're matched as
+    /// they are in the heap, and a field that isn't in WHNF doesn't match.
+    pub fn build_constructor_match(&mut self, term: Term, constructor: &syntax::Constructor) -> Term {
+        let id = self.get_name_id(&constructor.name);
+
+        let condition = Term::logical_and(
+            Term::equal(term.get_tag(), self.tag(Tag::CONSTRUCTOR)),
+            Term::equal(term.get_ext(), self.ext(id, &constructor.name)),
+        );
+
+        let fields = self.build_fields_match(term, constructor);
+
+        Term::logical_and(condition, fields).simplify().clone()
+    }
+
+    /// Builds the condition to match the nested patterns of the fields of the
+    /// [syntax::Constructor], assuming that the term is already the constructor.
+    ///
+    /// If the pattern has another arity than the one of the rule book, the arity
+    /// of the term is checked first, so the fields aren't loaded out of the node.
+    pub fn build_fields_match(&mut self, term: Term, constructor: &syntax::Constructor) -> Term {
+        let mut condition = match self.global.arities.get(&constructor.name) {
+            Some(arity) if *arity != constructor.arity => {
+                Term::equal(Term::arity_of(term.clone()), Term::Int(constructor.arity))
             }
             _ => Term::True,
+        };
+
+        for (index, pattern) in constructor.flatten_patterns.iter().enumerate() {
+            let field = Term::load_arg(term.clone(), index as u64);
+            let check = match pattern {
+                Pattern::Constructor(nested) => self.build_constructor_match(field, nested),
+                Pattern::U60(value) => self.build_u60_match(field, *value),
+                Pattern::F60(value) => self.build_f60_match(field, *value),
+                Pattern::Atom(..) | Pattern::Erased => continue,
+            };
+
+            condition = Term::logical_and(condition, check).simplify().clone();
         }
+
+        condition
+    }
+
+    // The number is compared with the whole pointer, because [Term::create_u60]
+    // and [Term::create_f60] already carries the tag bits.
+    pub fn build_u60_match(&mut self, term: Term, value: u64) -> Term {
+        Term::logical_and(
+            Term::equal(term.get_tag(), self.tag(Tag::U60)),
+            Term::equal(term, Term::create_u60(value)),
+        )
+    }
+
+    pub fn build_f60_match(&mut self, term: Term, value: f64) -> Term {
+        Term::logical_and(
+            Term::equal(term.get_tag(), self.tag(Tag::F60)),
+            Term::equal(term, Term::create_f60(value)),
+        )
     }
 
     pub fn build_constructor_patterns(&mut self, rule: &Rule) {
@@ -52,18 +80,39 @@ impl Codegen {
                 continue;
             };
 
-            for (index, _) in constructor.flatten_patterns.iter().enumerate() {
-                let name = self.fresh_name("pat");
-                let argument = self.get_argument(argument);
-                let term = Term::load_arg(argument.clone().unbox(), index as u64);
-                argument.add_field(Term::reference(&name));
+            let term = self.get_argument(argument).unbox();
+            let fields = self.build_field_patterns(term, constructor);
+            self.get_argument(argument).1 = fields;
+        }
+    }
+
+    /// Binds every field of the constructor, taking them from the node of the
+    /// given term, that is freed in the end of the rule, and binds recursively
+    /// the fields of the nested constructor patterns.
+    fn build_field_patterns(&mut self, term: Term, constructor: &syntax::Constructor) -> Vec<Argument> {
+        let mut fields = Vec::new();
+        if constructor.flatten_patterns.is_empty() {
+            return fields;
+        }
+
+        let node = self.fresh_name("node");
+        self.instr(Instruction::binding(&node, Term::get_position(term, 0)));
+
+        for (index, pattern) in constructor.flatten_patterns.iter().enumerate() {
+            let name = self.fresh_name("pat");
+            let value = Term::take_arg(Position::new(&node, 0), Term::Int(index as u64));
+
+            // Creates a binding instruction, initializing the variable with the term.
+            self.instr(Instruction::binding(&name, value));
 
-                // Pushes to the variables, and creates a binding instruction,
-                // initializing the variable with the term.
-                self.variables.push((name.clone(), Term::reference(&name)));
-                self.instr(Instruction::binding(&name, term));
+            let mut field = Argument::new(Term::reference(&name));
+            if let Pattern::Constructor(nested) = pattern {
+                field.1 = self.build_field_patterns(Term::reference(&name), nested);
             }
+
+            fields.push(field);
         }
-    }
 
+        fields
+    }
 }
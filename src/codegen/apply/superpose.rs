@@ -0,0 +1,25 @@
+use crate::codegen::apply::Codegen;
+use crate::ir::apply::{Instruction, Position, Term};
+use crate::ir::syntax::Super as IRSuper;
+
+impl Codegen {
+    pub fn build_super(&mut self, expr: IRSuper) -> Term {
+        let IRSuper {
+            label,
+            box first,
+            box second,
+        } = expr;
+
+        let first = self.build_term(first);
+        let second = self.build_term(second);
+
+        let name = self.fresh_name("sup");
+        let color = self.global.label_color(label.as_deref());
+        let sup_alloc = self.alloc(2);
+        self.instr(Instruction::binding(&name, sup_alloc));
+        self.instr(Instruction::link(Position::initial(&name), first));
+        self.instr(Instruction::link(Position::new(&name, 1), second));
+
+        Term::create_super(color, Position::initial(&name))
+    }
+}
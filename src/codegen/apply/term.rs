@@ -18,19 +18,23 @@ impl Codegen {
                 Some(global_name) => bb.build_constructor(app.arguments, global_name),
                 None => bb.build_app(*app.callee, app.arguments),
             }),
-            Duplicate(_) => todo!(),
-            Super(_) => todo!(),
+            Duplicate(dup_expr) => self.build_duplicate(dup_expr),
+            Super(super_expr) => self.with_metadata(term, |bb, _| bb.build_super(super_expr)),
         }
     }
 
     fn build_let(&mut self, expr: IRLet) -> Term {
         let IRLet {
+            erased,
             name,
             box value,
             box body,
         } = expr;
 
         let binding = self.build_term(value);
+        if erased {
+            self.instr(Instruction::collect(binding.clone()));
+        }
         self.variables.push((name, binding));
         let body = self.build_term(body);
         self.variables.pop();
@@ -1,20 +1,22 @@
 use crate::codegen::apply::Codegen;
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::apply::Term;
 use crate::ir::apply::Variable;
 
 impl Variable {
     pub fn as_name(&self) -> String {
-        match self.field_index {
-            Some(field_index) => format!("arg{index}_{field_index}", index = self.index),
-            None => format!("arg{index}", index = self.index),
-        }
+        format!("arg{index}{fields}", index = self.index, fields = self.fields_suffix())
     }
 
     pub fn as_simple_name(&self) -> String {
-        match self.field_index {
-            Some(field_index) => format!("x{index}_{field_index}", index = self.index),
-            None => format!("x{index}", index = self.index),
-        }
+        format!("x{index}{fields}", index = self.index, fields = self.fields_suffix())
+    }
+
+    fn fields_suffix(&self) -> String {
+        self.fields
+            .iter()
+            .map(|field_index| format!("_{field_index}"))
+            .collect()
     }
 
     pub fn as_term(&self) -> Term {
@@ -26,12 +28,17 @@ impl Codegen {
     pub fn variable_as_tuple(&mut self, variable: &Variable) -> (String, Term) {
         let name = "*";
 
-        let term = match variable.field_index {
-            Some(field_index) => self
-                .get_argument(variable.index as usize)
-                .get_field(field_index as usize),
-            None => self.get_argument(variable.index as usize).unbox(),
-        };
+        let term = self
+            .get_argument(variable.index as usize)
+            .get_path(&variable.fields)
+            .unwrap_or_else(|| {
+                self.report(Diagnostic::error(
+                    ErrorCode::InvalidPattern,
+                    format!("the field {:?} of the parameter {} isn't bound", variable.fields, variable.index),
+                ));
+
+                Term::erased()
+            });
 
         (name.into(), term)
     }
@@ -0,0 +1,472 @@
+//! Exhaustiveness and redundancy checks of the rule groups.
+//!
+//! The checks walk the [Decision] tree of each rule group, so they see the rules
+//! in the same way that the apply codegen does:
+//!   - A branch without rules is a non-exhaustive match, and the path to the
+//!     branch is reported as an example of a missing pattern;
+//!   - A rule that isn't reachable in any leaf is shadowed by the previous ones.
+//!
+//! HVM is untyped, so the constructors that belong together are inferred from the
+//! patterns: two constructors are in the same family, if they're matched in the
+//! same argument of a rule group, or in the same field of a constructor.
+
+use fxhash::FxHashMap;
+use itertools::Itertools;
+
+use crate::codegen::apply::decision::Decision;
+use crate::codegen::GlobalContext;
+use crate::diagnostic::{Diagnostic, ErrorCode};
+use crate::ir::syntax::{Constructor, Parameter, Pattern, Rule, RuleGroup};
+
+/// The maximum number of missing patterns displayed in a diagnostic.
+const MAX_MISSING_PATTERNS: usize = 3;
+
+/// The families of the constructors, inferred from the patterns of the rule book.
+#[derive(Debug, Default)]
+pub struct Families {
+    /// The constructor name -> family id, binding map.
+    family: FxHashMap<String, usize>,
+
+    /// The constructor name -> arity, binding map.
+    arity: FxHashMap<String, u64>,
+}
+
+impl Families {
+    /// Infers the constructor families from the patterns of every rule group, only
+    /// the constructors in the [GlobalContext] constructor table are considered.
+    pub fn new(groups: &[RuleGroup], global: &GlobalContext) -> Self {
+        let mut families = Self::default();
+
+        for group in groups {
+            for column in 0..group.strict_parameters.len() {
+                let constructors = group
+                    .rules
+                    .iter()
+                    .filter_map(|rule| match rule.parameters.get(column) {
+                        Some(Parameter::Constructor(constructor)) => Some(constructor),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                families.add_siblings(&constructors, global);
+            }
+        }
+
+        families
+    }
+
+    /// Unifies the families of the given constructors, and then the families
+    /// of the constructors matched in their fields.
+    fn add_siblings(&mut self, constructors: &[&Constructor], global: &GlobalContext) {
+        let constructors = constructors
+            .iter()
+            .filter(|constructor| global.constructors.contains_key(&constructor.name))
+            .collect::<Vec<_>>();
+
+        for constructor in &constructors {
+            self.arity.insert(constructor.name.clone(), constructor.arity);
+            let next = self.family.len();
+            self.family.entry(constructor.name.clone()).or_insert(next);
+        }
+
+        if let Some(first) = constructors.first() {
+            let family = self.family[&first.name];
+            for constructor in &constructors {
+                let old = self.family[&constructor.name];
+                for value in self.family.values_mut().filter(|value| **value == old) {
+                    *value = family;
+                }
+            }
+        }
+
+        // The fields of the same constructor are siblings too
+        let by_name = constructors.iter().into_group_map_by(|constructor| constructor.name.clone());
+        for (_, constructors) in by_name.into_iter().sorted_by_key(|(name, _)| name.clone()) {
+            let arity = constructors.first().map(|constructor| constructor.arity).unwrap_or(0);
+            for field in 0..arity as usize {
+                let nested = constructors
+                    .iter()
+                    .filter_map(|constructor| match constructor.flatten_patterns.get(field) {
+                        Some(Pattern::Constructor(nested)) => Some(nested),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                self.add_siblings(&nested, global);
+            }
+        }
+    }
+
+    /// Returns the constructors in the same families of the given ones, that
+    /// aren't in the list, sorted by their ids.
+    fn missing(&self, constructors: &[String], global: &GlobalContext) -> Vec<String> {
+        let families = constructors
+            .iter()
+            .filter_map(|name| self.family.get(name))
+            .collect::<Vec<_>>();
+
+        self.family
+            .iter()
+            .filter(|(name, family)| families.contains(family) && !constructors.contains(*name))
+            .map(|(name, _)| name.clone())
+            .sorted_by_key(|name| global.constructors.get(name).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// Displays a constructor pattern, with wildcards in the fields.
+    fn pattern(&self, name: &str) -> String {
+        let arity = self.arity.get(name).copied().unwrap_or(0);
+        let fields = (0..arity).map(|_| " _").collect::<String>();
+
+        format!("({name}{fields})")
+    }
+}
+
+/// Checks the exhaustiveness of the rule group, and if there are unreachable rules,
+/// returning the warnings.
+pub fn check_group(group: &RuleGroup, families: &Families, global: &GlobalContext) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let decision = Decision::compile(group);
+
+    let mut context = Context {
+        group,
+        families,
+        global,
+        path: vec!["_".into(); group.strict_parameters.len()],
+        missing: Vec::new(),
+        reachable: vec![false; group.rules.len()],
+    };
+    context.walk(&decision);
+
+    let Context { missing, reachable, .. } = context;
+    let missing = missing.into_iter().unique().collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        let patterns = missing
+            .iter()
+            .take(MAX_MISSING_PATTERNS)
+            .map(|pattern| format!("`{pattern}`"))
+            .join(", ");
+        let more = match missing.len().saturating_sub(MAX_MISSING_PATTERNS) {
+            0 => String::new(),
+            n => format!(" and {n} more"),
+        };
+
+        let diagnostic = Diagnostic::warning(
+            ErrorCode::NonExhaustive,
+            format!("non-exhaustive patterns: {patterns}{more} not covered"),
+        )
+        .with_rule(&group.name, None)
+        .with_label(&group.name, "patterns not covered")
+        .with_note("the apply function returns false when no rule matches, leaving the term unreduced");
+
+        diagnostics.push(diagnostic);
+    }
+
+    for (equation, reachable) in reachable.into_iter().enumerate() {
+        if reachable {
+            continue;
+        }
+
+        let diagnostic = Diagnostic::warning(ErrorCode::UnreachableRule, "unreachable rule")
+            .with_rule(&group.name, Some(equation))
+            .with_label(&group.name, "this rule is shadowed by the previous ones");
+
+        diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+}
+
+struct Context<'a> {
+    group: &'a RuleGroup,
+    families: &'a Families,
+    global: &'a GlobalContext,
+
+    /// The patterns of the arguments, in the current branch of the tree.
+    path: Vec<String>,
+
+    missing: Vec<String>,
+    reachable: Vec<bool>,
+}
+
+impl Context<'_> {
+    fn walk(&mut self, decision: &Decision) {
+        match decision {
+            Decision::Leaf(rows) if rows.is_empty() => self.report_missing(),
+            Decision::Leaf(rows) => {
+                for row in rows {
+                    self.reachable[*row] = true;
+                    if !has_nested_patterns(&self.group.rules[*row]) {
+                        return;
+                    }
+                }
+
+                // Every rule has nested patterns, that could not match
+                if !self.is_nested_exhaustive(rows) {
+                    self.report_missing();
+                }
+            }
+            Decision::Switch(switch) => {
+                let argument = switch.argument;
+                let old = self.path[argument].clone();
+
+                for (name, decision) in &switch.constructors {
+                    self.path[argument] = self.families.pattern(name);
+                    self.walk(decision);
+                }
+
+                for (value, decision) in &switch.u60 {
+                    self.path[argument] = value.to_string();
+                    self.walk(decision);
+                }
+
+                for (value, decision) in &switch.f60 {
+                    self.path[argument] = format!("{value:?}");
+                    self.walk(decision);
+                }
+
+                // The default is shared by the constructors, the numbers, and
+                // the other tags of the non-strict arguments.
+                if !switch.default.is_unreachable() {
+                    self.path[argument] = old;
+                    self.walk(&switch.default);
+                    return;
+                }
+
+                let names = switch
+                    .constructors
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>();
+                for name in self.families.missing(&names, self.global) {
+                    self.path[argument] = self.families.pattern(&name);
+                    self.report_missing();
+                }
+
+                if !switch.u60.is_empty() {
+                    // The smallest number that isn't matched
+                    let value = (0..).find(|value| switch.u60.iter().all(|(other, _)| other != value));
+                    self.path[argument] = value.unwrap_or_default().to_string();
+                    self.report_missing();
+                }
+
+                if !switch.f60.is_empty() {
+                    let value = switch.f60.iter().map(|(value, _)| *value).fold(0.0, f64::max) + 1.0;
+                    self.path[argument] = format!("{value:?}");
+                    self.report_missing();
+                }
+
+                self.path[argument] = old;
+            }
+        }
+    }
+
+    /// If the nested patterns of the rows of a leaf match every constructor of
+    /// their families. The fields of each argument are the columns of the matrix,
+    /// the arguments without a constructor pattern have wildcard fields.
+    fn is_nested_exhaustive(&self, rows: &[usize]) -> bool {
+        let arities = (0..self.group.strict_parameters.len())
+            .map(|column| {
+                rows.iter()
+                    .find_map(|row| match self.group.rules[*row].parameters.get(column) {
+                        Some(Parameter::Constructor(constructor)) => Some(constructor.arity),
+                        _ => None,
+                    })
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let matrix = rows
+            .iter()
+            .map(|row| {
+                let rule = &self.group.rules[*row];
+                arities
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(column, arity)| match rule.parameters.get(column) {
+                        Some(Parameter::Constructor(constructor)) => constructor.flatten_patterns.iter().collect(),
+                        _ => vec![&WILDCARD; *arity as usize],
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        self.is_exhaustive(matrix)
+    }
+
+    /// If the rows of patterns match every term, specializing the first column by
+    /// each constructor of the family, or taking the wildcard rows, if the family
+    /// isn't complete. The numbers are only matched by the wildcards.
+    fn is_exhaustive(&self, matrix: Vec<Vec<&Pattern>>) -> bool {
+        let Some(first) = matrix.first() else {
+            return false;
+        };
+        if first.is_empty() {
+            return true;
+        }
+
+        let names = matrix
+            .iter()
+            .filter_map(|row| match row[0] {
+                Pattern::Constructor(constructor) => Some(constructor.name.clone()),
+                _ => None,
+            })
+            .unique()
+            .collect::<Vec<_>>();
+
+        if names.is_empty() || !self.families.missing(&names, self.global).is_empty() {
+            let default = matrix
+                .iter()
+                .filter(|row| is_wildcard(row[0]))
+                .map(|row| row[1..].to_vec())
+                .collect();
+
+            return self.is_exhaustive(default);
+        }
+
+        names.iter().all(|name| {
+            let arity = self.families.arity.get(name).copied().unwrap_or(0);
+            let specialized = matrix
+                .iter()
+                .filter_map(|row| match row[0] {
+                    Pattern::Constructor(constructor) if constructor.name == *name => {
+                        Some(constructor.flatten_patterns.iter().chain(row[1..].iter().copied()).collect())
+                    }
+                    pattern if is_wildcard(pattern) => Some(
+                        std::iter::repeat(&WILDCARD)
+                            .take(arity as usize)
+                            .chain(row[1..].iter().copied())
+                            .collect(),
+                    ),
+                    _ => None,
+                })
+                .collect();
+
+            self.is_exhaustive(specialized)
+        })
+    }
+
+    fn report_missing(&mut self) {
+        let name = &self.group.name;
+        let pattern = match self.path.is_empty() {
+            true => format!("({name})"),
+            false => format!("({name} {})", self.path.join(" ")),
+        };
+
+        self.missing.push(pattern);
+    }
+}
+
+/// The pattern of the fields that aren't matched.
+static WILDCARD: Pattern = Pattern::Erased;
+
+fn is_wildcard(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::Atom(..) | Pattern::Erased)
+}
+
+/// If the rule has nested patterns, that are only checked in the leaves.
+fn has_nested_patterns(rule: &Rule) -> bool {
+    rule.parameters.iter().any(|parameter| match parameter {
+        Parameter::Constructor(constructor) => constructor
+            .flatten_patterns
+            .iter()
+            .any(|pattern| !is_wildcard(pattern)),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::eval::setup_global_context;
+    use crate::codegen::syntax::Transform;
+
+    fn check(code: &str, name: &str) -> Vec<Diagnostic> {
+        let file = hvm::language::syntax::read_file(code).unwrap();
+        let book = hvm::language::rulebook::gen_rulebook(&file);
+        let global = setup_global_context(&book);
+        let groups = book.transform().unwrap();
+        let families = Families::new(&groups, &global);
+
+        let group = groups.iter().find(|group| group.name == name).unwrap();
+        check_group(group, &families, &global)
+    }
+
+    #[test]
+    fn it_reports_the_missing_constructors() {
+        let diagnostics = check(
+            r#"
+            (Foo (Nil))       = 0
+            (Foo (Cons x xs)) = 1
+            (Bar (Nil))       = 0
+            "#,
+            "Bar",
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, ErrorCode::NonExhaustive);
+        assert_eq!(
+            diagnostics[0].message,
+            "non-exhaustive patterns: `(Bar (Cons _ _))` not covered"
+        );
+
+        assert!(check("(Foo (Nil)) = 0\n(Foo (Cons x xs)) = 1", "Foo").is_empty());
+    }
+
+    #[test]
+    fn it_reports_the_shadowed_rules() {
+        let diagnostics = check(
+            r#"
+            (Foo x)     = 0
+            (Foo (Nil)) = 1
+            "#,
+            "Foo",
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, ErrorCode::UnreachableRule);
+        assert_eq!(diagnostics[0].equation, Some(1));
+    }
+
+    #[test]
+    fn it_checks_the_number_columns() {
+        let diagnostics = check(
+            r#"
+            (Foo 0) = 1
+            (Foo 1) = 0
+            "#,
+            "Foo",
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, ErrorCode::NonExhaustive);
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `(Foo 2)` not covered");
+
+        let diagnostics = check(
+            r#"
+            (Foo 0) = 1
+            (Foo n) = n
+            "#,
+            "Foo",
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn it_checks_the_nested_patterns() {
+        let code = r#"
+            (Foo (Succ (Succ n))) = n
+            (Foo (Succ (Zero)))   = 1
+            (Foo (Zero))          = 0
+            (Bar (Succ (Succ n))) = n
+            (Bar (Zero))          = 0
+            "#;
+
+        assert!(check(code, "Foo").is_empty());
+
+        let diagnostics = check(code, "Bar");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `(Bar (Succ _))` not covered");
+    }
+}
@@ -0,0 +1,487 @@
+//! Linearity pass over the [crate::ir::syntax] terms.
+//!
+//! The HVM terms are linear, every variable must be used exactly once, because
+//! the same pointer can't be linked into two places of the heap. This pass counts
+//! the uses of each binder, and just like the HVM's sanitizer:
+//!   - Inserts [Duplicate] nodes for the variables that are used more than once,
+//!     each one of them gets a fresh color in the apply codegen;
+//!   - Marks the variables that are never used as erased, so the apply codegen
+//!     collects their values.
+//!
+//! The [Atom]s are indexed by the position of their binder in the variable stack,
+//! so the indexes are computed again, as the inserted duplications bind new
+//! variables.
+
+use crate::ir::syntax::*;
+
+/// The variables that replace a binder of the original term, each use of the
+/// binder takes the next one of them.
+#[derive(Default)]
+struct Slot {
+    copies: Vec<(String, u64)>,
+    next: usize,
+}
+
+/// A duplication to be inserted before the body of a binder.
+struct Dup {
+    from: String,
+    to: String,
+    value: Atom,
+}
+
+#[derive(Default)]
+struct Linearizer {
+    /// The slots of the original binders, indexed by the original [Atom::index].
+    scope: Vec<Slot>,
+
+    /// The size of the new variable stack.
+    depth: u64,
+}
+
+impl Rule {
+    /// Makes the rule linear, duplicating the variables that are used more than
+    /// once, and erasing the ones that are never used.
+    pub fn linearize(self) -> Self {
+        let Rule {
+            name,
+            parameters,
+            value,
+        } = self;
+
+        let mut names = Vec::new();
+        parameters_names(&parameters, &mut names);
+
+        let mut linearizer = Linearizer {
+            scope: Vec::new(),
+            depth: names.len() as u64,
+        };
+
+        let mut unused = Vec::new();
+        let mut dups = Vec::new();
+        for (index, name) in names.iter().enumerate() {
+            let uses = count_uses(&value, index as u64);
+            match name {
+                Some(name) => dups.extend(linearizer.bind(name, index as u64, uses)),
+                None => linearizer.scope.push(Slot::default()),
+            }
+            unused.push(name.is_some() && uses == 0);
+        }
+
+        let value = wrap(linearizer.walk(value), dups);
+
+        let mut index = 0;
+        let parameters = parameters
+            .into_iter()
+            .map(|parameter| erase_parameter(parameter, &unused, &mut index))
+            .collect();
+
+        Rule {
+            name,
+            parameters,
+            value,
+        }
+    }
+}
+
+impl Linearizer {
+    /// Binds a new variable in the given position, and creates the duplications
+    /// needed to use it `uses` times.
+    fn bind(&mut self, name: &str, position: u64, uses: u64) -> Vec<Dup> {
+        let mut slot = Slot::default();
+        let mut dups = Vec::new();
+
+        match uses {
+            0 => {}
+            1 => slot.copies.push((name.into(), position)),
+            _ => {
+                let mut value = Atom {
+                    name: name.into(),
+                    index: position,
+                    fields: vec![],
+                };
+
+                // Creates a chain of duplications, each one of them holding a
+                // copy, and the rest of the copies in the second variable.
+                for i in 0..uses - 1 {
+                    let from = format!("{name}.{i}");
+                    let to = if i == uses - 2 {
+                        format!("{name}.{}", i + 1)
+                    } else {
+                        format!("{name}.r{i}")
+                    };
+
+                    slot.copies.push((from.clone(), self.depth));
+                    let rest = Atom {
+                        name: to.clone(),
+                        index: self.depth + 1,
+                        fields: vec![],
+                    };
+                    self.depth += 2;
+
+                    dups.push(Dup {
+                        from,
+                        to,
+                        value: std::mem::replace(&mut value, rest),
+                    });
+                }
+
+                slot.copies.push((value.name, value.index));
+            }
+        }
+
+        self.scope.push(slot);
+        dups
+    }
+
+    /// Binds a new variable on the top of the stack, and walks the body with it.
+    fn walk_scope(&mut self, name: &str, body: Term) -> (Term, u64) {
+        let depth = self.depth;
+        let uses = count_uses(&body, self.scope.len() as u64);
+
+        self.depth += 1;
+        let dups = self.bind(name, depth, uses);
+        let body = wrap(self.walk(body), dups);
+        self.scope.pop();
+        self.depth = depth;
+
+        (body, uses)
+    }
+
+    fn walk(&mut self, term: Term) -> Term {
+        match term {
+            Term::U60(..) | Term::F60(..) => term,
+            Term::Atom(atom) => Term::Atom(self.use_atom(atom)),
+            Term::Lam(lam) => {
+                let (value, uses) = self.walk_scope(&lam.parameter, *lam.value);
+
+                Term::Lam(Lam {
+                    erased: lam.erased || uses == 0,
+                    value: value.into(),
+                    ..lam
+                })
+            }
+            Term::Let(let_expr) => {
+                let value = self.walk(*let_expr.value);
+                let (body, uses) = self.walk_scope(&let_expr.name, *let_expr.body);
+
+                Term::Let(Let {
+                    name: let_expr.name,
+                    erased: uses == 0,
+                    value: value.into(),
+                    body: body.into(),
+                })
+            }
+            Term::Duplicate(dup) => self.walk_duplicate(dup),
+            Term::App(app) => Term::App(App {
+                callee: self.walk(*app.callee).into(),
+                arguments: app
+                    .arguments
+                    .into_iter()
+                    .map(|argument| self.walk(argument))
+                    .collect(),
+                ..app
+            }),
+            Term::Super(Super { label, first, second }) => Term::Super(Super {
+                label,
+                first: self.walk(*first).into(),
+                second: self.walk(*second).into(),
+            }),
+            Term::Binary(Binary { lhs, op, rhs }) => Term::Binary(Binary {
+                lhs: self.walk(*lhs).into(),
+                op,
+                rhs: self.walk(*rhs).into(),
+            }),
+        }
+    }
+
+    fn walk_duplicate(&mut self, dup: Duplicate) -> Term {
+        let Duplicate {
+            label,
+            from,
+            to,
+            box value,
+            box body,
+        } = dup;
+
+        let value = self.walk(value);
+        let position = self.scope.len() as u64;
+        let from_uses = count_uses(&body, position);
+        let to_uses = count_uses(&body, position + 1);
+
+        // If both sides are never used, the duplication is replaced by an
+        // erased let, so the value gets collected.
+        if from_uses == 0 && to_uses == 0 {
+            let depth = self.depth;
+            self.depth += 1;
+            self.scope.push(Slot::default());
+            self.scope.push(Slot::default());
+            let body = self.walk(body);
+            self.scope.truncate(position as usize);
+            self.depth = depth;
+
+            return Term::Let(Let {
+                name: from,
+                erased: true,
+                value: value.into(),
+                body: body.into(),
+            });
+        }
+
+        let depth = self.depth;
+        self.depth += 2;
+        let mut dups = self.bind(&from, depth, from_uses);
+        dups.extend(self.bind(&to, depth + 1, to_uses));
+        let body = wrap(self.walk(body), dups);
+        self.scope.truncate(position as usize);
+        self.depth = depth;
+
+        Term::Duplicate(Duplicate {
+            label,
+            from: if from_uses == 0 { "*".into() } else { from },
+            to: if to_uses == 0 { "*".into() } else { to },
+            value: value.into(),
+            body: body.into(),
+        })
+    }
+
+    /// Takes the next copy of the variable.
+    fn use_atom(&mut self, atom: Atom) -> Atom {
+        let slot = &mut self.scope[atom.index as usize];
+        let (name, index) = slot.copies[slot.next].clone();
+        slot.next += 1;
+
+        Atom {
+            name,
+            index,
+            // The left-hand side variables that are used once, keep their
+            // original position.
+            fields: if index == atom.index { atom.fields } else { vec![] },
+        }
+    }
+}
+
+/// Wraps the body within the duplications.
+fn wrap(body: Term, dups: Vec<Dup>) -> Term {
+    dups.into_iter().rev().fold(body, |body, dup| {
+        Term::Duplicate(Duplicate {
+            label: None,
+            from: dup.from,
+            to: dup.to,
+            value: Term::Atom(dup.value).into(),
+            body: body.into(),
+        })
+    })
+}
+
+/// Counts how many times the variable in the given position is used.
+fn count_uses(term: &Term, index: u64) -> u64 {
+    match term {
+        Term::U60(..) | Term::F60(..) => 0,
+        Term::Atom(atom) => (atom.index == index) as u64,
+        Term::Lam(lam) => count_uses(&lam.value, index),
+        Term::Let(let_expr) => count_uses(&let_expr.value, index) + count_uses(&let_expr.body, index),
+        Term::Duplicate(dup) => count_uses(&dup.value, index) + count_uses(&dup.body, index),
+        Term::App(app) => {
+            count_uses(&app.callee, index)
+                + app
+                    .arguments
+                    .iter()
+                    .map(|argument| count_uses(argument, index))
+                    .sum::<u64>()
+        }
+        Term::Super(sup) => count_uses(&sup.first, index) + count_uses(&sup.second, index),
+        Term::Binary(binary) => count_uses(&binary.lhs, index) + count_uses(&binary.rhs, index),
+    }
+}
+
+/// Collects the names of the left-hand side variables, in the same order that
+/// they are bound by [crate::codegen::syntax].
+fn parameters_names(parameters: &[Parameter], names: &mut Vec<Option<String>>) {
+    for parameter in parameters {
+        match parameter {
+            Parameter::Erased => names.push(None),
+            Parameter::Atom(name) => names.push(Some(name.clone())),
+            Parameter::Constructor(constructor) => patterns_names(&constructor.flatten_patterns, names),
+            Parameter::U60(..) | Parameter::F60(..) => {}
+        }
+    }
+}
+
+fn patterns_names(patterns: &[Pattern], names: &mut Vec<Option<String>>) {
+    for pattern in patterns {
+        match pattern {
+            Pattern::Erased => names.push(None),
+            Pattern::Atom(name) => names.push(Some(name.clone())),
+            Pattern::Constructor(constructor) => patterns_names(&constructor.flatten_patterns, names),
+            Pattern::U60(..) | Pattern::F60(..) => {}
+        }
+    }
+}
+
+fn erase_parameter(parameter: Parameter, unused: &[bool], index: &mut usize) -> Parameter {
+    match parameter {
+        Parameter::Erased => {
+            *index += 1;
+            Parameter::Erased
+        }
+        Parameter::Atom(name) => {
+            *index += 1;
+            if unused[*index - 1] {
+                Parameter::Erased
+            } else {
+                Parameter::Atom(name)
+            }
+        }
+        Parameter::Constructor(constructor) => Parameter::Constructor(erase_constructor(constructor, unused, index)),
+        parameter => parameter,
+    }
+}
+
+fn erase_constructor(constructor: Constructor, unused: &[bool], index: &mut usize) -> Constructor {
+    let flatten_patterns = constructor
+        .flatten_patterns
+        .into_iter()
+        .map(|pattern| match pattern {
+            Pattern::Erased => {
+                *index += 1;
+                Pattern::Erased
+            }
+            Pattern::Atom(name) => {
+                *index += 1;
+                if unused[*index - 1] {
+                    Pattern::Erased
+                } else {
+                    Pattern::Atom(name)
+                }
+            }
+            Pattern::Constructor(constructor) => Pattern::Constructor(erase_constructor(constructor, unused, index)),
+            pattern => pattern,
+        })
+        .collect();
+
+    Constructor {
+        flatten_patterns,
+        ..constructor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::syntax::*;
+
+    fn linearize(code: &str) -> Rule {
+        let file = hvm::language::syntax::read_file(code).unwrap();
+        let book = hvm::language::rulebook::gen_rulebook(&file);
+        let mut group = RuleGroup::specialize("Foo".into(), &book).unwrap();
+
+        group.rules.remove(0)
+    }
+
+    /// Collects the labels of the duplications, from the outermost one.
+    fn dup_labels(term: &Term, labels: &mut Vec<Option<String>>) {
+        match term {
+            Term::Duplicate(dup) => {
+                labels.push(dup.label.clone());
+                dup_labels(&dup.body, labels);
+            }
+            Term::Let(let_expr) => dup_labels(&let_expr.body, labels),
+            Term::Lam(lam) => dup_labels(&lam.value, labels),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn it_erases_the_unused_variables() {
+        let rule = linearize("(Foo a) = (Zero)");
+
+        assert!(matches!(rule.parameters[0], Parameter::Erased));
+        assert_eq!(rule.value.to_string(), "(Zero)");
+    }
+
+    #[test]
+    fn it_keeps_the_linear_variables() {
+        let rule = linearize("(Foo a) = (Succ a)");
+
+        assert!(matches!(rule.parameters[0], Parameter::Atom(..)));
+        assert_eq!(rule.value.to_string(), "(Succ a)");
+    }
+
+    #[test]
+    fn it_duplicates_the_variables() {
+        let rule = linearize("(Foo a) = (Pair a a)");
+        assert_eq!(rule.value.to_string(), "dup a.0 a.1 = a in (Pair a.0 a.1)");
+
+        // The inserted duplications get a fresh color
+        let mut labels = Vec::new();
+        dup_labels(&rule.value, &mut labels);
+        assert_eq!(labels, vec![None]);
+    }
+
+    #[test]
+    fn it_chains_the_duplications() {
+        let rule = linearize("(Foo a) = (Triple a a a)");
+        assert_eq!(
+            rule.value.to_string(),
+            "dup a.0 a.r0 = a in dup a.1 a.2 = a.r0 in (Triple a.0 a.1 a.2)"
+        );
+
+        let Term::Duplicate(outer) = &rule.value else {
+            panic!("expected a duplication, found {}", rule.value);
+        };
+        let Term::Duplicate(inner) = &*outer.body else {
+            panic!("expected a nested duplication, found {}", outer.body);
+        };
+        let Term::Atom(value) = &*inner.value else {
+            panic!("expected the rest of the copies, found {}", inner.value);
+        };
+        // The parameter is bound at 0, and the first duplication at 1 and 2
+        assert_eq!(value.index, 2);
+
+        let mut labels = Vec::new();
+        dup_labels(&rule.value, &mut labels);
+        assert_eq!(labels, vec![None, None]);
+    }
+
+    #[test]
+    fn it_duplicates_the_shadowed_variables() {
+        let rule = linearize("(Foo a) = @a (Pair a a)");
+
+        assert!(matches!(rule.parameters[0], Parameter::Erased));
+        assert_eq!(rule.value.to_string(), "λa (dup a.0 a.1 = a in (Pair a.0 a.1))");
+    }
+
+    #[test]
+    fn it_duplicates_the_let_binders() {
+        let rule = linearize("(Foo x) = let b = x; (Pair b b)");
+        assert_eq!(rule.value.to_string(), "let b = x in dup b.0 b.1 = b in (Pair b.0 b.1)");
+
+        let rule = linearize("(Foo x) = let b = x; (Zero)");
+        let Term::Let(let_expr) = &rule.value else {
+            panic!("expected a let, found {}", rule.value);
+        };
+        assert!(let_expr.erased);
+    }
+
+    #[test]
+    fn it_keeps_the_user_duplications() {
+        let rule = linearize("(Foo x) = dup a b = x; (Pair a (Pair a b))");
+        assert_eq!(
+            rule.value.to_string(),
+            "dup a b = x in dup a.0 a.1 = a in (Pair a.0 (Pair a.1 b))"
+        );
+
+        let mut labels = Vec::new();
+        dup_labels(&rule.value, &mut labels);
+        assert_eq!(labels, vec![Some(DEFAULT_LABEL.to_string()), None]);
+    }
+
+    #[test]
+    fn it_erases_the_lambda_binders() {
+        let rule = linearize("(Foo x) = @y x");
+
+        let Term::Lam(lam) = &rule.value else {
+            panic!("expected a lambda, found {}", rule.value);
+        };
+        assert!(lam.erased);
+    }
+}
@@ -1 +1,110 @@
+use itertools::Itertools;
 
+use crate::ir::apply::Tag;
+use crate::ir::graph::{BasicBlock, Label, Terminator};
+use crate::ir::reduce::{FunctionId, Instruction, ReduceBlock, Rule, Stage, Term};
+
+#[derive(Default, Debug, Clone)]
+pub struct Codegen {
+    pub basic_blocks: Vec<(String, ReduceBlock)>,
+}
+
+impl Codegen {
+    /// Builds the reduce function, the state machine of the `reduce` of the original
+    /// HVM code, it reduces the term in the host to the WHNF, and returns true.
+    ///
+    /// The terms are visited, and then applied, the function applications are
+    /// dispatched by their function id, directly to the compiled functions, and
+    /// the other terms to the rules of the HVM runtime. When a term can't be
+    /// applied, the redex of its continuation is completed, and then the next
+    /// location of the visit queue is visited, until there's nothing left.
+    pub fn build_reduce(&mut self, functions: &[(FunctionId, String)]) -> ReduceBlock {
+        let mut bb = BasicBlock::new("entry");
+
+        self.build_stage(Stage::Visit, functions, "apply");
+        self.build_stage(Stage::Apply, functions, "done");
+
+        self.new_block("done", |bb| {
+            bb.terminator = cond(Term::CompleteRedex, "apply", "blink");
+        });
+        self.new_block("blink", |bb| {
+            bb.terminator = cond(Term::NextVisit, "visit", "exit");
+        });
+        self.new_block("exit", |bb| bb.with_return(Term::True));
+
+        bb.terminator = Terminator::Jump(Label("visit".into()));
+        bb.declared_blocks = self.basic_blocks.drain(..).collect();
+        bb
+    }
+
+    /// Builds the blocks of the stage, that loads the term of the host, and
+    /// switches over its tag. The rules that return true go to the visit stage,
+    /// with the new term of the host, and the other ones go to the given block.
+    fn build_stage(&mut self, stage: Stage, functions: &[(FunctionId, String)], otherwise: &str) {
+        let rules = [
+            (Tag::APP, Rule::App),
+            (Tag::DUP0, Rule::Dup),
+            (Tag::DUP1, Rule::Dup),
+            (Tag::BINARY, Rule::Op2),
+        ];
+
+        let mut cases = rules
+            .into_iter()
+            .map(|(tag, rule)| (Term::Tag(tag), Label(format!("{stage}_{rule}"))))
+            .collect::<Vec<_>>();
+        cases.push((Term::Tag(Tag::FUNCTION), Label(format!("{stage}_fun"))));
+
+        for rule in [Rule::App, Rule::Dup, Rule::Op2] {
+            self.new_block(&format!("{stage}_{rule}"), |bb| {
+                bb.terminator = cond(Term::Rule(stage, rule), "visit", otherwise);
+            });
+        }
+        self.build_functions(stage, functions, otherwise);
+
+        self.new_block(&stage.to_string(), |bb| {
+            bb.instructions.push(Instruction::LoadTerm);
+            bb.terminator = Terminator::Switch(Term::CurrentTag, cases, Label(otherwise.into()));
+        });
+    }
+
+    /// Builds the function applications of the stage, it switches over the function
+    /// id of the current term, and calls the compiled function of the matching
+    /// rule group. The functions that aren't compiled are reduced by the runtime.
+    fn build_functions(&mut self, stage: Stage, functions: &[(FunctionId, String)], otherwise: &str) {
+        let cases = functions
+            .iter()
+            .sorted_by_key(|(id, _)| *id)
+            .map(|(id, name)| {
+                let case_bb = self.new_block(&format!("{stage}_fun_{id}"), |bb| {
+                    bb.terminator = cond(Term::Call(stage, name.clone()), "visit", otherwise);
+                });
+
+                (Term::Id(*id), Label(case_bb.label))
+            })
+            .collect::<Vec<_>>();
+
+        let runtime_bb = self.new_block(&format!("{stage}_runtime_fun"), |bb| {
+            bb.terminator = cond(Term::Rule(stage, Rule::Fun), "visit", otherwise);
+        });
+
+        self.new_block(&format!("{stage}_fun"), |bb| {
+            bb.instructions.push(Instruction::SetFunctionId);
+            bb.terminator = Terminator::Switch(Term::FunctionId, cases, Label(runtime_bb.label));
+        });
+    }
+
+    pub fn new_block<F>(&mut self, name: &str, f: F) -> ReduceBlock
+    where
+        F: FnOnce(&mut ReduceBlock),
+    {
+        let mut bb = BasicBlock::new(name);
+        f(&mut bb);
+        self.basic_blocks.push((name.into(), bb.clone()));
+        bb
+    }
+}
+
+/// A conditional jump to the blocks with the given labels.
+fn cond(term: Term, then: &str, otherwise: &str) -> Terminator<Instruction> {
+    Terminator::Cond(term, Label(then.into()), Label(otherwise.into()))
+}
@@ -3,16 +3,17 @@ use std::ops::Deref;
 use hvm::rulebook::RuleBook;
 use hvm::{get_global_name_misc, hash};
 
+use crate::diagnostic::{Diagnostic, ErrorCode};
 use crate::ir::syntax;
 use crate::ir::syntax::*;
 
-pub type Result<T> = std::result::Result<T, String>;
+pub type Result<T> = std::result::Result<T, Diagnostic>;
 
 #[derive(Debug)]
 pub struct Variable {
     pub name: Option<String>,
     pub index: u64,
-    pub field_index: Option<u64>,
+    pub fields: Vec<u64>,
 }
 
 #[derive(Debug)]
@@ -20,6 +21,18 @@ pub struct Context {
     pub index: u64,
     pub variables: Vec<Variable>,
     pub book: RuleBook,
+
+    /// The rule group name, and the index of the current rule in the
+    /// group, used to report diagnostics.
+    pub name: String,
+    pub equation: usize,
+}
+
+impl Context {
+    /// Creates a [Diagnostic] for the current rule.
+    pub fn error(&self, code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::error(code, message).with_rule(&self.name, Some(self.equation))
+    }
 }
 
 pub trait Transform {
@@ -39,7 +52,9 @@ impl ContextTransform for hvm::syntax::Rule {
 
     fn transform(self, context: &mut Context) -> Result<Self::Output> {
         let hvm::Term::Ctr { name, args } = *self.lhs else {
-            return Err("rule is not a constructor".into());
+            return Err(context
+                .error(ErrorCode::InvalidRule, "the left-hand side of a rule must be a constructor")
+                .with_label(&self.lhs.to_string(), "expected a constructor"));
         };
         let rhs = *self.rhs;
 
@@ -47,6 +62,8 @@ impl ContextTransform for hvm::syntax::Rule {
             book: context.book.clone(),
             index: 0,
             variables: Vec::new(),
+            name: context.name.clone(),
+            equation: context.equation,
         };
 
         Ok(Rule {
@@ -65,24 +82,27 @@ impl ContextTransform for hvm::syntax::Term {
 
         match self {
             U6O { numb } => Ok(Term::U60(numb)),
-            F6O { numb } => Ok(Term::F60(numb as f64)),
+            F6O { numb } => Ok(Term::F60(hvm::runtime::f60::to_f64(numb))),
+            // Searches from the innermost binding, so shadowed variables
+            // are resolved to the last bound one.
             Var { name } => context
                 .variables
                 .iter()
-                .enumerate()
-                .find_map(|(index, variable)| {
-                    if variable.name == Some(name.clone()) {
-                        Some(Ok(Term::Atom(Atom {
-                            name: name.clone(),
-                            index: index as u64,
-                            field_index: variable.field_index,
-                        })))
-                    } else {
-                        None
-                    }
+                .rposition(|variable| variable.name.as_ref() == Some(&name))
+                .map(|index| {
+                    Term::Atom(Atom {
+                        name: name.clone(),
+                        index: index as u64,
+                        fields: context.variables[index].fields.clone(),
+                    })
                 })
-                .unwrap(),
+                .ok_or_else(|| {
+                    context
+                        .error(ErrorCode::UnboundVariable, format!("unbound variable `{name}`"))
+                        .with_label(&name, "not found in this scope")
+                }),
             Sup { box val0, box val1 } => Ok(Term::Super(Super {
+                label: Some(DEFAULT_LABEL.into()),
                 first: val0.transform(context)?.into(),
                 second: val1.transform(context)?.into(),
             })),
@@ -90,7 +110,7 @@ impl ContextTransform for hvm::syntax::Term {
                 context.variables.push(Variable {
                     name: Some(name.clone()),
                     index: 0,
-                    field_index: None,
+                    fields: vec![],
                 });
 
                 let erased = name == "*";
@@ -117,13 +137,16 @@ impl ContextTransform for hvm::syntax::Term {
                 callee: func.transform(context)?.into(),
                 arguments: vec![argm.transform(context)?],
             })),
+            Ctr { name, .. } if !context.book.name_to_id.contains_key(&name) => Err(context
+                .error(ErrorCode::UnknownConstructor, format!("unknown constructor `{name}`"))
+                .with_label(&name, "not declared in the rule book")),
             Ctr { name, args } => Ok(Term::App(syntax::App {
                 is_function: context.book.ctr_is_fun.contains_key(&name),
                 global_name: Some(name.clone()),
                 callee: Term::Atom(Atom {
                     name,
                     index: 0,
-                    field_index: None,
+                    fields: vec![],
                 })
                 .into(),
                 arguments: args
@@ -143,12 +166,13 @@ impl ContextTransform for hvm::syntax::Term {
                 context.variables.push(Variable {
                     name: Some(name.clone()),
                     index: 0,
-                    field_index: None,
+                    fields: vec![],
                 });
                 let body = body.transform(context)?;
                 context.variables.pop();
 
                 Ok(Term::Let(syntax::Let {
+                    erased: false,
                     name,
                     value: expr.into(),
                     body: body.into(),
@@ -159,12 +183,31 @@ impl ContextTransform for hvm::syntax::Term {
                 nam1,
                 box expr,
                 box body,
-            } => Ok(Term::Duplicate(Duplicate {
-                from: nam0,
-                to: nam1,
-                value: expr.transform(context)?.into(),
-                body: body.transform(context)?.into(),
-            })),
+            } => {
+                let expr = expr.transform(context)?;
+
+                context.variables.push(Variable {
+                    name: Some(nam0.clone()),
+                    index: 0,
+                    fields: vec![],
+                });
+                context.variables.push(Variable {
+                    name: Some(nam1.clone()),
+                    index: 0,
+                    fields: vec![],
+                });
+                let body = body.transform(context)?;
+                context.variables.pop();
+                context.variables.pop();
+
+                Ok(Term::Duplicate(Duplicate {
+                    label: Some(DEFAULT_LABEL.into()),
+                    from: nam0,
+                    to: nam1,
+                    value: expr.into(),
+                    body: body.into(),
+                }))
+            }
             Op2 {
                 oper,
                 box val0,
@@ -191,18 +234,22 @@ impl Transform for RuleBook {
 
 impl RuleGroup {
     pub fn specialize(name: String, book: &RuleBook) -> Result<Self> {
-        let (_id, group) = book
-            .rule_group
-            .get(&name)
-            .ok_or(format!("No such group: {name}"))?;
+        let (_id, group) = book.rule_group.get(&name).ok_or_else(|| {
+            Diagnostic::error(ErrorCode::UnknownRuleGroup, format!("no such rule group `{name}`"))
+        })?;
         let rules = group
             .iter()
-            .map(|rule| {
-                rule.clone().transform(&mut Context {
-                    book: book.clone(),
-                    index: 0,
-                    variables: Vec::new(),
-                })
+            .enumerate()
+            .map(|(equation, rule)| {
+                rule.clone()
+                    .transform(&mut Context {
+                        book: book.clone(),
+                        index: 0,
+                        variables: Vec::new(),
+                        name: name.clone(),
+                        equation,
+                    })
+                    .map(Rule::linearize)
             })
             .collect::<Result<Vec<_>>>()?;
 
@@ -244,7 +291,7 @@ fn specialize_parameters(
                 context.variables.push(Variable {
                     name: None,
                     index: context.index,
-                    field_index: None,
+                    fields: vec![],
                 });
 
                 Ok(Parameter::Erased)
@@ -253,7 +300,7 @@ fn specialize_parameters(
                 context.variables.push(Variable {
                     name: Some(name.clone()),
                     index: context.index,
-                    field_index: None,
+                    fields: vec![],
                 });
 
                 Ok(Parameter::Atom(name))
@@ -261,18 +308,25 @@ fn specialize_parameters(
             Ctr { name, args } => Ok(Parameter::Constructor(Constructor {
                 name,
                 arity: args.len() as u64,
-                flatten_patterns: specialize_flatten_patterns(args, index as u64, context)?,
+                flatten_patterns: specialize_flatten_patterns(args, index as u64, vec![], context)?,
             })),
             U6O { numb } => Ok(Parameter::U60(numb)),
-            _ => Err("Invalid pattern".into()),
+            F6O { numb } => Ok(Parameter::F60(hvm::runtime::f60::to_f64(numb))),
+            term => Err(context
+                .error(ErrorCode::InvalidPattern, "invalid pattern in the left-hand side of the rule")
+                .with_label(&term.to_string(), "expected a variable, a constructor or a number")),
         })
         .collect::<Result<_>>()
 }
 
+/// Specializes the nested patterns of the parameter `index`, the variables are
+/// bound with the path of `fields` to the nested constructor, so the deep
+/// patterns are resolved to their own field.
 #[allow(clippy::vec_box)]
 fn specialize_flatten_patterns(
     flatten_patterns: Vec<Box<hvm::syntax::Term>>,
     index: u64,
+    fields: Vec<u64>,
     context: &mut Context,
 ) -> Result<Vec<Pattern>> {
     use hvm::syntax::Term::*;
@@ -281,12 +335,18 @@ fn specialize_flatten_patterns(
         .iter()
         .map(Deref::deref)
         .enumerate()
-        .map(|(pattern_index, term)| match term {
+        .map(|(pattern_index, term)| {
+            let mut fields = fields.clone();
+            fields.push(pattern_index as u64);
+
+            (fields, term)
+        })
+        .map(|(fields, term)| match term {
             Var { name } if name == "*" => {
                 context.variables.push(Variable {
                     name: None,
                     index,
-                    field_index: Some(pattern_index as u64),
+                    fields,
                 });
 
                 Ok(Pattern::Erased)
@@ -295,12 +355,21 @@ fn specialize_flatten_patterns(
                 context.variables.push(Variable {
                     name: Some(name.clone()),
                     index,
-                    field_index: Some(pattern_index as u64),
+                    fields,
                 });
 
                 Ok(Pattern::Atom(name.clone()))
             }
-            _ => Err("Invalid flatten pattern".into()),
+            Ctr { name, args } => Ok(Pattern::Constructor(Constructor {
+                name: name.clone(),
+                arity: args.len() as u64,
+                flatten_patterns: specialize_flatten_patterns(args.clone(), index, fields, context)?,
+            })),
+            U6O { numb } => Ok(Pattern::U60(*numb)),
+            F6O { numb } => Ok(Pattern::F60(hvm::runtime::f60::to_f64(*numb))),
+            term => Err(context
+                .error(ErrorCode::InvalidPattern, "invalid nested pattern in the left-hand side of the rule")
+                .with_label(&term.to_string(), "expected a variable, a constructor or a number")),
         })
         .collect()
 }
@@ -15,7 +15,8 @@ impl Codegen {
         let mut bb = self.new_block("entry", move |this, bb| {
             bb.with_return(Term::False);
 
-            if !group.strict_parameters.is_empty() {
+            // Without strict arguments, there's nothing to reduce
+            if !group.strict_parameters.contains(&true) {
                 return;
             }
 
@@ -32,12 +33,12 @@ impl Codegen {
 
             let goup_bb = this.new_block("goup", |_, bb| {
                 bb.instructions.push(Instruction::SetGoup(Term::Redex));
-                for (index, is_strict) in group.strict_parameters.iter().enumerate() {
-                    if !is_strict {
-                        continue;
-                    }
 
-                    bb.instructions.push(Instruction::Visit(index as u64));
+                // The arguments that aren't in the WHNF form are pushed in the
+                // vbuf, so it has at most one slot per strict argument.
+                let strict_count = group.strict_parameters.iter().filter(|is_strict| **is_strict).count();
+                for slot in 0..strict_count {
+                    bb.instructions.push(Instruction::Visit(slot as u64));
                 }
                 bb.instructions.push(Instruction::UpdateCont);
                 bb.instructions.push(Instruction::UpdateHost);
@@ -0,0 +1,439 @@
+//! Compiler diagnostics, reported by the code generation stages.
+//!
+//! The HVM syntax tree doesn't carry any source location, so the diagnostics
+//! are created with the rule name, the equation index inside the rule group,
+//! and the text of the offending piece of code, the `label`. The [Span] is
+//! recovered later by [Source::locate], when the diagnostic is rendered.
+
+use std::fmt::{Display, Formatter};
+
+use colored::Colorize;
+
+/// The severity of a [Diagnostic].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The error codes of the compiler, each one can be rendered as
+/// `E0001`, in the same way as rustc does. The warnings are rendered
+/// as `W0001`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The file could not be parsed by HVM.
+    ParseError,
+
+    /// The left-hand side of a rule is not a constructor.
+    InvalidRule,
+
+    /// A parameter, or a nested pattern, in the left-hand side of a rule
+    /// is not supported.
+    InvalidPattern,
+
+    /// A variable is used, but it is not bound.
+    UnboundVariable,
+
+    /// A constructor, or a function, is not declared in the rule book.
+    UnknownConstructor,
+
+    /// A rule group is not declared in the rule book.
+    UnknownRuleGroup,
+
+    /// A rule group has no rules.
+    EmptyRuleGroup,
+
+    /// The backend could not compile the program.
+    Backend,
+
+    /// The codegen generated an IR that doesn't pass the verifier,
+    /// it's a bug in the compiler.
+    InvalidIr,
+
+    /// A rule group doesn't match every possible argument.
+    NonExhaustive,
+
+    /// A rule is shadowed by the previous rules of the group.
+    UnreachableRule,
+}
+
+/// A range of bytes in the source code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A compiler diagnostic, with its [ErrorCode], the rule where it happened,
+/// and the text of the offending code.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+
+    /// The rule group name, where the diagnostic happened.
+    pub rule: Option<String>,
+
+    /// The index of the equation, in the rule group.
+    pub equation: Option<usize>,
+
+    /// The text that the diagnostic points to, and the message that is
+    /// displayed below it.
+    pub label: Option<(String, String)>,
+
+    /// The location of the diagnostic in the source code, if it's already known.
+    pub span: Option<Span>,
+
+    pub notes: Vec<String>,
+}
+
+/// A source file, used to locate and render the [Diagnostic]s.
+pub struct Source<'a> {
+    pub name: &'a str,
+    pub code: &'a str,
+}
+
+impl ErrorCode {
+    /// Returns the code to be displayed, like `E0001`.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::ParseError => "E0000",
+            ErrorCode::InvalidRule => "E0001",
+            ErrorCode::InvalidPattern => "E0002",
+            ErrorCode::UnboundVariable => "E0003",
+            ErrorCode::UnknownConstructor => "E0004",
+            ErrorCode::UnknownRuleGroup => "E0005",
+            ErrorCode::EmptyRuleGroup => "E0006",
+            ErrorCode::Backend => "E0007",
+            ErrorCode::InvalidIr => "E0008",
+            ErrorCode::NonExhaustive => "W0001",
+            ErrorCode::UnreachableRule => "W0002",
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Creates a new error diagnostic, with the given code and message.
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            rule: None,
+            equation: None,
+            label: None,
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Creates a new warning diagnostic, with the given code and message.
+    pub fn warning(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message)
+        }
+    }
+
+    /// Sets the rule group name, and the equation index, where the diagnostic happened.
+    pub fn with_rule(mut self, rule: &str, equation: Option<usize>) -> Self {
+        self.rule = Some(rule.into());
+        self.equation = equation;
+        self
+    }
+
+    /// Sets the text that the diagnostic points to, with a message.
+    pub fn with_label(mut self, text: &str, message: impl Into<String>) -> Self {
+        self.label = Some((text.into(), message.into()));
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic in the rustc style, with the line of code
+    /// where it happened, if it can be located.
+    pub fn render(&self, source: &Source) -> String {
+        let mut out = String::new();
+
+        let severity = match self.severity {
+            Severity::Error => format!("error[{}]", self.code.code()).red().bold(),
+            Severity::Warning => format!("warning[{}]", self.code.code()).yellow().bold(),
+        };
+        out.push_str(&format!("{severity}{} {}\n", ":".bold(), self.message.bold()));
+
+        let span = self.span.or_else(|| source.locate(self));
+        match span {
+            Some(span) => {
+                let (line, column) = source.line_column(span.start);
+                let text = source.code.lines().nth(line - 1).unwrap_or_default();
+                let gutter = " ".repeat(line.to_string().len());
+                let length = (span.end - span.start).max(1);
+                let message = self.label.as_ref().map(|(_, message)| message.as_str());
+
+                out.push_str(&format!(
+                    "{gutter}{} {}:{line}:{column}\n",
+                    "-->".blue().bold(),
+                    source.name
+                ));
+                out.push_str(&format!("{gutter} {}\n", "|".blue().bold()));
+                out.push_str(&format!("{} {} {text}\n", line.to_string().blue().bold(), "|".blue().bold()));
+                out.push_str(&format!(
+                    "{gutter} {} {}{} {}\n",
+                    "|".blue().bold(),
+                    " ".repeat(column - 1),
+                    "^".repeat(length).red().bold(),
+                    message.unwrap_or_default().red().bold(),
+                ));
+                self.render_notes(&gutter, &mut out);
+            }
+            None => {
+                out.push_str(&format!("{} {}\n", "-->".blue().bold(), source.name));
+                self.render_notes("", &mut out);
+            }
+        }
+
+        out
+    }
+
+    fn render_notes(&self, gutter: &str, out: &mut String) {
+        if let Some(rule) = &self.rule {
+            let note = format!("in the rule `{rule}`");
+            out.push_str(&format!("{gutter} {} {} {note}\n", "=".blue().bold(), "note:".bold()));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("{gutter} {} {} {note}\n", "=".blue().bold(), "note:".bold()));
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}[{}]: {}", self.code.code(), self.message)?;
+        if let Some(rule) = &self.rule {
+            write!(f, " (in the rule `{rule}`)")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Source<'a> {
+    pub fn new(name: &'a str, code: &'a str) -> Self {
+        Self { name, code }
+    }
+
+    /// Locates the diagnostic in the source code, searching for the equation of
+    /// the rule, and then for the label's text inside the equation. If the label
+    /// can't be found, it points to the rule's name.
+    pub fn locate(&self, diagnostic: &Diagnostic) -> Option<Span> {
+        let rule = diagnostic.rule.as_ref()?;
+        let equation = self.locate_equation(rule, diagnostic.equation.unwrap_or(0))?;
+
+        let label = diagnostic.label.as_ref().and_then(|(text, _)| {
+            let start = find_word(&self.code[equation.start..equation.end], text)?;
+            Some((equation.start + start, text.len()))
+        });
+
+        // Skips the `(` of the rule's left-hand side
+        let (start, length) = label.unwrap_or((equation.start + 1, rule.len()));
+
+        Some(Span {
+            start,
+            end: start + length,
+        })
+    }
+
+    /// Locates the n-th equation of the given rule, an equation starts in a line
+    /// that begins with `(Name`, and ends in the next line that starts with `(`.
+    /// The indentation of the lines is skipped.
+    pub fn locate_equation(&self, rule: &str, equation: usize) -> Option<Span> {
+        let header = format!("({rule}");
+
+        let mut offset = 0;
+        let mut starts = Vec::new();
+        for line in self.code.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('(') {
+                let start = offset + line.len() - trimmed.len();
+                starts.push((start, trimmed.starts_with(&header) && is_boundary(trimmed, header.len())));
+            }
+            offset += line.len();
+        }
+
+        let (index, (start, _)) = starts
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, is_rule))| *is_rule)
+            .nth(equation)?;
+        let end = starts
+            .get(index + 1)
+            .map(|(start, _)| *start)
+            .unwrap_or(self.code.len());
+
+        Some(Span { start: *start, end })
+    }
+
+    /// Returns the line and the column of the given offset, both starting in 1.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let before = &self.code[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rfind('\n').map(|i| offset - i).unwrap_or(offset + 1);
+
+        (line, column)
+    }
+}
+
+/// Renders every diagnostic of the list, separated by a blank line.
+pub fn render_all(diagnostics: &[Diagnostic], source: &Source) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(source))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Finds the first occurrence of `word`, that isn't part of another name.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    text.match_indices(word)
+        .map(|(index, _)| index)
+        .find(|index| {
+            let before = text[..*index].chars().next_back();
+            !before.map(is_name_char).unwrap_or(false) && is_boundary(text, index + word.len())
+        })
+}
+
+fn is_boundary(text: &str, index: usize) -> bool {
+    !text[index..].chars().next().map(is_name_char).unwrap_or(false)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use hvm::rulebook::RuleBook;
+
+    use super::*;
+    use crate::cli::eval::{ir_codegen_book, parse_book, setup_global_context};
+    use crate::passes::PassManager;
+
+    const CODE: &str = "(Add (Succ a) b) = (Succ (Add a b))\n(Add  Zero    b) =  c\n";
+
+    #[test]
+    fn it_locates_labels() {
+        let source = Source::new("example.hvm", CODE);
+        let diagnostic = Diagnostic::error(ErrorCode::UnboundVariable, "unbound variable `c`")
+            .with_rule("Add", Some(1))
+            .with_label("c", "not found in this scope");
+
+        let span = source.locate(&diagnostic).unwrap();
+
+        assert_eq!(&CODE[span.start..span.end], "c");
+        assert_eq!(source.line_column(span.start), (2, 21));
+    }
+
+    #[test]
+    fn it_skips_partial_names() {
+        let source = Source::new("example.hvm", CODE);
+        let diagnostic = Diagnostic::error(ErrorCode::UnknownConstructor, "unknown constructor")
+            .with_rule("Add", Some(0))
+            .with_label("Succ", "");
+
+        let span = source.locate(&diagnostic).unwrap();
+
+        assert_eq!(source.line_column(span.start), (1, 6));
+        assert!(source.locate_equation("Ad", 0).is_none());
+    }
+
+    #[test]
+    fn it_locates_indented_equations() {
+        let code = "\n    (Add (Succ a) b) = (Succ (Add a b))\n    (Add Zero b) = b\n";
+        let source = Source::new("example.hvm", code);
+
+        let span = source.locate_equation("Add", 1).unwrap();
+
+        assert_eq!(&code[span.start..span.end], "(Add Zero b) = b\n");
+    }
+
+    /// Generates the IR of the code, after the rule book is changed by `f`,
+    /// returning the diagnostics.
+    fn codegen_diagnostics<F: FnOnce(&mut RuleBook)>(code: &str, f: F) -> Vec<Diagnostic> {
+        let source = Source::new("example.hvm", code);
+        let mut book = parse_book(&source);
+        f(&mut book);
+
+        let global = setup_global_context(&book);
+        match ir_codegen_book(&book, global, &PassManager::default()) {
+            Ok(_) => vec![],
+            Err(diagnostics) => diagnostics,
+        }
+    }
+
+    /// Asserts that the only diagnostic has the code, and that it's located in
+    /// the second equation of `Foo`.
+    fn assert_located(code: &str, diagnostics: &[Diagnostic], error_code: ErrorCode) -> Span {
+        let source = Source::new("example.hvm", code);
+        let [diagnostic] = diagnostics else {
+            panic!("expected one diagnostic, got {diagnostics:?}");
+        };
+        assert_eq!(diagnostic.code, error_code);
+
+        let span = source.locate(diagnostic).unwrap();
+        let equation = source.locate_equation("Foo", 1).unwrap();
+        assert!(equation.start <= span.start && span.end <= equation.end);
+
+        span
+    }
+
+    #[test]
+    fn it_reports_the_unbound_variables() {
+        let code = "
+            (Foo (Zero)) = 0
+            (Foo x) = y
+            (Main) = (Foo 1)
+        ";
+        let diagnostics = codegen_diagnostics(code, |_| {});
+
+        let span = assert_located(code, &diagnostics, ErrorCode::UnboundVariable);
+        assert_eq!(&code[span.start..span.end], "y");
+    }
+
+    #[test]
+    fn it_reports_the_unknown_constructors() {
+        let code = "
+            (Foo (Zero)) = 0
+            (Foo x) = (Bar x)
+            (Main) = (Foo 1)
+        ";
+        let diagnostics = codegen_diagnostics(code, |book| {
+            book.name_to_id.remove("Bar");
+        });
+
+        let span = assert_located(code, &diagnostics, ErrorCode::UnknownConstructor);
+        assert_eq!(&code[span.start..span.end], "Bar");
+    }
+
+    #[test]
+    fn it_reports_the_invalid_patterns() {
+        let code = "
+            (Foo (Zero)) = 0
+            (Foo @x x) = 1
+            (Main) = (Foo 1)
+        ";
+        let diagnostics = codegen_diagnostics(code, |_| {});
+
+        assert_located(code, &diagnostics, ErrorCode::InvalidPattern);
+    }
+}
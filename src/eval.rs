@@ -19,6 +19,10 @@ pub struct Context {
 
     /// Local variables evaluation context.
     pub variables: FxHashMap<String, Object>,
+
+    /// The label of the block that jumped to the current one, used to select the
+    /// incoming variables of the phi nodes.
+    pub predecessor: Option<String>,
 }
 
 /// The `Control` enum is used to control the evaluation of the HVM terms.
@@ -29,6 +33,10 @@ pub enum Control {
 
     /// Continues the evaluation.
     Continue,
+
+    /// Jumps to a block that isn't declared in the current basic block, it's
+    /// handled by the ancestor that declares it, like a join block.
+    Jump(String),
 }
 
 /// The `Eval` trait is used to evaluate the HVM terms.
@@ -43,6 +51,7 @@ impl Context {
         Self {
             reduce,
             variables: FxHashMap::default(),
+            predecessor: None,
         }
     }
 }
@@ -1,13 +1,15 @@
 use crate::eval::{Context, Control, Eval, Object};
 use crate::ir::apply::{
-    build_binary_op, Agent, Alloc, Block, Free, FunctionId, GetExt, GetNumber, GetPosition, GetTag,
-    If, Instruction, Let, Link, LoadArgument, Position, Tag, Term, Value, U60,
+    build_binary_op, Agent, Alloc, ArityOf, Binary, Block, Collect, Color, Free, FunctionId, GetExt, GetNumber,
+    GetPosition, GetTag, If, Instruction, Let, Link, LoadArgument, Position, Select, Tag,
+    TakeArgument, Term, Value, F60, U60,
 };
 use crate::runtime::{
-    hvm__alloc, hvm__create_app, hvm__create_binary, hvm__create_constructor, hvm__create_erased,
-    hvm__create_function, hvm__create_lam, hvm__create_u60, hvm__create_var, hvm__free,
-    hvm__get_ext, hvm__get_host, hvm__get_loc, hvm__get_number, hvm__get_tag, hvm__get_term,
-    hvm__increment_cost, hvm__link, hvm__load_argument,
+    hvm__alloc, hvm__arity_of, hvm__collect, hvm__create_app, hvm__create_binary, hvm__create_constructor, hvm__create_dp0,
+    hvm__create_dp1, hvm__create_erased, hvm__create_f60, hvm__create_function, hvm__create_lam,
+    hvm__create_super, hvm__create_u60, hvm__create_var, hvm__free, hvm__get_ext, hvm__get_host,
+    hvm__get_loc, hvm__get_number, hvm__get_tag, hvm__get_term, hvm__increment_cost, hvm__link,
+    hvm__load_argument, hvm__operate, hvm__take_argument,
 };
 
 impl Eval for Position {
@@ -45,9 +47,22 @@ impl Eval for Term {
                 Term::Tag(Tag::FUNCTION) => Object::U64(hvm::FUN),
                 Term::Tag(Tag::CONSTRUCTOR) => Object::U64(hvm::CTR),
                 Term::Tag(..) => todo!(),
-                Term::ArityOf(_) => todo!(),
+                Term::ArityOf(ArityOf { box term }) => {
+                    let term = term.eval(context).as_u64();
+
+                    Object::U64(hvm__arity_of(context.reduce, term))
+                }
                 Term::Ext(id, ..) => Object::U64(id),
-                Term::TakeArgument(_) => todo!(),
+                Term::Int(value) => Object::U64(value),
+                Term::TakeArgument(TakeArgument {
+                    position,
+                    box argument_index,
+                }) => {
+                    let position = position.eval(context);
+                    let argument_index = argument_index.eval(context).as_u64();
+
+                    Object::U64(hvm__take_argument(context.reduce, position, argument_index))
+                }
                 Term::NotFound(atom) => {
                     panic!("Atom not found: ({:?})", atom)
                 }
@@ -57,7 +72,7 @@ impl Eval for Term {
                 Term::Alloc(Alloc { size }) => Object::U64(hvm__alloc(context.reduce, size)),
                 Term::Agent(Agent { arguments, .. }) => {
                     let name = format!("agent_{}", context.variables.len() + 1);
-                    let value = hvm__alloc(context.reduce, 1);
+                    let value = hvm__alloc(context.reduce, arguments.len() as u64);
                     context
                         .variables
                         .insert(name.clone(), Object::U64(value.clone()));
@@ -97,6 +112,7 @@ impl Eval for Term {
                 }
                 Term::Create(Value::Erased) => Object::U64(hvm__create_erased()),
                 Term::Create(Value::U60(U60(value))) => Object::U64(hvm__create_u60(value)),
+                Term::Create(Value::F60(F60(value))) => Object::U64(hvm__create_f60(value)),
                 Term::Create(Value::Function(FunctionId(_, id), position)) => {
                     Object::U64(hvm__create_function(id, position.eval(context)))
                 }
@@ -112,11 +128,37 @@ impl Eval for Term {
                 Term::Create(Value::App(position)) => {
                     Object::U64(hvm__create_app(position.eval(context)))
                 }
+                Term::Create(Value::Dp0(Color(color), position)) => {
+                    Object::U64(hvm__create_dp0(color, position.eval(context)))
+                }
+                Term::Create(Value::Dp1(Color(color), position)) => {
+                    Object::U64(hvm__create_dp1(color, position.eval(context)))
+                }
+                Term::Create(Value::Super(Color(color), position)) => {
+                    Object::U64(hvm__create_super(color, position.eval(context)))
+                }
                 Term::Create(Value::Binary(binary, position)) => {
                     let operand = build_binary_op(binary.op);
 
                     Object::U64(hvm__create_binary(operand, position.eval(context)))
                 }
+                Term::Operate(Binary { box lhs, op, box rhs }) => {
+                    let lhs = lhs.eval(context).as_u64();
+                    let rhs = rhs.eval(context).as_u64();
+
+                    Object::U64(hvm__operate(build_binary_op(op), lhs, rhs))
+                }
+                Term::Select(Select {
+                    box condition,
+                    box then,
+                    box otherwise,
+                }) => {
+                    if condition.eval(context).as_bool() {
+                        then.eval(context)
+                    } else {
+                        otherwise.eval(context)
+                    }
+                }
                 Term::Ref(name) => context
                     .variables
                     .get(&name)
@@ -124,20 +166,18 @@ impl Eval for Term {
                     .clone(),
                 Term::LogicalOr(box lhs, box rhs) => {
                     let lhs = lhs.eval(context);
-                    let rhs = rhs.eval(context);
 
                     if lhs.as_bool() {
                         lhs
                     } else {
-                        rhs
+                        rhs.eval(context)
                     }
                 }
                 Term::LogicalAnd(box lhs, box rhs) => {
                     let lhs = lhs.eval(context);
-                    let rhs = rhs.eval(context);
 
-                    if lhs.as_bool() && rhs.as_bool() {
-                        lhs
+                    if lhs.as_bool() {
+                        rhs.eval(context)
                     } else {
                         Object::Bool(false)
                     }
@@ -156,27 +196,26 @@ impl Eval for Instruction {
     fn eval(self, context: &mut Context) -> Self::Output {
         unsafe {
             match self {
-                Instruction::Collect(_) => {}
+                Instruction::Collect(Collect { term }) => {
+                    let term = term.eval(context).as_u64();
+
+                    hvm__collect(context.reduce, term)
+                }
                 Instruction::If(If {
                     condition,
                     then,
                     otherwise,
                 }) => {
+                    // The branches share the context, as they can assign the
+                    // variables declared before the condition.
                     let condition = condition.eval(context).as_bool();
-                    if condition {
-                        let mut then_context = context.clone();
-                        for instruction in then.block {
-                            if let Control::Break(value) = instruction.eval(&mut then_context) {
-                                return Control::Break(value);
-                            }
-                        }
-                    } else if let Some(otherwise) = otherwise {
-                        let mut otherwise_context = context.clone();
-                        for instruction in otherwise.block {
-                            if let Control::Break(value) = instruction.eval(&mut otherwise_context)
-                            {
-                                return Control::Break(value);
-                            }
+                    let block = match condition {
+                        true => Some(then),
+                        false => otherwise,
+                    };
+                    for instruction in block.into_iter().flat_map(|block| block.block) {
+                        if let Control::Break(value) = instruction.eval(context) {
+                            return Control::Break(value);
                         }
                     }
                 }
@@ -9,56 +9,68 @@ where
     type Output = Control;
 
     fn eval(self, context: &mut Context) -> Self::Output {
+        // The phi nodes are evaluated together, as they can use each other
+        let values = self
+            .phis
+            .iter()
+            .map(|phi| {
+                let predecessor = context.predecessor.as_deref();
+                let (_, name) = phi
+                    .incoming
+                    .iter()
+                    .find(|(Label(label), _)| Some(label.as_str()) == predecessor)
+                    .unwrap_or_else(|| panic!("the phi `%{}` has no incoming variable from {predecessor:?}", phi.name));
+
+                (phi.name.clone(), context.variables[name].clone())
+            })
+            .collect::<Vec<_>>();
+        context.variables.extend(values);
+
         for instruction in self.instructions {
             instruction.eval(context);
         }
 
-        match self.terminator {
+        let Label(mut label) = match self.terminator {
             Terminator::Unreachable => {
                 panic!("Unreachable");
             }
             Terminator::Debug(message) => {
                 println!("{message}");
-                Control::Break(Object::Bool(false))
-            }
-            Terminator::Return(value) => Control::Break(value.eval(context)),
-            Terminator::Jump(Label(label)) => {
-                let branch = self
-                    .declared_blocks
-                    .get(&label)
-                    .expect("could not find then branch")
-                    .clone();
-
-                let Control::Break(value) = branch.eval(context) else {
-                    panic!("The program did not finished correctly.")
-                };
-
-                Control::Break(value)
+                return Control::Break(Object::Bool(false));
             }
-            Terminator::Cond(cond, Label(then), Label(otherwise)) => {
-                let then = self
-                    .declared_blocks
-                    .get(&then)
-                    .expect("could not find then branch.")
-                    .clone();
-                let otherwise = self
-                    .declared_blocks
-                    .get(&otherwise)
-                    .expect("could not find otherwise branch.")
-                    .clone();
-
+            Terminator::Return(value) => return Control::Break(value.eval(context)),
+            Terminator::Jump(label) => label,
+            Terminator::Cond(cond, then, otherwise) => {
                 if cond.eval(context).as_bool() {
-                    let Control::Break(value) = then.eval(context) else {
-                        panic!("the program did not finished correctly.")
-                    };
-
-                    Control::Break(value)
-                } else if let Control::Break(value) = otherwise.eval(context) {
-                    Control::Break(value)
+                    then
                 } else {
-                    panic!("the program did not finished correctly.")
+                    otherwise
                 }
             }
+            Terminator::Switch(term, cases, default) => {
+                let value = term.eval(context);
+                cases
+                    .into_iter()
+                    .find(|(case, _)| case.clone().eval(context) == value)
+                    .map(|(_, label)| label)
+                    .unwrap_or(default)
+            }
+        };
+
+        context.predecessor = Some(self.label.clone());
+
+        // The jumps to the blocks declared by the ancestors, like the join
+        // blocks, are returned to them.
+        loop {
+            let Some(branch) = self.declared_blocks.get(&label) else {
+                return Control::Jump(label);
+            };
+
+            match branch.clone().eval(context) {
+                Control::Jump(target) => label = target,
+                Control::Continue => panic!("the program did not finished correctly."),
+                control => return control,
+            }
         }
     }
 }
@@ -32,32 +32,22 @@ impl Eval for Instruction {
                 }
                 Instruction::UpdateHost => {
                     let variables = &mut context.variables;
-                    let mut vbuf = variables
-                        .get("vbuf")
-                        .expect("vbuf not found")
-                        .as_ptr::<&'static [AtomicU64]>()
-                        .as_ref()
-                        .unwrap();
+                    let vbuf = variables.get("vbuf").expect("vbuf not found").as_ptr::<AtomicU64>();
                     let vlen = variables.get("vlen").expect("vlen not found").as_u64();
 
-                    hvm__update_host(context.reduce, &mut vbuf as *const _ as *mut _, vlen);
+                    hvm__update_host(context.reduce, vbuf, vlen);
                 }
                 Instruction::IncreaseLen(parameter_index) => {
                     let variables = &mut context.variables;
 
-                    let mut vbuf = variables
-                        .get("vbuf")
-                        .expect("vbuf not found")
-                        .as_ptr::<&'static [AtomicU64]>()
-                        .as_ref()
-                        .unwrap();
+                    let vbuf = variables.get("vbuf").expect("vbuf not found").as_ptr::<AtomicU64>();
 
                     let vlen = variables.get("vlen").expect("vlen not found").as_u64();
 
                     let new_vlen = hvm__increase_vlen(
                         context.reduce,
                         parameter_index,
-                        &mut vbuf as *const _ as *mut _,
+                        vbuf,
                         vlen,
                     );
 
@@ -66,12 +56,7 @@ impl Eval for Instruction {
                 Instruction::Visit(parameter_index) => {
                     let variables = &mut context.variables;
 
-                    let mut vbuf = variables
-                        .get("vbuf")
-                        .expect("vbuf not found")
-                        .as_ptr::<&'static [AtomicU64]>()
-                        .as_ref()
-                        .unwrap();
+                    let vbuf = variables.get("vbuf").expect("vbuf not found").as_ptr::<AtomicU64>();
 
                     let vlen = variables.get("vlen").expect("vlen not found").as_u64();
                     let goup = variables.get("goup").expect("goup not found").as_u64();
@@ -80,7 +65,7 @@ impl Eval for Instruction {
                         context.reduce,
                         parameter_index,
                         goup,
-                        &mut vbuf as *const _ as *mut _,
+                        vbuf,
                         vlen,
                     );
                 }
@@ -98,8 +83,8 @@ impl Eval for Term {
                 Term::True => Object::Bool(true),
                 Term::False => Object::Bool(false),
                 Term::CreateVBuf => {
-                    let vbuf = hvm__create_vbuf(context.reduce) as *const _ as *mut _;
-                    Object::Pointer(vbuf)
+                    let vbuf = hvm__create_vbuf(context.reduce);
+                    Object::Pointer(vbuf as *mut libc::c_void)
                 }
                 Term::Redex => {
                     let vlen = context
@@ -2,136 +2,310 @@ use std::sync::Arc;
 
 use fxhash::FxHashMap;
 use hvm::rulebook::RuleBook;
-use hvm::{Precomp, PrecompFuns, ReduceCtx, PRECOMP};
-use inkwell::targets::{InitializationConfig, Target};
-use inkwell::OptimizationLevel;
-use itertools::Itertools;
+use hvm::runtime::Program;
+use hvm::{PrecompFuns, ReduceCtx};
 
 use crate::eval::{Context, Control, Eval};
+use crate::hvm::precomp::install_precomp;
 use crate::ir::rule::RuleGroup;
-use crate::llvm::bridge::Bridge;
 
 pub mod llvm;
+pub mod precomp;
+pub mod reducer;
 
-type StrictMap = &'static [bool];
-
-pub fn setup_precomp(book: RuleBook, groups: FxHashMap<String, RuleGroup>) {
-    let mut precomp = PRECOMP
-        .clone()
-        .iter()
-        .map(|precomp| (precomp.id, precomp.clone()))
-        .collect::<FxHashMap<_, _>>();
-
-    Target::initialize_native(&InitializationConfig::default()).unwrap();
-
-    for (id, name) in itertools::sorted(book.id_to_name.iter()) {
-        let smap = book.id_to_smap.get(id).unwrap().clone().leak();
-        if *id <= 29 {
-            // Skip built-in constructors
-            continue;
-        }
-
-        let compiled_function = match groups.get(name) {
-            Some(group) => create_precomp(*id, smap, group.clone()),
-            // Interpreted function
-            None => Precomp {
-                id: *id,
-                name: name.clone().leak(),
-                smap,
-                funs: None,
-            },
-        };
+/// Installs the rule groups in the global precomp table, their functions are
+/// evaluated with the eval backend.
+pub fn setup_precomp(book: &RuleBook, groups: FxHashMap<String, RuleGroup>) {
+    install_precomp(book, |id| {
+        let group = groups.get(&book.id_to_name[&id])?;
 
-        precomp.insert(*id, compiled_function);
-    }
+        Some(create_precomp_funs(group.clone()))
+    });
+}
+
+/// Creates the program of the book, with the functions of the precomp table, so
+/// it must be created after they're installed.
+pub fn create_program(book: &RuleBook) -> Program {
+    let mut prog = Program::new();
+    prog.add_book(book);
+    prog
+}
 
-    unsafe {
-        let reordered = precomp
-            .iter()
-            .sorted_by_key(|(id, _)| *id)
-            .map(|(_, precomp)| precomp.clone())
-            .collect::<Vec<_>>();
+/// Creates the [PrecompFuns] of the rule group, its functions evaluate the visit
+/// and the apply graphs of the group, with the eval backend.
+pub fn create_precomp_funs(group: RuleGroup) -> PrecompFuns {
+    let group = Arc::new(group);
+    let visit_group = group.clone();
 
-        *Arc::get_mut_unchecked(&mut PRECOMP.clone()) = Box::new(reordered);
+    PrecompFuns {
+        apply: Arc::new(move |mut ctx| {
+            let mut context = Context::new(&mut ctx as *mut ReduceCtx as *mut _);
+            let Control::Break(done) = group.hvm_apply_graph.clone().eval(&mut context) else {
+                panic!("The program did not finished correctly.")
+            };
+            done.as_bool()
+        }),
+        visit: Arc::new(move |mut ctx| {
+            let mut context = Context::new(&mut ctx as *mut ReduceCtx as *mut _);
+            let Control::Break(done) = visit_group.hvm_visit.clone().eval(&mut context) else {
+                panic!("The program did not finished correctly.")
+            };
+            done.as_bool()
+        }),
     }
 }
 
-pub fn create_precomp(id: u64, smap: StrictMap, group: RuleGroup) -> Precomp {
-    let name = group.name.clone();
-    let hvm_apply = group.hvm_apply.clone();
-    let hvm_visit = group.hvm_visit.clone();
-    let cfg = hvm_apply.clone().into_control_flow_graph();
-
-    println!("[debug] apply: {:?}", group.name);
-    println!("[debug]   ir =");
-    println!("{}", cfg);
-
-    unsafe {
-        use std::mem::transmute;
-
-        let group = Box::leak(Box::new(group));
-
-        let context = inkwell::context::Context::create();
-        let bridge = Bridge::new(&context);
-
-        let visit_fn = bridge.create(visit_fn, &format!("{}__visit", name), group);
-        let apply_fn = bridge.create(apply_fn, &format!("{}__apply", name), group);
-
-        let engine = bridge
-            .module
-            .create_jit_execution_engine(OptimizationLevel::None)
-            .unwrap();
-
-        let _apply_fn: fn(*mut ReduceCtx) -> bool =
-            transmute(engine.get_function_address(&apply_fn).unwrap());
-
-        let _visit_fn: fn(*mut ReduceCtx) -> bool =
-            transmute(engine.get_function_address(&visit_fn).unwrap());
-
-        Precomp {
-            id,
-            name: name.leak(),
-            funs: Some(PrecompFuns {
-                apply: Arc::new(move |mut ctx| {
-                    // apply_fn(&mut ctx as *const _ as *mut _)
-                    let mut context = Context::new(&mut ctx as *const _ as *mut ReduceCtx);
-                    let Control::Break(done) = hvm_apply.clone().into_control_flow_graph().eval(&mut context) else {
-                        panic!("the program did not finished correctly.")
-                    };
-                    done.as_bool()
-                }),
-                visit: Arc::new(move |mut ctx| {
-                    // visit_fn(&mut ctx as *const _ as *mut _)
-                    let mut context = Context::new(&mut ctx as *const _ as *mut ReduceCtx);
-                    let Control::Break(done) = hvm_visit.clone().eval(&mut context) else {
-                        panic!("the program did not finished correctly.")
-                    };
-                    done.as_bool()
-                }),
-            }),
-            smap,
-        }
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use fxhash::FxHashMap;
+    use hvm::rulebook::RuleBook;
+
+    use crate::cli::eval::{ir_codegen_book, parse_book, setup_global_context};
+    use crate::diagnostic::Source;
+    use crate::hvm::reducer::Reducer;
+    use crate::ir::rule::RuleGroup;
+    use crate::llvm::optimize::Optimizer;
+    use crate::passes::PassManager;
+
+    /// The precomp table is global, so the programs are evaluated one at a time.
+    static PRECOMP_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Evaluates the `Main` of the program, with the eval backend, returning
+    /// its normal form.
+    pub fn eval_main(code: &str) -> String {
+        run_main(code, setup_eval).0
+    }
+
+    /// Evaluates the `Main` of the program, with the LLVM backend, returning
+    /// its normal form.
+    fn llvm_main(code: &str) -> String {
+        run_main(code, setup_llvm).0
+    }
+
+    /// Installs the rule groups in the precomp table, and creates the reducer
+    /// of HVM, that calls their functions.
+    fn setup_eval(book: &RuleBook, groups: FxHashMap<String, RuleGroup>) -> Reducer {
+        super::setup_precomp(book, groups);
+        create_reducer(book)
+    }
+
+    /// Compiles the rule groups, and creates the reducer that enters through
+    /// the generated reduce function.
+    fn setup_llvm(book: &RuleBook, groups: FxHashMap<String, RuleGroup>) -> Reducer {
+        let optimizer = Optimizer::new(0, None).unwrap();
+        let reduce = super::llvm::setup_llvm_reduce(book, groups, &optimizer).unwrap();
+        create_reducer(book).with_reduce(reduce)
+    }
+
+    fn create_reducer(book: &RuleBook) -> Reducer {
+        let heap_size = hvm::runtime::default_heap_size();
+        let thread_ids = hvm::runtime::default_heap_tids();
+        Reducer::new(super::create_program(book), heap_size, thread_ids)
+    }
+
+    /// Evaluates the `Main` of the program, with the reducer created by the given
+    /// function, returning its normal form, and the reducer.
+    fn run_main<F>(code: &str, setup: F) -> (String, Reducer)
+    where
+        F: FnOnce(&RuleBook, FxHashMap<String, RuleGroup>) -> Reducer,
+    {
+        let _guard = PRECOMP_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+        let source = Source::new("main.hvm", code);
+        let book = parse_book(&source);
+        let global = setup_global_context(&book);
+        let groups = ir_codegen_book(&book, global, &PassManager::default())
+            .unwrap_or_else(|diagnostics| panic!("{diagnostics:?}"));
+        let reducer = setup(&book, groups);
+
+        let (norm, _, _) = reducer.eval(book.name_to_id["Main"]);
+        (norm, reducer)
+    }
+
+    #[test]
+    fn it_annihilates_the_user_superpositions() {
+        let norm = eval_main(
+            r#"
+            (Main) = dup a b = {1 2}; (Pair a b)
+            "#,
+        );
+
+        assert_eq!(norm, "(Pair 1 2)");
+    }
+
+    #[test]
+    fn it_matches_the_f60_literals() {
+        let norm = eval_main(
+            r#"
+            (Half 1.5) = 1
+            (Half x) = 0
+            (Main) = (Pair (Half 1.5) (Half 2.5))
+            "#,
+        );
+
+        assert_eq!(norm, "(Pair 1 0)");
+    }
+
+    #[test]
+    fn it_reduces_the_nested_patterns() {
+        let norm = eval_main(
+            r#"
+            (Half (Succ (Succ n))) = (Succ (Half n))
+            (Half n) = (Zero)
+            (Pred3 (Succ (Succ (Succ n)))) = n
+            (Pred3 n) = n
+            (Main) = (Pair (Half (Succ (Succ (Succ (Zero))))) (Pred3 (Succ (Succ (Succ (Succ (Zero)))))))
+            "#,
+        );
+
+        assert_eq!(norm, "(Pair (Succ (Zero)) (Succ (Zero)))");
     }
-}
 
-fn visit_fn(group: *mut RuleGroup, ctx: *mut ReduceCtx) -> bool {
-    unsafe {
-        let group = group.read();
-        let mut ctx = ctx.read();
-        let mut context = Context::new(&mut ctx as *const _ as *mut _);
-        let Control::Break(done) = group.hvm_visit.eval(&mut context) else {
-            panic!("The program did not finished correctly.")
+    #[test]
+    fn it_matches_the_nested_fields_as_they_are() {
+        // The nested fields aren't reduced, so `(Id ..)` doesn't match `(Succ ..)`
+        let norm = eval_main(
+            r#"
+            (Id x) = x
+            (Half (Succ (Succ n))) = (Succ (Half n))
+            (Half n) = (Zero)
+            (Main) = (Half (Succ (Id (Succ (Zero)))))
+            "#,
+        );
+
+        assert_eq!(norm, "(Zero)");
+    }
+
+    #[test]
+    fn it_keeps_the_rule_priority() {
+        let norm = eval_main(
+            r#"
+            (Foo (A) (B)) = 1
+            (Foo x (B))   = 2
+            (Foo (A) y)   = 3
+            (Foo x y)     = 4
+            (Main) = (Quad (Foo (A) (B)) (Foo (C) (B)) (Foo (A) (C)) (Foo (C) (C)))
+            "#,
+        );
+
+        assert_eq!(norm, "(Quad 1 2 3 4)");
+    }
+
+    #[test]
+    fn it_reduces_the_binary_operations() {
+        let norm = eval_main(
+            r#"
+            (Id x) = x
+            (Main) = (Pair (+ 1 2) (Pair (- 2.5 1.0) (* (Id 2) 3)))
+            "#,
+        );
+
+        assert_eq!(norm, "(Pair 3 (Pair 1.5 6))");
+    }
+
+    #[test]
+    fn it_keeps_the_binary_operations_of_the_other_terms() {
+        let norm = eval_main(
+            r#"
+            (Inc x) = (+ x 1)
+            (Main) = (Pair (Inc @y y) (Inc 2))
+            "#,
+        );
+
+        assert!(norm.starts_with("(Pair (+ "), "{norm}");
+        assert!(norm.ends_with(" 1) 3)"), "{norm}");
+    }
+
+    /// `Pair` has the arity 2 in the rule book, so the pattern of `Third` checks the
+    /// arity of the term, and the fields of the matched nodes are taken from them.
+    const ARITY_CODE: &str = r#"
+        (Second (Pair a b)) = b
+        (Third (Pair a b c)) = c
+        (Main) = (Pair (Second (Pair 1 2)) (Third (Pair 1 2)))
+        "#;
+
+    /// Asserts that the apply function of `Third` has the arity terms.
+    fn assert_arity_terms(groups: &FxHashMap<String, RuleGroup>) {
+        let apply = format!("{:?}", groups["Third"].hvm_apply);
+
+        assert!(apply.contains("/arity"), "{apply}");
+        assert!(apply.contains("take-argument"), "{apply}");
+    }
+
+    #[test]
+    fn it_evaluates_the_arity_terms() {
+        let (norm, _) = run_main(ARITY_CODE, |book, groups| {
+            assert_arity_terms(&groups);
+            setup_eval(book, groups)
+        });
+
+        assert_eq!(norm, "(Pair 2 (Third (Pair 1 2)))");
+    }
+
+    #[test]
+    fn it_compiles_the_arity_terms() {
+        let (norm, _) = run_main(ARITY_CODE, |book, groups| {
+            assert_arity_terms(&groups);
+            setup_llvm(book, groups)
+        });
+
+        assert_eq!(norm, "(Pair 2 (Third (Pair 1 2)))");
+    }
+
+    #[test]
+    fn it_reduces_with_the_generated_reduce_function() {
+        let norm = llvm_main(
+            r#"
+            (Double (Zero)) = (Zero)
+            (Double (Succ x)) = (Succ (Succ (Double x)))
+            (Apply f x) = (f x)
+            (Main) = dup a b = (Double (Succ (Zero))); (Pair (Apply @x (+ x 1) 2) (Pair a b))
+            "#,
+        );
+
+        assert_eq!(norm, "(Pair 3 (Pair (Succ (Succ (Zero))) (Succ (Succ (Zero)))))");
+    }
+
+    /// `Fst` discards the second field of the pair, that must be collected, so
+    /// the heap has only the nodes of the normal form, like the program that
+    /// returns it directly.
+    const COLLECT_CODE: &str = r#"
+        (Fst (Pair a b)) = a
+        (Main) = (Fst (Pair (Succ (Zero)) (Pair (Succ (Zero)) (Succ (Zero)))))
+        "#;
+    const COLLECTED_CODE: &str = r#"
+        (Fst (Pair a b)) = a
+        (Main) = (Succ (Zero))
+        "#;
+
+    /// Asserts that the discarded arguments of [COLLECT_CODE] are collected, with
+    /// the reducer created by the given function.
+    fn assert_collected<F>(setup: F)
+    where
+        F: Fn(&RuleBook, FxHashMap<String, RuleGroup>) -> Reducer,
+    {
+        let (norm, used) = {
+            let (norm, reducer) = run_main(COLLECT_CODE, &setup);
+            (norm, reducer.used())
         };
-        done.as_bool()
+        let (expected, expected_used) = {
+            let (norm, reducer) = run_main(COLLECTED_CODE, &setup);
+            (norm, reducer.used())
+        };
+
+        assert_eq!(norm, expected);
+        assert_eq!(used, expected_used);
+    }
+
+    #[test]
+    fn it_collects_the_discarded_arguments() {
+        assert_collected(setup_eval);
     }
-}
 
-fn apply_fn(group: *mut RuleGroup, ctx: *mut ReduceCtx) -> bool {
-    unsafe {
-        let group = group.read();
-        let mut ctx = ctx.read();
-        let mut context = Context::new(&mut ctx as *const _ as *mut _);
-        let done = group.hvm_apply.eval(&mut context);
-        done.as_bool()
+    #[test]
+    fn it_collects_the_discarded_arguments_in_llvm() {
+        assert_collected(setup_llvm);
     }
 }
@@ -0,0 +1,53 @@
+//! Installs the functions of the eval backend in the global precomp table of
+//! HVM, that are called by the reducer of HVM.
+
+use std::sync::Arc;
+
+use fxhash::FxHashMap;
+use hvm::rulebook::RuleBook;
+use hvm::{Precomp, PrecompFuns, PRECOMP};
+use itertools::Itertools;
+
+/// Replaces the precomp table, the functions use the [PrecompFuns] returned for
+/// their ids, and the other ones are interpreted.
+pub fn install_precomp<F>(book: &RuleBook, funs: F)
+where
+    F: Fn(u64) -> Option<PrecompFuns>,
+{
+    let mut precomp = PRECOMP
+        .clone()
+        .iter()
+        .map(|precomp| (precomp.id, precomp.clone()))
+        .collect::<FxHashMap<_, _>>();
+
+    for (id, name) in itertools::sorted(book.id_to_name.iter()) {
+        let smap = book.id_to_smap.get(id).unwrap().clone().leak();
+        if *id <= 29 {
+            // Skip built-in constructors
+            continue;
+        }
+
+        // The functions without [PrecompFuns] are interpreted
+        let funs = funs(*id);
+
+        precomp.insert(
+            *id,
+            Precomp {
+                id: *id,
+                name: name.clone().leak(),
+                smap,
+                funs,
+            },
+        );
+    }
+
+    unsafe {
+        let reordered = precomp
+            .iter()
+            .sorted_by_key(|(id, _)| *id)
+            .map(|(_, precomp)| precomp.clone())
+            .collect::<Vec<_>>();
+
+        *Arc::get_mut_unchecked(&mut PRECOMP.clone()) = Box::new(reordered);
+    }
+}
@@ -0,0 +1,128 @@
+//! Reduces the programs to their normal form, with the generated reduce function,
+//! `hvm__reduce`, that reduces the term in the host to the WHNF, or with the
+//! reducer of HVM, when it isn't compiled.
+//!
+//! It's shared by the JIT and by the static runtime of the AOT compiled
+//! programs, so it only depends on the HVM crate.
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+use hvm::runtime::{Heap, Program};
+use hvm::{Ptr, ReduceCtx};
+
+/// The generated reduce function, it takes the [ReduceCtx] of the term in the
+/// host, and returns true when it's in the WHNF.
+pub type ReduceFn = unsafe extern "C" fn(*mut libc::c_void) -> bool;
+
+/// The heap and the program, that are reduced by the [ReduceFn]. The generated
+/// reduce function runs in a single thread, the first one of the heap.
+pub struct Reducer {
+    pub heap: Heap,
+    pub prog: Program,
+    pub tids: Box<[usize]>,
+    reduce: Option<ReduceFn>,
+    debug: bool,
+}
+
+impl Reducer {
+    pub fn new(prog: Program, heap_size: usize, thread_ids: usize) -> Self {
+        Self {
+            heap: hvm::runtime::new_heap(heap_size, thread_ids),
+            prog,
+            tids: hvm::runtime::new_tids(thread_ids),
+            reduce: None,
+            debug: false,
+        }
+    }
+
+    /// Reduces the terms with the generated reduce function, instead of the
+    /// reducer of HVM.
+    pub fn with_reduce(mut self, reduce: ReduceFn) -> Self {
+        self.reduce = Some(reduce);
+        self
+    }
+
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Reduces the application of the function, that has no arguments, like
+    /// `Main`, to the normal form, returning its normal form, the cost, and the
+    /// time of the reduction, in milliseconds.
+    pub fn eval(&self, main: u64) -> (String, u64, u64) {
+        let host = hvm::runtime::alloc(&self.heap, self.tids[0], 1);
+        hvm::runtime::link(&self.heap, host, hvm::runtime::Fun(main, 0));
+
+        let start = Instant::now();
+        self.normalize(host, &mut HashSet::new());
+        let time = start.elapsed().as_millis() as u64;
+
+        let norm = hvm::language::readback::as_term(&self.heap, &self.prog, host).to_string();
+        let cost = hvm::runtime::get_cost(&self.heap);
+
+        (norm, cost, time)
+    }
+
+    /// The number of the used words of the heap, the nodes that are allocated,
+    /// and weren't freed yet.
+    pub fn used(&self) -> i64 {
+        hvm::runtime::get_used(&self.heap)
+    }
+
+    /// Reduces the term in the host to the WHNF, returning it.
+    pub fn reduce(&self, host: u64) -> Ptr {
+        let Some(reduce) = self.reduce else {
+            return hvm::runtime::reduce(&self.heap, &self.prog, &self.tids, host, false, self.debug);
+        };
+
+        let tid = self.tids[0];
+        let mut cont = hvm::runtime::REDEX_CONT_RET;
+        let mut current = host;
+        let mut ctx = ReduceCtx {
+            heap: &self.heap,
+            prog: &self.prog,
+            tid,
+            hold: true,
+            term: hvm::runtime::load_ptr(&self.heap, host),
+            visit: &self.heap.vstk[tid],
+            redex: &self.heap.rbag,
+            cont: &mut cont,
+            host: &mut current,
+        };
+        unsafe {
+            reduce(&mut ctx as *mut ReduceCtx as *mut libc::c_void);
+        }
+
+        hvm::runtime::load_ptr(&self.heap, host)
+    }
+
+    /// Reduces the term in the host to the normal form, reducing it to the WHNF,
+    /// and then its children, that are visited only once.
+    pub fn normalize(&self, host: u64, seen: &mut HashSet<u64>) -> Ptr {
+        use hvm::runtime::{get_loc, get_tag, APP, CTR, DP0, DP1, FUN, LAM, SUP};
+
+        if !seen.insert(host) {
+            return hvm::runtime::load_ptr(&self.heap, host);
+        }
+
+        let term = self.reduce(host);
+        let children = match get_tag(term) {
+            LAM => vec![get_loc(term, 1)],
+            APP | SUP => vec![get_loc(term, 0), get_loc(term, 1)],
+            DP0 | DP1 => vec![get_loc(term, 2)],
+            CTR | FUN => {
+                let arity = hvm::runtime::arity_of(&self.prog.aris, term);
+                (0..arity).map(|index| get_loc(term, index)).collect()
+            }
+            _ => vec![],
+        };
+        for child in children {
+            let normal = self.normalize(child, seen);
+            hvm::runtime::link(&self.heap, child, normal);
+        }
+
+        hvm::runtime::load_ptr(&self.heap, host)
+    }
+}
@@ -2,5 +2,7 @@ pub mod apply;
 pub mod graph;
 pub mod reduce;
 pub mod rule;
+pub mod ssa;
 pub mod syntax;
+pub mod verify;
 pub mod visit;
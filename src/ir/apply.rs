@@ -43,7 +43,12 @@ pub struct Block {
 pub struct Variable {
     pub erased: bool,
     pub index: u64,
-    pub field_index: Option<u64>,
+
+    /// The path of field indexes, from the argument to the variable, it's
+    /// empty if the variable is the argument itself.
+    ///
+    /// E.g: The `a` in `(Add (Succ (Succ a)) b)` has the path `[0, 0]`.
+    pub fields: Vec<u64>,
 }
 
 impl Block {
@@ -9,6 +9,8 @@ use crate::ir::apply::{Block, Position, Term};
 /// [crate::ir::graph::BasicBlock].
 #[derive(Debug, Clone)]
 pub enum Instruction {
+    /// Collects an erased term with the HVM's garbage collector, freeing
+    /// its nodes.
     Collect(Collect),
     Free(Free),
 
@@ -63,8 +65,8 @@ pub enum Instruction {
 ///
 /// E.g: This code: `Succ Zero`, generates the following IR:
 /// ```
-/// %1 = make-agent                           ; term = (Zero)
-/// %2 = make-agent (Constructor.new Zero %1) ; term = (Succ (Zero))
+/// %1 = (make-agent)                                ; term = (Zero)
+/// %2 = (make-agent (Constructor.new Zero:1 %1))    ; term = (Succ (Zero))
 /// ```
 /// The comments are added to the IR, to make easier to understand.
 #[derive(Debug, Clone)]
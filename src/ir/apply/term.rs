@@ -12,10 +12,16 @@ pub enum Term {
     /// Represents the id of a Tag, in a constant value.
     Tag(Tag),
 
-    /// Gets the arity of the given term.
+    /// Gets the arity of the node of the given term, as an [Term::Int]. The arity
+    /// of the functions and constructors is looked up in the arity table of the
+    /// program, by their extension.
     ArityOf(ArityOf),
 
+    /// Takes the argument of the node in the given [Position], with an index that
+    /// is only known in runtime, like the ones compared with a [Term::ArityOf].
     TakeArgument(TakeArgument),
+
+    /// Loads the argument of the given term, with a constant index.
     LoadArgument(LoadArgument),
 
     /// Gets the extension of the given term.
@@ -46,18 +52,39 @@ pub enum Term {
     /// purposes.
     Agent(Agent),
 
+    /// Computes the [Binary] operation inline, creating a number, without
+    /// allocating an `Op2` node. The operands must be both [Tag::U60], or
+    /// both [Tag::F60], which should be checked before.
+    Operate(Binary),
+
+    /// Selects the `then` term if the condition is true, or the `otherwise` term
+    /// if it's false. Only the selected term is evaluated, so it can guard
+    /// allocations.
+    Select(Select),
+
     //>>> Internal
     /// Represents the id of an Extension, in a constant value.
     Ext(u64, String),
 
     //>>> Logical operations
     Equal(Box<Term>, Box<Term>),
+
+    /// Short-circuit logical or, the right-hand side is only evaluated
+    /// if the left-hand side is false.
     LogicalOr(Box<Term>, Box<Term>),
+
+    /// Short-circuit logical and, the right-hand side is only evaluated
+    /// if the left-hand side is true. It's used to guard the loading of
+    /// nested pattern fields, with the constructor checks.
     LogicalAnd(Box<Term>, Box<Term>),
     //<<< Logical operations
 
     //>>> Values
     Ref(String),
+
+    /// Represents a raw integer constant, like an arity or an argument index, it
+    /// isn't a HVM pointer, unlike the [Value::U60].
+    Int(u64),
     True,
     False,
 
@@ -112,6 +139,13 @@ pub struct ArityOf {
     pub term: Box<Term>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Select {
+    pub condition: Box<Term>,
+    pub then: Box<Term>,
+    pub otherwise: Box<Term>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Agent {
     pub arity: Arity,
@@ -150,6 +184,13 @@ impl Term {
     }
 
     // internal
+    pub fn take_arg(position: Position, argument_index: Term) -> Self {
+        Term::TakeArgument(TakeArgument {
+            position,
+            argument_index: argument_index.into(),
+        })
+    }
+
     pub fn reference(name: &str) -> Self {
         Term::Ref(name.into())
     }
@@ -166,6 +207,22 @@ impl Term {
         Term::LogicalAnd(lhs.into(), rhs.into())
     }
 
+    pub fn select(condition: Term, then: Term, otherwise: Term) -> Self {
+        Term::Select(Select {
+            condition: condition.into(),
+            then: then.into(),
+            otherwise: otherwise.into(),
+        })
+    }
+
+    pub fn operate(lhs: Term, op: Oper, rhs: Term) -> Self {
+        Term::Operate(Binary {
+            lhs: Box::new(lhs),
+            op,
+            rhs: Box::new(rhs),
+        })
+    }
+
     pub fn ext(id: u64, ext: &str) -> Self {
         Term::Ext(id, ext.into())
     }
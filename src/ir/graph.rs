@@ -22,6 +22,15 @@ pub struct Variable {
     pub name: String,
 }
 
+/// Represents a phi node, it defines the variable with one of the incoming
+/// variables, selected by the block that jumped to the current one. They're
+/// placed in the join blocks, by the SSA construction [crate::ir::ssa].
+#[derive(Debug, Clone)]
+pub struct Phi {
+    pub name: String,
+    pub incoming: Vec<(Label, String)>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Terminator<I: HasTerm> {
     Unreachable,
@@ -29,6 +38,11 @@ pub enum Terminator<I: HasTerm> {
     Return(I::Term),
     Jump(Label),
     Cond(I::Term, Label, Label),
+
+    /// Jumps to the label of the first case that is equal to the term, or to the
+    /// default label. The cases are constants, like the constructor extensions,
+    /// so it can be lowered to a jump table.
+    Switch(I::Term, Vec<(I::Term, Label)>, Label),
 }
 
 /// Represents a basic block, composed by a label, a list of variables, a list of instructions
@@ -38,11 +52,14 @@ pub struct BasicBlock<I: HasTerm> {
     pub label: String,
 
     pub variables: Vec<Variable>,
+    pub phis: Vec<Phi>,
     pub instructions: Vec<I>,
     pub terminator: Terminator<I>,
 
     /// The declared blocks, are the current tree of blocks that are declared with
-    /// the terminators [Terminator::Jump] and [Terminator::Cond].
+    /// the terminators [Terminator::Jump], [Terminator::Cond] and [Terminator::Switch]. A block can jump to
+    /// the blocks declared by its ancestors, like the join blocks, that are declared
+    /// next to the branches that jump to them.
     ///
     /// These are used to evaluate the [BasicBlock] and to compile the
     /// [BasicBlock] into a LLVM IR.
@@ -74,6 +91,7 @@ impl<I: HasTerm> Default for BasicBlock<I> {
         Self {
             label: String::new(),
             variables: Vec::new(),
+            phis: Vec::new(),
             instructions: Vec::new(),
             terminator: Terminator::Unreachable,
             // internal stuff
@@ -82,6 +100,22 @@ impl<I: HasTerm> Default for BasicBlock<I> {
     }
 }
 
+impl<I: HasTerm> Terminator<I> {
+    /// Returns the labels of the blocks that the terminator can jump to.
+    pub fn labels(&self) -> Vec<&Label> {
+        match self {
+            Terminator::Jump(label) => vec![label],
+            Terminator::Cond(_, then, otherwise) => vec![then, otherwise],
+            Terminator::Switch(_, cases, default) => {
+                let mut labels = cases.iter().map(|(_, label)| label).collect::<Vec<_>>();
+                labels.push(default);
+                labels
+            }
+            Terminator::Unreachable | Terminator::Debug(..) | Terminator::Return(..) => vec![],
+        }
+    }
+}
+
 impl<I: HasTerm> BasicBlock<I> {
     pub fn new(label: &str) -> Self {
         Self {
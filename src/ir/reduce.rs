@@ -1 +1,81 @@
+use crate::ir::apply::Tag;
+use crate::ir::graph::{BasicBlock, HasTerm};
 
+pub type ReduceBlock = BasicBlock<Instruction>;
+
+pub type FunctionId = u64;
+
+/// The stage of the reduction, that the reduce function dispatches to. It's the
+/// `FAST_VISIT` and the `FAST_APPLY` of the original HVM code, respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Visit,
+    Apply,
+}
+
+/// The rules of the HVM runtime, that reduce the terms that aren't applications
+/// of the compiled functions, like the lambda applications and the duplications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    App,
+    Dup,
+    Op2,
+
+    /// The functions that aren't compiled, like the built-in ones, that are
+    /// looked up in the program.
+    Fun,
+}
+
+/// An internal intermediate representation between HVM <-> LLVM, this is used to
+/// generate the reduce function, the state machine that reduces the term in
+/// the host to the WHNF, dispatching the current term, by its function id,
+/// directly to the compiled functions.
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    /// Loads the term of the host to the current term of the context, that is
+    /// reduced by the next rule, or compiled function.
+    LoadTerm,
+
+    /// Sets the function id in the current environment, by the extension of the
+    /// current term, that is a function application.
+    SetFunctionId,
+}
+
+#[derive(Debug, Clone)]
+pub enum Term {
+    /// The tag of the current term, set by [Instruction::LoadTerm].
+    CurrentTag,
+
+    /// A tag literal, that is used to match the [Term::CurrentTag].
+    Tag(Tag),
+
+    /// The function id of the current term, set by [Instruction::SetFunctionId].
+    FunctionId,
+
+    /// A function id literal, that is used to match the [Term::FunctionId].
+    Id(FunctionId),
+
+    /// Calls the compiled function of the rule group with the given name, in the
+    /// given [Stage], with the current context, and returns its result.
+    Call(Stage, String),
+
+    /// Calls the rule of the HVM runtime, in the given [Stage], with the current
+    /// context, and returns its result.
+    Rule(Stage, Rule),
+
+    /// Completes the redex of the continuation, returning if its function must be
+    /// applied, it's false when the continuation is the root, or when it has
+    /// strict arguments that aren't reduced yet.
+    CompleteRedex,
+
+    /// Takes the next location of the visit queue, returning if it was taken,
+    /// it's false when there are no more locations to visit.
+    NextVisit,
+
+    True,
+    False,
+}
+
+impl HasTerm for Instruction {
+    type Term = Term;
+}
@@ -8,4 +8,8 @@ pub struct RuleGroup {
     pub name: String,
     pub hvm_visit: ir::graph::BasicBlock<ir::visit::Instruction>,
     pub hvm_apply: ir::apply::Block,
+
+    /// The control flow graph of the apply function, after the optimization
+    /// passes, it's the one used by the backends.
+    pub hvm_apply_graph: ir::apply::ApplyBasicBlock,
 }
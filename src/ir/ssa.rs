@@ -0,0 +1,623 @@
+//! Static single assignment construction, for the control flow graphs.
+//!
+//! Every variable is renamed to be assigned only once, and the [Phi] nodes are
+//! placed in the blocks where different definitions of a variable meet. It follows
+//! Cytron et al.: the dominators are computed with the iterative algorithm of
+//! Cooper, Harvey and Kennedy, and the phi nodes are placed in the iterated
+//! dominance frontier of the definitions, only where the variable is live, so it
+//! builds the pruned SSA form.
+//!
+//! The variables used by a block, that are defined in another block, are recorded
+//! in [BasicBlock::variables], with the block that defines them. So the backends
+//! can use the values directly, instead of storing them in the stack.
+
+use fxhash::{FxHashMap, FxHashSet};
+use itertools::Itertools;
+
+use crate::ir::apply::{Instruction, Position, Term, Value};
+use crate::ir::graph::{BasicBlock, HasTerm, Label, Phi, Terminator, Variable};
+
+/// The instructions that can be converted to the SSA form.
+pub trait SsaInstruction: HasTerm {
+    /// The name of the variable defined by the instruction, if any.
+    fn definition_mut(&mut self) -> Option<&mut String>;
+
+    /// Calls the function with every variable used by the instruction.
+    fn uses_mut(&mut self, f: &mut dyn FnMut(&mut String));
+
+    /// Calls the function with every variable used by the term.
+    fn term_uses_mut(term: &mut Self::Term, f: &mut dyn FnMut(&mut String));
+}
+
+/// The dominator tree of a control flow graph, only the blocks that are reachable
+/// from the entry block are in the tree.
+#[derive(Debug, Default)]
+pub struct Dominators {
+    /// The reachable blocks, in reverse postorder.
+    pub order: Vec<String>,
+
+    /// The block -> immediate dominator, binding map. The entry block has none.
+    pub idom: FxHashMap<String, String>,
+
+    /// The block -> dominance frontier, binding map. It has the blocks where the
+    /// dominance of the block ends.
+    pub frontiers: FxHashMap<String, FxHashSet<String>>,
+}
+
+impl Dominators {
+    /// Computes the dominators of the flattened blocks, by the label.
+    pub fn new<I: HasTerm>(entry: &str, blocks: &FxHashMap<String, BasicBlock<I>>) -> Self {
+        let mut order = Vec::new();
+        postorder(entry, blocks, &mut FxHashSet::default(), &mut order);
+        order.reverse();
+
+        let index = order
+            .iter()
+            .enumerate()
+            .map(|(index, label)| (label.clone(), index))
+            .collect::<FxHashMap<_, _>>();
+        let predecessors = predecessors(blocks);
+        let predecessors = |label: &str| {
+            predecessors
+                .get(label)
+                .into_iter()
+                .flatten()
+                .filter(|predecessor| index.contains_key(*predecessor))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        // The entry dominates itself while the tree is computed, so the
+        // intersection always stops.
+        let mut idom = FxHashMap::default();
+        idom.insert(entry.to_string(), entry.to_string());
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for label in order.iter().skip(1) {
+                let mut new_idom: Option<String> = None;
+                for predecessor in predecessors(label) {
+                    if !idom.contains_key(&predecessor) {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => predecessor,
+                        Some(current) => intersect(predecessor, current, &idom, &index),
+                    });
+                }
+
+                if let Some(new_idom) = new_idom {
+                    if idom.get(label) != Some(&new_idom) {
+                        idom.insert(label.clone(), new_idom);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut frontiers = FxHashMap::<String, FxHashSet<String>>::default();
+        for label in &order {
+            let predecessors = predecessors(label);
+            if predecessors.len() < 2 {
+                continue;
+            }
+
+            for mut runner in predecessors {
+                while runner != idom[label] {
+                    frontiers.entry(runner.clone()).or_default().insert(label.clone());
+                    runner = idom[&runner].clone();
+                }
+            }
+        }
+
+        idom.remove(entry);
+
+        Self { order, idom, frontiers }
+    }
+
+    /// If every path from the entry to `b` goes through `a`.
+    pub fn dominates(&self, a: &str, b: &str) -> bool {
+        let mut current = b;
+        loop {
+            if current == a {
+                return true;
+            }
+            match self.idom.get(current) {
+                Some(idom) => current = idom.as_str(),
+                None => return false,
+            }
+        }
+    }
+
+    /// Returns the blocks that are immediately dominated by the block, sorted
+    /// by the label.
+    pub fn children(&self, label: &str) -> Vec<String> {
+        self.idom
+            .iter()
+            .filter(|(_, idom)| *idom == label)
+            .map(|(child, _)| child.clone())
+            .sorted()
+            .collect()
+    }
+}
+
+fn intersect(
+    mut a: String,
+    mut b: String,
+    idom: &FxHashMap<String, String>,
+    index: &FxHashMap<String, usize>,
+) -> String {
+    while a != b {
+        while index[&a] > index[&b] {
+            a = idom[&a].clone();
+        }
+        while index[&b] > index[&a] {
+            b = idom[&b].clone();
+        }
+    }
+
+    a
+}
+
+fn postorder<I: HasTerm>(
+    label: &str,
+    blocks: &FxHashMap<String, BasicBlock<I>>,
+    visited: &mut FxHashSet<String>,
+    order: &mut Vec<String>,
+) {
+    let Some(bb) = blocks.get(label) else {
+        return;
+    };
+    if !visited.insert(label.to_string()) {
+        return;
+    }
+
+    for Label(successor) in bb.terminator.labels() {
+        postorder(successor, blocks, visited, order);
+    }
+
+    order.push(label.to_string());
+}
+
+fn predecessors<I: HasTerm>(blocks: &FxHashMap<String, BasicBlock<I>>) -> FxHashMap<String, Vec<String>> {
+    let mut predecessors = FxHashMap::<String, Vec<String>>::default();
+    for (label, bb) in blocks.iter().sorted_by_key(|(label, _)| *label) {
+        for Label(successor) in bb.terminator.labels() {
+            predecessors.entry(successor.clone()).or_default().push(label.clone());
+        }
+    }
+
+    predecessors
+}
+
+/// Moves the declared blocks of the tree into the map, by the label, recording
+/// where they were declared.
+fn flatten<I: HasTerm>(
+    mut bb: BasicBlock<I>,
+    blocks: &mut FxHashMap<String, BasicBlock<I>>,
+    children: &mut FxHashMap<String, Vec<String>>,
+) {
+    for (label, declared) in std::mem::take(&mut bb.declared_blocks) {
+        children.entry(bb.label.clone()).or_default().push(label);
+        flatten(declared, blocks, children);
+    }
+
+    blocks.insert(bb.label.clone(), bb);
+}
+
+/// Moves the blocks back into the tree, where they were declared.
+fn unflatten<I: HasTerm>(
+    label: &str,
+    blocks: &mut FxHashMap<String, BasicBlock<I>>,
+    children: &FxHashMap<String, Vec<String>>,
+) -> BasicBlock<I> {
+    let mut bb = blocks.remove(label).unwrap();
+    for child in children.get(label).into_iter().flatten() {
+        let child = unflatten(child, blocks, children);
+        bb.declared_blocks.insert(child.label.clone(), child);
+    }
+
+    bb
+}
+
+fn terminator_uses_mut<I: SsaInstruction>(terminator: &mut Terminator<I>, f: &mut dyn FnMut(&mut String)) {
+    match terminator {
+        Terminator::Return(term) | Terminator::Cond(term, ..) => I::term_uses_mut(term, f),
+        Terminator::Switch(term, cases, _) => {
+            I::term_uses_mut(term, f);
+            for (value, _) in cases {
+                I::term_uses_mut(value, f);
+            }
+        }
+        Terminator::Unreachable | Terminator::Debug(..) | Terminator::Jump(..) => {}
+    }
+}
+
+impl<I: SsaInstruction> BasicBlock<I> {
+    /// Converts the control flow graph into the SSA form, see [crate::ir::ssa]. The
+    /// graph must not have phi nodes yet.
+    pub fn into_ssa(self) -> Self {
+        let entry = self.label.clone();
+        let mut blocks = FxHashMap::default();
+        let mut children = FxHashMap::default();
+        flatten(self, &mut blocks, &mut children);
+
+        let dominators = Dominators::new(&entry, &blocks);
+
+        // The variables that are used before being defined in each block, and
+        // the blocks that define each variable.
+        let mut used = FxHashMap::<String, FxHashSet<String>>::default();
+        let mut defined = FxHashMap::<String, FxHashSet<String>>::default();
+        let mut definitions = FxHashMap::<String, FxHashSet<String>>::default();
+        for (label, bb) in &mut blocks {
+            let used = used.entry(label.clone()).or_default();
+            let defined = defined.entry(label.clone()).or_default();
+            for instruction in &mut bb.instructions {
+                instruction.uses_mut(&mut |name| {
+                    if !defined.contains(name) {
+                        used.insert(name.clone());
+                    }
+                });
+                if let Some(name) = instruction.definition_mut() {
+                    defined.insert(name.clone());
+                    definitions.entry(name.clone()).or_default().insert(label.clone());
+                }
+            }
+            terminator_uses_mut(&mut bb.terminator, &mut |name| {
+                if !defined.contains(name) {
+                    used.insert(name.clone());
+                }
+            });
+        }
+
+        let live = liveness(&blocks, &used, &defined);
+
+        // Places the phi nodes in the iterated dominance frontier of the
+        // definitions, where the variable is live.
+        for (name, defining) in definitions.iter().sorted_by_key(|(name, _)| *name) {
+            let mut worklist = defining.iter().cloned().sorted().collect::<Vec<_>>();
+            let mut placed = FxHashSet::default();
+            while let Some(label) = worklist.pop() {
+                for frontier in dominators.frontiers.get(&label).into_iter().flatten().sorted() {
+                    if !live[frontier].contains(name) || !placed.insert(frontier.clone()) {
+                        continue;
+                    }
+
+                    blocks.get_mut(frontier).unwrap().phis.push(Phi {
+                        name: name.clone(),
+                        incoming: vec![],
+                    });
+                    if !defining.contains(frontier) {
+                        worklist.push(frontier.clone());
+                    }
+                }
+            }
+        }
+
+        let mut renamer = Renamer::default();
+        renamer.rename(&entry, &mut blocks, &dominators);
+
+        record_variables(&mut blocks);
+
+        unflatten(&entry, &mut blocks, &children)
+    }
+}
+
+/// Computes the variables that are live in the start of each block.
+fn liveness<I: HasTerm>(
+    blocks: &FxHashMap<String, BasicBlock<I>>,
+    used: &FxHashMap<String, FxHashSet<String>>,
+    defined: &FxHashMap<String, FxHashSet<String>>,
+) -> FxHashMap<String, FxHashSet<String>> {
+    let mut live = used.clone();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (label, bb) in blocks.iter().sorted_by_key(|(label, _)| *label) {
+            let mut live_in = used[label].clone();
+            for Label(successor) in bb.terminator.labels() {
+                let live_out = live.get(successor).into_iter().flatten();
+                live_in.extend(live_out.filter(|name| !defined[label].contains(*name)).cloned());
+            }
+
+            if live_in.len() != live[label].len() {
+                live.insert(label.clone(), live_in);
+                changed = true;
+            }
+        }
+    }
+
+    live
+}
+
+/// Renames the definitions of the variables, in the dominator tree order.
+#[derive(Default)]
+struct Renamer {
+    /// The variable -> the names of its definitions that are in scope.
+    stacks: FxHashMap<String, Vec<String>>,
+
+    /// The variable -> number of definitions, the first one keeps the name.
+    versions: FxHashMap<String, usize>,
+
+    /// The block -> the original variables of its phi nodes.
+    phis: FxHashMap<String, Vec<String>>,
+}
+
+impl Renamer {
+    fn define(&mut self, name: &mut String) -> String {
+        let original = name.clone();
+        let version = self.versions.entry(original.clone()).or_default();
+        if *version > 0 {
+            *name = format!("{original}.{version}");
+        }
+        *version += 1;
+
+        self.stacks.entry(original.clone()).or_default().push(name.clone());
+        original
+    }
+
+    fn current(&self, name: &str) -> Option<&String> {
+        self.stacks.get(name).and_then(|stack| stack.last())
+    }
+
+    fn rename<I: SsaInstruction>(
+        &mut self,
+        label: &str,
+        blocks: &mut FxHashMap<String, BasicBlock<I>>,
+        dominators: &Dominators,
+    ) {
+        let mut bb = blocks.remove(label).unwrap();
+        let mut defined = Vec::new();
+
+        let phis = bb.phis.iter().map(|phi| phi.name.clone()).collect();
+        self.phis.entry(label.to_string()).or_insert(phis);
+        for phi in &mut bb.phis {
+            defined.push(self.define(&mut phi.name));
+        }
+
+        for instruction in &mut bb.instructions {
+            instruction.uses_mut(&mut |name| {
+                if let Some(current) = self.current(name) {
+                    *name = current.clone();
+                }
+            });
+            if let Some(name) = instruction.definition_mut() {
+                defined.push(self.define(name));
+            }
+        }
+        terminator_uses_mut(&mut bb.terminator, &mut |name| {
+            if let Some(current) = self.current(name) {
+                *name = current.clone();
+            }
+        });
+
+        // Fills the incoming variables of the phi nodes of the successors
+        for Label(successor) in bb.terminator.labels() {
+            let Some(target) = blocks.get_mut(successor) else {
+                continue;
+            };
+            let originals = self
+                .phis
+                .entry(successor.clone())
+                .or_insert_with(|| target.phis.iter().map(|phi| phi.name.clone()).collect())
+                .clone();
+
+            for (phi, original) in target.phis.iter_mut().zip(originals) {
+                let current = self.current(&original).cloned().unwrap_or(original);
+                phi.incoming.push((Label(label.to_string()), current));
+            }
+        }
+
+        blocks.insert(label.to_string(), bb);
+
+        for child in dominators.children(label) {
+            self.rename(&child, blocks, dominators);
+        }
+
+        for name in defined {
+            self.stacks.get_mut(&name).unwrap().pop();
+        }
+    }
+}
+
+/// Records the variables that are used by each block, and defined in another.
+fn record_variables<I: SsaInstruction>(blocks: &mut FxHashMap<String, BasicBlock<I>>) {
+    let mut declared_blocks = FxHashMap::default();
+    for (label, bb) in blocks.iter_mut() {
+        for phi in &bb.phis {
+            declared_blocks.insert(phi.name.clone(), label.clone());
+        }
+        for instruction in &mut bb.instructions {
+            if let Some(name) = instruction.definition_mut() {
+                declared_blocks.insert(name.clone(), label.clone());
+            }
+        }
+    }
+
+    for (label, bb) in blocks.iter_mut() {
+        let mut names = Vec::new();
+        let mut record = |name: &mut String| {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        };
+        for instruction in &mut bb.instructions {
+            instruction.uses_mut(&mut record);
+        }
+        terminator_uses_mut(&mut bb.terminator, &mut record);
+
+        bb.variables = names
+            .into_iter()
+            .filter_map(|name| {
+                let declared_block = declared_blocks.get(&name)?;
+                (declared_block != label).then(|| Variable {
+                    declared_block: Label(declared_block.clone()),
+                    name,
+                })
+            })
+            .collect();
+    }
+}
+
+impl SsaInstruction for Instruction {
+    fn definition_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Instruction::Let(let_instruction) => Some(&mut let_instruction.name),
+            _ => None,
+        }
+    }
+
+    fn uses_mut(&mut self, f: &mut dyn FnMut(&mut String)) {
+        match self {
+            Instruction::Collect(collect) => Self::term_uses_mut(&mut collect.term, f),
+            Instruction::Free(free) => Self::term_uses_mut(&mut free.position, f),
+            Instruction::Metadata(metadata) => {
+                for instruction in &mut metadata.instructions {
+                    instruction.uses_mut(f);
+                }
+            }
+            Instruction::Let(let_instruction) => Self::term_uses_mut(&mut let_instruction.value, f),
+            Instruction::If(if_instruction) => Self::term_uses_mut(&mut if_instruction.condition, f),
+            Instruction::Term(term) | Instruction::Return(term) => Self::term_uses_mut(term, f),
+            Instruction::Link(link) => {
+                position_uses_mut(&mut link.position, f);
+                Self::term_uses_mut(&mut link.term, f);
+            }
+            Instruction::IncrementCost | Instruction::Println(..) => {}
+        }
+    }
+
+    fn term_uses_mut(term: &mut Term, f: &mut dyn FnMut(&mut String)) {
+        match term {
+            Term::Ref(name) => f(name),
+            Term::ArityOf(arity_of) => Self::term_uses_mut(&mut arity_of.term, f),
+            Term::TakeArgument(take_argument) => {
+                position_uses_mut(&mut take_argument.position, f);
+                Self::term_uses_mut(&mut take_argument.argument_index, f);
+            }
+            Term::LoadArgument(load_argument) => Self::term_uses_mut(&mut load_argument.term, f),
+            Term::GetExt(get_ext) => Self::term_uses_mut(&mut get_ext.term, f),
+            Term::GetNumber(get_number) => Self::term_uses_mut(&mut get_number.term, f),
+            Term::GetTag(get_tag) => Self::term_uses_mut(&mut get_tag.term, f),
+            Term::GetPosition(get_position) => Self::term_uses_mut(&mut get_position.term, f),
+            Term::Agent(agent) => {
+                for argument in &mut agent.arguments {
+                    Self::term_uses_mut(argument, f);
+                }
+            }
+            Term::Operate(binary) => {
+                Self::term_uses_mut(&mut binary.lhs, f);
+                Self::term_uses_mut(&mut binary.rhs, f);
+            }
+            Term::Select(select) => {
+                Self::term_uses_mut(&mut select.condition, f);
+                Self::term_uses_mut(&mut select.then, f);
+                Self::term_uses_mut(&mut select.otherwise, f);
+            }
+            Term::Equal(lhs, rhs) | Term::LogicalOr(lhs, rhs) | Term::LogicalAnd(lhs, rhs) => {
+                Self::term_uses_mut(lhs, f);
+                Self::term_uses_mut(rhs, f);
+            }
+            Term::Create(value) => match value {
+                Value::Dp0(_, position)
+                | Value::Dp1(_, position)
+                | Value::Argument(position)
+                | Value::Atom(position)
+                | Value::Lam(position)
+                | Value::App(position)
+                | Value::Super(_, position)
+                | Value::Function(_, position)
+                | Value::Constructor(_, position) => position_uses_mut(position, f),
+                Value::Binary(binary, position) => {
+                    Self::term_uses_mut(&mut binary.lhs, f);
+                    Self::term_uses_mut(&mut binary.rhs, f);
+                    position_uses_mut(position, f);
+                }
+                Value::U60(..) | Value::F60(..) | Value::Erased => {}
+            },
+            Term::Current
+            | Term::Tag(..)
+            | Term::Ext(..)
+            | Term::Int(..)
+            | Term::Alloc(..)
+            | Term::True
+            | Term::False
+            | Term::NotFound(..) => {}
+        }
+    }
+}
+
+fn position_uses_mut(position: &mut Position, f: &mut dyn FnMut(&mut String)) {
+    if let Position::Named { reference_name, .. } = position {
+        f(reference_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::apply::{ApplyBasicBlock, Block};
+    use crate::ir::verify::Verify;
+
+    fn graph(block: &str) -> ApplyBasicBlock {
+        block.parse::<Block>().unwrap().into_control_flow_graph()
+    }
+
+    const BRANCHES: &str = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  if (%arg0/tag == 'U60):
+    %x = (U60.new 1)
+  else:
+    %x = (U60.new 2)
+  end
+  link [^host] %x
+  ret true
+";
+
+    #[test]
+    fn it_computes_the_dominators() {
+        let mut blocks = FxHashMap::default();
+        flatten(graph(BRANCHES), &mut blocks, &mut FxHashMap::default());
+
+        let dominators = Dominators::new("entry", &blocks);
+        assert_eq!(dominators.order[0], "entry");
+        assert_eq!(dominators.idom["bb_3"], "entry");
+        assert!(dominators.dominates("entry", "bb_1"));
+        assert!(!dominators.dominates("bb_1", "bb_3"));
+        assert!(dominators.frontiers["bb_1"].contains("bb_3"));
+    }
+
+    #[test]
+    fn it_places_the_phi_nodes() {
+        let bb = graph(BRANCHES).into_ssa();
+        assert!(bb.verify().is_ok());
+
+        let text = bb.to_string();
+        assert!(text.contains("%x.2 = phi [@bb_1 %x] [@bb_2 %x.1]"), "{text}");
+        assert!(text.contains("link [^host] %x.2"), "{text}");
+        assert_eq!(text.parse::<ApplyBasicBlock>().unwrap().to_string(), text);
+
+        // The variables of the entry block are recorded in the blocks that use them
+        let variables = &bb.declared_blocks["bb_3"].variables;
+        assert!(variables.is_empty());
+        let variables = &graph(
+            "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  if (%arg0/tag == 'U60):
+    link [^host] %arg0
+  end
+  ret true
+",
+        )
+        .into_ssa()
+        .declared_blocks["bb_1"]
+            .variables;
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].name, "arg0");
+        assert_eq!(variables[0].declared_block.0, "entry");
+    }
+}
@@ -1,5 +1,8 @@
 use hvm::syntax::Oper;
 
+/// The label of the user-written duplications and superpositions.
+pub const DEFAULT_LABEL: &str = "default";
+
 #[derive(Debug, Clone)]
 pub enum Term {
     U60(u64),
@@ -26,6 +29,11 @@ pub struct RuleGroup {
 pub enum Pattern {
     Atom(String),
     Erased,
+    U60(u64),
+    F60(f64),
+
+    /// A nested constructor pattern, e.g: the `(Succ a)` in `(Add (Succ (Succ a)) b)`.
+    Constructor(Constructor),
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +61,10 @@ pub struct Rule {
 
 #[derive(Debug, Clone)]
 pub struct Duplicate {
+    /// The label of the user-written duplications, see [Super::label]. The
+    /// duplications inserted by the linearity pass have none, and get a fresh
+    /// color.
+    pub label: Option<String>,
     pub from: String,
     pub to: String,
     pub value: Box<Term>,
@@ -69,6 +81,8 @@ pub struct App {
 
 #[derive(Debug, Clone)]
 pub struct Let {
+    /// If the variable is never used, the value is collected.
+    pub erased: bool,
     pub name: String,
     pub value: Box<Term>,
     pub body: Box<Term>,
@@ -76,6 +90,10 @@ pub struct Let {
 
 #[derive(Debug, Clone)]
 pub struct Super {
+    /// The nodes with the same label get the same color, so a duplication
+    /// annihilates with a superposition of its label. The HVM syntax has no
+    /// labels, so every user-written node has the [DEFAULT_LABEL].
+    pub label: Option<String>,
     pub first: Box<Term>,
     pub second: Box<Term>,
 }
@@ -99,5 +117,8 @@ pub struct Binary {
 pub struct Atom {
     pub name: String,
     pub index: u64,
-    pub field_index: Option<u64>,
+
+    /// The path of field indexes of the variable, inside the nested constructor
+    /// patterns of its parameter, it's empty for the top-level parameters.
+    pub fields: Vec<u64>,
 }
@@ -0,0 +1,397 @@
+//! IR verifier, checks the integrity of the [crate::ir::apply] blocks, and of the
+//! control flow graphs of [crate::ir::apply], [crate::ir::visit] and [crate::ir::reduce].
+//!
+//! The verifier runs after every codegen stage, so the broken IR is reported
+//! where it's generated, instead of the eval or LLVM stages. It checks:
+//!   - Every [Term::Ref] and [Position::Named] is defined before it's used;
+//!   - No [Term::NotFound] survives the codegen;
+//!   - The labels of [Terminator::Cond], [Terminator::Switch] and [Terminator::Jump] exist in the
+//!     declared blocks, of the current block or of one of its ancestors, like
+//!     the join blocks;
+//!   - Every basic block has a terminator, that isn't [Terminator::Unreachable].
+//!
+//! It doesn't stop in the first error, every problem is reported.
+
+use std::fmt::{Display, Formatter};
+
+use fxhash::FxHashSet;
+use itertools::Itertools;
+
+use crate::ir::apply::{Block, Instruction, Position, Term, Value};
+use crate::ir::graph::{BasicBlock, HasTerm, Label, Terminator};
+use crate::ir::{reduce, visit};
+
+/// An error found by the verifier, with the path of the blocks where it was found.
+#[derive(Debug, Clone)]
+pub struct VerifyError {
+    pub location: String,
+    pub message: String,
+}
+
+/// Verifies the IR, returning every error found.
+pub trait Verify {
+    fn verify(&self) -> Result<(), Vec<VerifyError>>;
+}
+
+/// Verifies the instructions and the terms of a [BasicBlock].
+pub trait VerifyInstruction: HasTerm {
+    fn verify_instruction(&self, verifier: &mut Verifier);
+
+    fn verify_term(term: &Self::Term, verifier: &mut Verifier);
+}
+
+/// The verifier state, with the names that are defined in the current scope.
+#[derive(Default)]
+pub struct Verifier {
+    scope: FxHashSet<String>,
+    labels: FxHashSet<String>,
+    location: Vec<String>,
+    errors: Vec<VerifyError>,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl Verifier {
+    /// Reports a new error in the current location.
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(VerifyError {
+            location: self.location.join("/"),
+            message: message.into(),
+        });
+    }
+
+    pub fn define(&mut self, name: &str) {
+        self.scope.insert(name.into());
+    }
+
+    /// Checks if the name is defined in the current scope.
+    pub fn expect_defined(&mut self, name: &str) {
+        if !self.scope.contains(name) {
+            self.error(format!("reference `%{name}` is used before being defined"));
+        }
+    }
+
+    /// Runs the given function in a nested location, the names and the labels
+    /// defined inside of it are dropped at the end.
+    pub fn nested<F: FnOnce(&mut Self)>(&mut self, location: &str, f: F) {
+        let scope = self.scope.clone();
+        let labels = self.labels.clone();
+        self.location.push(location.into());
+        f(self);
+        self.location.pop();
+        self.scope = scope;
+        self.labels = labels;
+    }
+
+    fn finish(self) -> Result<(), Vec<VerifyError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn verify_block(&mut self, block: &Block) {
+        for instruction in &block.block {
+            instruction.verify_instruction(self);
+        }
+    }
+
+    fn verify_basic_block<I: VerifyInstruction>(&mut self, bb: &BasicBlock<I>) {
+        self.nested(&bb.label, |verifier| {
+            verifier.labels.extend(bb.declared_blocks.keys().cloned());
+
+            // The incoming variables are defined in the predecessors, that can be
+            // siblings of the block, so only the phi nodes are defined here.
+            for phi in &bb.phis {
+                verifier.define(&phi.name);
+            }
+
+            for instruction in &bb.instructions {
+                instruction.verify_instruction(verifier);
+            }
+
+            let mut labels = Vec::new();
+            match &bb.terminator {
+                Terminator::Unreachable => verifier.error("the block has no terminator"),
+                Terminator::Debug(..) => {}
+                Terminator::Return(term) => I::verify_term(term, verifier),
+                Terminator::Jump(label) => labels.push(label),
+                Terminator::Cond(condition, then, otherwise) => {
+                    I::verify_term(condition, verifier);
+                    labels.push(then);
+                    labels.push(otherwise);
+                }
+                Terminator::Switch(term, cases, default) => {
+                    I::verify_term(term, verifier);
+                    for (value, label) in cases {
+                        I::verify_term(value, verifier);
+                        labels.push(label);
+                    }
+                    labels.push(default);
+                }
+            }
+
+            for Label(label) in labels {
+                if !verifier.labels.contains(label) {
+                    verifier.error(format!("the label `@{label}` isn't declared"));
+                }
+            }
+
+            // Sorted, so the errors are reported in the same order
+            for (_, declared) in bb.declared_blocks.iter().sorted_by_key(|(label, _)| *label) {
+                verifier.verify_basic_block(declared);
+            }
+        });
+    }
+
+    fn verify_term(&mut self, term: &Term) {
+        match term {
+            Term::Current
+            | Term::Tag(..)
+            | Term::Ext(..)
+            | Term::Int(..)
+            | Term::Alloc(..)
+            | Term::True
+            | Term::False => {}
+            Term::ArityOf(arity_of) => self.verify_term(&arity_of.term),
+            Term::TakeArgument(take_argument) => {
+                self.verify_position(&take_argument.position);
+                self.verify_term(&take_argument.argument_index);
+            }
+            Term::LoadArgument(load_argument) => self.verify_term(&load_argument.term),
+            Term::GetExt(get_ext) => self.verify_term(&get_ext.term),
+            Term::GetNumber(get_number) => self.verify_term(&get_number.term),
+            Term::GetTag(get_tag) => self.verify_term(&get_tag.term),
+            Term::GetPosition(get_position) => self.verify_term(&get_position.term),
+            Term::Create(value) => self.verify_value(value),
+            Term::Agent(agent) => {
+                for argument in &agent.arguments {
+                    self.verify_term(argument);
+                }
+            }
+            Term::Equal(lhs, rhs) | Term::LogicalOr(lhs, rhs) | Term::LogicalAnd(lhs, rhs) => {
+                self.verify_term(lhs);
+                self.verify_term(rhs);
+            }
+            Term::Operate(binary) => {
+                self.verify_term(&binary.lhs);
+                self.verify_term(&binary.rhs);
+            }
+            Term::Select(select) => {
+                self.verify_term(&select.condition);
+                self.verify_term(&select.then);
+                self.verify_term(&select.otherwise);
+            }
+            Term::Ref(name) => self.expect_defined(name),
+            Term::NotFound(atom) => {
+                self.error(format!("the variable `{}` wasn't resolved by the codegen", atom.name));
+            }
+        }
+    }
+
+    fn verify_value(&mut self, value: &Value) {
+        match value {
+            Value::Dp0(_, position)
+            | Value::Dp1(_, position)
+            | Value::Argument(position)
+            | Value::Atom(position)
+            | Value::Lam(position)
+            | Value::App(position)
+            | Value::Super(_, position)
+            | Value::Function(_, position)
+            | Value::Constructor(_, position) => self.verify_position(position),
+            Value::Binary(binary, position) => {
+                self.verify_term(&binary.lhs);
+                self.verify_term(&binary.rhs);
+                self.verify_position(position);
+            }
+            Value::U60(..) | Value::F60(..) | Value::Erased => {}
+        }
+    }
+
+    fn verify_position(&mut self, position: &Position) {
+        match position {
+            Position::Named { reference_name, .. } => self.expect_defined(reference_name),
+            Position::Host => {}
+        }
+    }
+}
+
+impl VerifyInstruction for Instruction {
+    fn verify_instruction(&self, verifier: &mut Verifier) {
+        match self {
+            Instruction::Collect(collect) => verifier.verify_term(&collect.term),
+            Instruction::Free(free) => verifier.verify_term(&free.position),
+            // The metadata instructions are flattened into the current
+            // block, so they share the same scope.
+            Instruction::Metadata(metadata) => {
+                for instruction in &metadata.instructions {
+                    instruction.verify_instruction(verifier);
+                }
+            }
+            Instruction::If(if_instruction) => {
+                verifier.verify_term(&if_instruction.condition);
+                verifier.nested("then", |verifier| verifier.verify_block(&if_instruction.then));
+                if let Some(otherwise) = &if_instruction.otherwise {
+                    verifier.nested("otherwise", |verifier| verifier.verify_block(otherwise));
+                }
+            }
+            Instruction::Let(binding) => {
+                verifier.verify_term(&binding.value);
+                verifier.define(&binding.name);
+            }
+            Instruction::Link(link) => {
+                verifier.verify_position(&link.position);
+                verifier.verify_term(&link.term);
+            }
+            Instruction::Term(term) | Instruction::Return(term) => verifier.verify_term(term),
+            Instruction::IncrementCost | Instruction::Println(..) => {}
+        }
+    }
+
+    fn verify_term(term: &Term, verifier: &mut Verifier) {
+        verifier.verify_term(term);
+    }
+}
+
+/// The visit instructions use the `vlen`, `vbuf` and `goup` variables of the
+/// context, that must be set before.
+impl VerifyInstruction for visit::Instruction {
+    fn verify_instruction(&self, verifier: &mut Verifier) {
+        use visit::Instruction::*;
+
+        match self {
+            SetVLen => verifier.define("vlen"),
+            SetVBuf(..) => verifier.define("vbuf"),
+            SetGoup(..) => verifier.define("goup"),
+            IncreaseLen(..) | UpdateHost => {
+                verifier.expect_defined("vbuf");
+                verifier.expect_defined("vlen");
+            }
+            Visit(..) => {
+                verifier.expect_defined("vbuf");
+                verifier.expect_defined("vlen");
+                verifier.expect_defined("goup");
+            }
+            UpdateCont => verifier.expect_defined("goup"),
+        }
+    }
+
+    fn verify_term(_: &visit::Term, _: &mut Verifier) {}
+}
+
+/// The reduce terms match the `fid` variable, that must be set before.
+impl VerifyInstruction for reduce::Instruction {
+    fn verify_instruction(&self, verifier: &mut Verifier) {
+        match self {
+            reduce::Instruction::SetFunctionId => verifier.define("fid"),
+        }
+    }
+
+    fn verify_term(term: &reduce::Term, verifier: &mut Verifier) {
+        if let reduce::Term::FunctionId = term {
+            verifier.expect_defined("fid");
+        }
+    }
+}
+
+impl Verify for Block {
+    fn verify(&self) -> Result<(), Vec<VerifyError>> {
+        let mut verifier = Verifier::default();
+        verifier.nested("block", |verifier| verifier.verify_block(self));
+        verifier.finish()
+    }
+}
+
+impl<I: VerifyInstruction> Verify for BasicBlock<I> {
+    fn verify(&self) -> Result<(), Vec<VerifyError>> {
+        let mut verifier = Verifier::default();
+        verifier.verify_basic_block(self);
+        verifier.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::syntax::Atom;
+
+    fn messages(errors: Vec<VerifyError>) -> Vec<String> {
+        errors.into_iter().map(|error| error.to_string()).collect()
+    }
+
+    #[test]
+    fn it_reports_the_undefined_references() {
+        let block = Block::new(vec![
+            Instruction::binding("arg0", Term::load_arg(Term::Current, 0)),
+            Instruction::ret(Term::reference("arg1")),
+        ]);
+
+        assert_eq!(
+            messages(block.verify().unwrap_err()),
+            vec!["block: reference `%arg1` is used before being defined"]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_unresolved_variables() {
+        let block = Block::new(vec![Instruction::ret(Term::NotFound(Atom {
+            name: "x".into(),
+            index: 0,
+            fields: vec![],
+        }))]);
+
+        assert_eq!(
+            messages(block.verify().unwrap_err()),
+            vec!["block: the variable `x` wasn't resolved by the codegen"]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_unknown_labels() {
+        let mut bb = BasicBlock::<Instruction>::new("entry");
+        bb.terminator = Terminator::Jump(Label("nowhere".into()));
+
+        assert_eq!(
+            messages(bb.verify().unwrap_err()),
+            vec!["entry: the label `@nowhere` isn't declared"]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_missing_terminators() {
+        let bb = BasicBlock::<Instruction>::new("entry");
+
+        assert_eq!(
+            messages(bb.verify().unwrap_err()),
+            vec!["entry: the block has no terminator"]
+        );
+    }
+
+    #[test]
+    fn it_reports_every_error() {
+        let mut bb = BasicBlock::<Instruction>::new("entry");
+        bb.instructions.push(Instruction::link(Position::initial("ctr"), Term::reference("value")));
+
+        let mut declared = BasicBlock::<Instruction>::new("then");
+        declared.terminator = Terminator::Jump(Label("nowhere".into()));
+        bb.declared_blocks.insert("then".into(), declared);
+        bb.terminator = Terminator::Cond(Term::reference("cond"), Label("then".into()), Label("otherwise".into()));
+
+        assert_eq!(
+            messages(bb.verify().unwrap_err()),
+            vec![
+                "entry: reference `%ctr` is used before being defined",
+                "entry: reference `%value` is used before being defined",
+                "entry: reference `%cond` is used before being defined",
+                "entry: the label `@otherwise` isn't declared",
+                "entry/then: the label `@nowhere` isn't declared",
+            ]
+        );
+    }
+}
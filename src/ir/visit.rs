@@ -14,11 +14,12 @@ pub enum Instruction {
     /// WHNF form.
     IncreaseLen(ArgumentIndex),
 
-    /// Visits an argument indexed by [ArgumentIndex], reduces it,
+    /// Visits the argument stored in the given slot of the vbuf, reduces it,
     /// and pushes a new visit into the current context.
     ///
-    /// It only performs the visit, if current vlen [Instruction::SetVLen], is
-    /// less than the given [ArgumentIndex].
+    /// It only performs the visit, if the slot is less than the current vlen
+    /// [Instruction::SetVLen] minus one, as the last slot becomes the new host,
+    /// by [Instruction::UpdateHost].
     Visit(ArgumentIndex),
 
     //>>> Internal
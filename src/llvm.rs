@@ -3,6 +3,11 @@
 #[allow(unused_macro_rules)]
 pub mod cstr;
 
-pub mod bridge;
+pub mod aot;
 pub mod execution;
+pub mod graph;
 pub mod apply;
+pub mod optimize;
+pub mod program;
+pub mod reduce;
+pub mod visit;
@@ -0,0 +1,232 @@
+//! Ahead-of-time compilation of the program module to a native executable.
+//!
+//! The module of [Codegen::build_program] is written to an object file, with
+//! the entry point of the program, and linked with the static runtime, the
+//! `trazodone_runtime` library [crate::runtime]. The entry point only calls the
+//! `hvm__aot_main` function of the runtime, that reduces `Main` with the reduce
+//! function of the module, `hvm__reduce`, and prints its normal form. The
+//! runtime reads the program from the globals of the module:
+//!   - `hvm__main`: The function id of `Main`;
+//!   - `hvm__symbols_len`, `hvm__symbol_ids`, `hvm__symbol_arities` and
+//!     `hvm__symbol_names`: The table of the functions and constructors of the
+//!     rule book, with their arities and their null terminated names, that are
+//!     used to collect and to read back the terms.
+//!
+//! The module can also be emitted as LLVM IR, bitcode or assembly, with `trazodone
+//! compile --emit`, to be read with the standard LLVM tools.
+
+use std::path::Path;
+use std::process::Command;
+
+use hvm::rulebook::RuleBook;
+use inkwell::module::{Linkage, Module};
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine};
+use inkwell::values::{BasicValue, GlobalValue};
+use inkwell::{AddressSpace, OptimizationLevel};
+
+use crate::llvm::apply::Codegen;
+
+/// The format of the emitted module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    LlvmIr,
+    Bitcode,
+    Assembly,
+    Object,
+}
+
+impl Emit {
+    /// The extension of the emitted file.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Emit::LlvmIr => "ll",
+            Emit::Bitcode => "bc",
+            Emit::Assembly => "s",
+            Emit::Object => "o",
+        }
+    }
+}
+
+/// The native libraries that the Rust standard library, in the static runtime,
+/// depends on. They're the ones of `rustc --print native-static-libs`, for the
+/// host target, as the program is compiled for the host.
+#[cfg(target_os = "linux")]
+const NATIVE_LIBRARIES: &[&str] = &["-lgcc_s", "-lutil", "-lrt", "-lpthread", "-lm", "-ldl"];
+
+#[cfg(target_os = "macos")]
+const NATIVE_LIBRARIES: &[&str] = &["-lSystem", "-lc", "-lm"];
+
+/// The other targets are left to the defaults of the C compiler.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+const NATIVE_LIBRARIES: &[&str] = &[];
+
+impl<'a> Codegen<'a> {
+    /// Builds the globals of the program, and the `main` function, that calls
+    /// the `hvm__aot_main` function of the runtime.
+    pub fn build_entry(&mut self, book: &RuleBook) -> Result<(), String> {
+        let i64_type = self.context.i64_type();
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+
+        let main = book.name_to_id.get("Main").ok_or("The program has no `Main` function")?;
+        self.add_constant("hvm__main", i64_type.const_int(*main, false));
+
+        let symbols = itertools::sorted(book.id_to_name.iter()).collect::<Vec<_>>();
+        self.add_constant("hvm__symbols_len", i64_type.const_int(symbols.len() as u64, false));
+
+        let ids = symbols
+            .iter()
+            .map(|(id, _)| i64_type.const_int(**id, false))
+            .collect::<Vec<_>>();
+        self.add_constant("hvm__symbol_ids", i64_type.const_array(&ids));
+
+        let arities = symbols
+            .iter()
+            .map(|(id, _)| {
+                let arity = book.id_to_smap.get(id).map_or(0, |smap| smap.len());
+                i64_type.const_int(arity as u64, false)
+            })
+            .collect::<Vec<_>>();
+        self.add_constant("hvm__symbol_arities", i64_type.const_array(&arities));
+
+        let names = symbols
+            .iter()
+            .map(|(id, name)| {
+                let name = self.context.const_string(name.as_bytes(), true);
+                let global = self.add_constant(&format!("hvm__symbol_name.{id}"), name);
+                global.as_pointer_value().const_cast(ptr_type)
+            })
+            .collect::<Vec<_>>();
+        self.add_constant("hvm__symbol_names", ptr_type.const_array(&names));
+
+        // Function signature: main() -> i32
+        let aot_main = self.module.add_function(
+            "hvm__aot_main",
+            i32_type.fn_type(&[], false),
+            Some(Linkage::External),
+        );
+        let function = self.module.add_function("main", i32_type.fn_type(&[], false), None);
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+
+        let status = self
+            .builder
+            .build_direct_call(aot_main, &[], "status")
+            .try_as_basic_value()
+            .left()
+            .expect("hvm__aot_main should return the exit status");
+        self.builder.build_return(Some(&status));
+
+        // Verify the function integrity
+        function.verify(true);
+
+        Ok(())
+    }
+
+    /// Adds a constant global to the module, with the given initializer.
+    fn add_constant<V: BasicValue<'a>>(&self, name: &str, value: V) -> GlobalValue<'a> {
+        let global = self.module.add_global(value.as_basic_value_enum().get_type(), None, name);
+        global.set_initializer(&value);
+        global.set_constant(true);
+        global
+    }
+}
+
+/// Creates the target machine of the host, where the program is compiled.
+pub fn create_target_machine(level: OptimizationLevel) -> Result<TargetMachine, String> {
+    Target::initialize_native(&InitializationConfig::default())
+        .map_err(|e| format!("Could not initialize llvm native target: {e}"))?;
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple).map_err(|e| format!("Could not find the target: {e}"))?;
+
+    target
+        .create_target_machine(
+            &triple,
+            &TargetMachine::get_host_cpu_name().to_string(),
+            &TargetMachine::get_host_cpu_features().to_string(),
+            level,
+            RelocMode::PIC,
+            CodeModel::Default,
+        )
+        .ok_or_else(|| format!("Could not create the target machine for {}", triple))
+}
+
+/// Writes the module in the given format, with the target of the host, even
+/// for the LLVM IR and the bitcode, so they match the generated code.
+pub fn emit(module: &Module, emit: Emit, level: OptimizationLevel, path: &Path) -> Result<(), String> {
+    let machine = create_target_machine(level)?;
+    module.set_triple(&machine.get_triple());
+    module.set_data_layout(&machine.get_target_data().get_data_layout());
+
+    match emit {
+        Emit::LlvmIr => module
+            .print_to_file(path)
+            .map_err(|e| format!("Could not write the LLVM IR: {e}")),
+        Emit::Bitcode => match module.write_bitcode_to_path(path) {
+            true => Ok(()),
+            false => Err("Could not write the bitcode".into()),
+        },
+        Emit::Assembly => machine
+            .write_to_file(module, FileType::Assembly, path)
+            .map_err(|e| format!("Could not write the assembly: {e}")),
+        Emit::Object => machine
+            .write_to_file(module, FileType::Object, path)
+            .map_err(|e| format!("Could not write the object file: {e}")),
+    }
+}
+
+/// Links the object file with the static runtime, with the C compiler of the
+/// `CC` environment variable, or `cc` by default.
+pub fn link(object: &Path, runtime: &Path, output: &Path) -> Result<(), String> {
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let status = Command::new(&compiler)
+        .arg(object)
+        .arg(runtime)
+        .args(NATIVE_LIBRARIES)
+        .arg("-o")
+        .arg(output)
+        .status()
+        .map_err(|e| format!("Could not run the linker `{compiler}`: {e}"))?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("The linker `{compiler}` failed with {status}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inkwell::context::Context;
+
+    use crate::cli::eval::{ir_codegen_book, parse_book, setup_global_context};
+    use crate::diagnostic::Source;
+    use crate::llvm::apply::Codegen;
+    use crate::passes::PassManager;
+
+    #[test]
+    fn it_builds_the_entry_of_the_program() {
+        let code = "(Add a b) = (+ a b)\n(Main) = (Add 1 2)";
+        let source = Source::new("main.hvm", code);
+        let book = parse_book(&source);
+        let global = setup_global_context(&book);
+        let groups = ir_codegen_book(&book, global, &PassManager::default())
+            .unwrap_or_else(|diagnostics| panic!("{diagnostics:?}"));
+
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+        let compiled = codegen.build_program(&book, &groups).unwrap();
+        codegen.build_entry(&book).unwrap();
+
+        codegen.module.verify().unwrap_or_else(|err| {
+            println!("{}", codegen.module.print_to_string().to_string_lossy());
+            panic!("Module is broken: {}", err.to_string_lossy());
+        });
+        assert!(compiled.contains(&book.name_to_id["Add"]));
+        assert!(codegen.module.get_function("main").is_some());
+        let globals = ["hvm__main", "hvm__symbols_len", "hvm__symbol_ids", "hvm__symbol_arities", "hvm__symbol_names"];
+        for global in globals {
+            assert!(codegen.module.get_global(global).is_some(), "missing `{global}`");
+        }
+    }
+}
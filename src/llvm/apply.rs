@@ -1,16 +1,19 @@
 use std::error::Error;
 
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
-use inkwell::values::BasicValueEnum;
+use inkwell::values::{BasicValueEnum, PhiValue};
+
+use crate::ir::graph::Phi;
 
 pub mod agent;
 pub mod bb;
 pub mod functions;
 pub mod instruction;
 pub mod main;
+pub mod pointer;
 pub mod position;
 pub mod runtime;
 pub mod term;
@@ -23,7 +26,8 @@ pub struct Codegen<'a> {
     pub builder: Builder<'a>,
 
     //>>>Contextual stuff
-    /// The current function let bound names
+    /// The current function let bound names, the function is in the SSA form, so
+    /// they're bound to the values directly.
     pub names: FxHashMap<String, BasicValueEnum<'a>>,
 
     /// The context parameter for the apply function
@@ -31,6 +35,21 @@ pub struct Codegen<'a> {
 
     /// The current basic block
     pub bb: Option<inkwell::basic_block::BasicBlock<'a>>,
+
+    /// The label -> basic block, binding map of the current function, the blocks
+    /// are created before being built, so they can be the target of jumps.
+    pub blocks: FxHashMap<String, inkwell::basic_block::BasicBlock<'a>>,
+
+    /// The label -> basic block, binding map of the blocks where each label ends,
+    /// that are the incoming blocks of the phi nodes.
+    pub exits: FxHashMap<String, inkwell::basic_block::BasicBlock<'a>>,
+
+    /// The phi nodes of the current function, their incoming values are added
+    /// after every block is built.
+    pub phis: Vec<(PhiValue<'a>, Phi)>,
+
+    /// The names of the current function that are bound to booleans.
+    pub bools: FxHashSet<String>,
     //<<<
 }
 
@@ -53,6 +72,10 @@ impl<'a> Codegen<'a> {
             names: FxHashMap::default(),
             ctx: None,
             bb: None,
+            blocks: FxHashMap::default(),
+            exits: FxHashMap::default(),
+            phis: Vec::new(),
+            bools: FxHashSet::default(),
         };
 
         Ok(codegen)
@@ -62,10 +85,14 @@ impl<'a> Codegen<'a> {
 #[cfg(test)]
 mod tests {
     use fxhash::FxHashMap;
+    use inkwell::targets::{InitializationConfig, Target};
     use inkwell::OptimizationLevel;
 
     use crate::cli::eval::{ir_codegen_book, setup_global_context};
+    use crate::ir::apply::Tag;
     use crate::ir::rule::RuleGroup;
+    use crate::llvm::optimize::Optimizer;
+    use crate::passes::PassManager;
 
     use super::*;
 
@@ -86,11 +113,178 @@ mod tests {
             .expect("Could not create execution engine");
     }
 
+    #[test]
+    fn it_inlines_the_pointer_layout() {
+        Target::initialize_native(&InitializationConfig::default()).unwrap();
+
+        let context = Context::create();
+        let codegen = Codegen::new(&context).unwrap();
+        let function_type = context.i64_type().fn_type(&[], false);
+        let function = codegen.module.add_function("pointer", function_type, None);
+        codegen.builder.position_at_end(context.append_basic_block(function, "entry"));
+
+        let position = codegen.u64(7);
+        let pointer = codegen.build_pointer(Tag::CONSTRUCTOR, Some(codegen.u64(5)), position);
+        let ext = codegen.build_pointer_ext(pointer);
+        codegen.builder.build_return(Some(&ext));
+
+        let engine = codegen
+            .module
+            .create_jit_execution_engine(OptimizationLevel::None)
+            .expect("Could not create execution engine");
+        unsafe {
+            let pointer = engine.get_function::<unsafe extern "C" fn() -> u64>("pointer").unwrap();
+            assert_eq!(pointer.call(), hvm::runtime::get_ext(hvm::runtime::Ctr(5, 7)));
+        }
+    }
+
+    #[test]
+    fn it_optimizes_the_module() {
+        let book = setup_book();
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+
+        codegen_entry(&mut codegen, &book["Main"]);
+        codegen_entry(&mut codegen, &book["Add"]);
+
+        let optimizer = Optimizer::new(3, None).unwrap();
+        optimizer.run(&codegen.module);
+
+        codegen.module.verify().unwrap_or_else(|err| {
+            println!("{}", codegen.module.print_to_string().to_string_lossy());
+            panic!("Module is broken: {}", err.to_string_lossy());
+        });
+        assert!(Optimizer::new(0, Some(&["licm".into()])).is_err());
+    }
+
+    #[test]
+    fn it_lowers_the_arity_terms() {
+        let block = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  if (%arg0/arity == #2):
+    %node = (get-position %arg0 0)
+    %field = (take-argument %node #1)
+    link [^host] %field
+    ret true
+  end
+  ret false
+"
+        .parse::<crate::ir::apply::Block>()
+        .unwrap();
+
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+        let group = RuleGroup {
+            name: "Arity".into(),
+            hvm_apply_graph: block.into_control_flow_graph(),
+            ..Default::default()
+        };
+
+        codegen_entry(&mut codegen, &group);
+    }
+
+    #[test]
+    fn it_lowers_the_println() {
+        let block = "
+entry:
+  println \"[trace] Example: rule 0\"
+  ret true
+"
+        .parse::<crate::ir::apply::Block>()
+        .unwrap();
+
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+        let group = RuleGroup {
+            name: "Example".into(),
+            hvm_apply_graph: block.into_control_flow_graph(),
+            ..Default::default()
+        };
+
+        codegen_entry(&mut codegen, &group);
+
+        let module = codegen.module.print_to_string().to_string();
+        assert!(module.contains("[trace] Example: rule 0"));
+        assert!(module.contains("call void @hvm__println"));
+    }
+
+    #[test]
+    fn it_lowers_the_visit_function() {
+        let bb = "
+goup:
+  %go-up = new_redex
+  (visit-argument 0)
+  ctx.cont = %go-up
+  ctx.host = $updated-host
+  ret true
+
+otherwise:
+  ret false
+
+entry:
+  %vlen = 0
+  %vbuf = new_vbuf
+  %vlen = + %vlen (int-is-whnf 0)
+  %vlen = + %vlen (int-is-whnf 1)
+  cond (vlen == 0) @goup @otherwise
+"
+        .parse::<crate::ir::visit::VisitBlock>()
+        .unwrap();
+
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+        let group = RuleGroup {
+            name: "Strict".into(),
+            ..Default::default()
+        };
+
+        codegen.initialize_std_functions();
+        let name = codegen.build_visit_function(&group, bb);
+
+        codegen.module.verify().unwrap_or_else(|err| {
+            println!("{}", codegen.module.print_to_string().to_string_lossy());
+            panic!("Module is broken: {}", err.to_string_lossy());
+        });
+        assert!(codegen.module.get_function(&name).is_some());
+    }
+
+    #[test]
+    fn it_lowers_the_reduce_function() {
+        let bb = crate::codegen::reduce::Codegen::default().build_reduce(&[(30, "Strict".into())]);
+        let bb = bb.to_string().parse::<crate::ir::reduce::ReduceBlock>().unwrap();
+        let block = "
+entry:
+  ret false
+"
+        .parse::<crate::ir::apply::Block>()
+        .unwrap();
+
+        let context = Context::create();
+        let mut codegen = Codegen::new(&context).unwrap();
+        let group = RuleGroup {
+            name: "Strict".into(),
+            hvm_apply_graph: block.into_control_flow_graph(),
+            ..Default::default()
+        };
+
+        codegen.initialize_std_functions();
+        codegen.build_apply_function(&group, group.hvm_apply_graph.clone()).unwrap();
+        codegen.build_visit_function(&group, group.hvm_visit.clone());
+        let name = codegen.build_reduce_function(bb);
+
+        codegen.module.verify().unwrap_or_else(|err| {
+            println!("{}", codegen.module.print_to_string().to_string_lossy());
+            panic!("Module is broken: {}", err.to_string_lossy());
+        });
+        assert_eq!(name, "hvm__reduce");
+    }
+
     fn codegen_entry(codegen: &mut Codegen, fun: &RuleGroup) {
-        let ir = fun.hvm_apply.clone().into_control_flow_graph();
+        let ir = fun.hvm_apply_graph.clone();
 
         codegen.initialize_std_functions();
-        codegen.build_apply_function(fun, ir);
+        codegen.build_apply_function(fun, ir).unwrap();
 
         // Verify the LLVM module integrity
         codegen.module.verify().unwrap_or_else(|err| {
@@ -112,6 +306,11 @@ mod tests {
         let book = hvm::language::rulebook::gen_rulebook(&file);
 
         let global = setup_global_context(&book);
-        ir_codegen_book(&book, global)
+        ir_codegen_book(&book, global, &PassManager::default()).unwrap_or_else(|diagnostics| {
+            for diagnostic in diagnostics {
+                eprintln!("{diagnostic}");
+            }
+            panic!("Failed to compile")
+        })
     }
 }
@@ -9,7 +9,7 @@ impl<'a> Codegen<'a> {
     /// This is a special case because it requires an alloca, and it's better to
     /// do that in a special function, because, this will desugar the agent into
     /// a [Term::alloc] and [Instruction::link] anyway.
-    pub fn build_agent(&self, agent: Agent) -> BasicValueEnum {
+    pub fn build_agent(&self, agent: Agent) -> BasicValueEnum<'a> {
         let value = self.hvm__alloc(self.u64(agent.arity));
 
         for (index, argument) in agent.arguments.iter().enumerate() {
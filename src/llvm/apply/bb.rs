@@ -1,7 +1,9 @@
+use fxhash::FxHashSet;
 use inkwell::basic_block::BasicBlock;
-use inkwell::values::FunctionValue;
+use inkwell::values::{BasicValue, FunctionValue};
 
-use crate::ir::apply::ApplyBasicBlock;
+use crate::ir::apply::{ApplyBasicBlock, Instruction, Let, Term};
+use crate::ir::graph::Label;
 use crate::llvm::apply::Codegen;
 
 impl<'a> Codegen<'a> {
@@ -10,10 +12,26 @@ impl<'a> Codegen<'a> {
         function: FunctionValue<'a>,
         bb: ApplyBasicBlock,
     ) -> BasicBlock<'a> {
-        let llvm_bb = self.context.append_basic_block(function, &bb.label);
+        let llvm_bb = match self.blocks.get(&bb.label) {
+            Some(llvm_bb) => *llvm_bb,
+            None => self.context.append_basic_block(function, &bb.label),
+        };
         self.builder.position_at_end(llvm_bb);
         self.bb = Some(llvm_bb);
 
+        // The incoming values are added later, as the predecessors can be built
+        // after the current block.
+        for phi in &bb.phis {
+            let phi_type = match self.bools.contains(&phi.name) {
+                true => self.context.bool_type(),
+                false => self.context.i64_type(),
+            };
+            let value = self.builder.build_phi(phi_type, &phi.name);
+
+            self.names.insert(phi.name.clone(), value.as_basic_value());
+            self.phis.push((value, phi.clone()));
+        }
+
         for instruction in &bb.instructions {
             self.build_instruction(instruction.clone());
         }
@@ -22,4 +40,63 @@ impl<'a> Codegen<'a> {
 
         llvm_bb
     }
+
+    /// Adds the incoming values of the phi nodes, from the blocks where the
+    /// predecessors ended. A predecessor that wasn't built, or that doesn't
+    /// define the incoming variable, is an error of the SSA form.
+    pub fn build_phi_incoming(&mut self) -> Result<(), String> {
+        for (value, phi) in std::mem::take(&mut self.phis) {
+            for (Label(label), name) in phi.incoming {
+                let Some(block) = self.exits.get(&label) else {
+                    return Err(format!("Phi {} has the incoming block {label}, that wasn't built", phi.name));
+                };
+                let Some(incoming) = self.names.get(&name) else {
+                    return Err(format!("Phi {} has the incoming value {name} from {label}, that isn't defined", phi.name));
+                };
+
+                value.add_incoming(&[(incoming as &dyn BasicValue<'a>, *block)]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the names of the graph that are bound to booleans, so the phi nodes
+/// are built with the right type.
+pub fn bool_names(bb: &ApplyBasicBlock) -> FxHashSet<String> {
+    fn is_bool(term: &Term, bools: &FxHashSet<String>) -> bool {
+        match term {
+            Term::Equal(..) | Term::LogicalOr(..) | Term::LogicalAnd(..) | Term::True | Term::False => true,
+            Term::Ref(name) => bools.contains(name),
+            Term::Select(select) => is_bool(&select.then, bools),
+            _ => false,
+        }
+    }
+
+    fn walk(bb: &ApplyBasicBlock, bools: &mut FxHashSet<String>) -> bool {
+        let mut changed = false;
+        for instruction in &bb.instructions {
+            if let Instruction::Let(Let { name, value }) = instruction {
+                if is_bool(value, bools) {
+                    changed |= bools.insert(name.clone());
+                }
+            }
+        }
+        for phi in &bb.phis {
+            if phi.incoming.iter().any(|(_, name)| bools.contains(name)) {
+                changed |= bools.insert(phi.name.clone());
+            }
+        }
+        for declared in bb.declared_blocks.values() {
+            changed |= walk(declared, bools);
+        }
+
+        changed
+    }
+
+    let mut bools = FxHashSet::default();
+    while walk(bb, &mut bools) {}
+
+    bools
 }
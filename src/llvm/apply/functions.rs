@@ -8,6 +8,18 @@ macro_rules! std_llvm_type {
             .i8_type()
             .ptr_type(inkwell::AddressSpace::default())
     };
+    ($codegen:expr, ptr) => {
+        $codegen
+            .context
+            .i8_type()
+            .ptr_type(inkwell::AddressSpace::default())
+    };
+    ($codegen:expr, str) => {
+        $codegen
+            .context
+            .i8_type()
+            .ptr_type(inkwell::AddressSpace::default())
+    };
     ($codegen:expr, bool) => {
         $codegen.context.bool_type()
     };
@@ -17,6 +29,9 @@ macro_rules! std_llvm_type {
     ($codegen:expr, u64) => {
         $codegen.context.i64_type()
     };
+    ($codegen:expr, f64) => {
+        $codegen.context.f64_type()
+    };
 }
 
 macro_rules! build_std_functions {
@@ -41,43 +56,57 @@ macro_rules! register_jit_function {
 
 macro_rules! std_function {
     ($name:ident(ctx) -> u64) => {
-        #[allow(clippy::needless_lifetimes)]
         #[allow(non_snake_case)]
-        pub fn $name<'b>(&'b self) -> inkwell::values::BasicValueEnum<'b> {
+        pub fn $name(&self) -> inkwell::values::BasicValueEnum<'a> {
+            self.call_std(stringify!($name), &[])
+        }
+    };
+    ($name:ident(ctx) -> bool) => {
+        #[allow(non_snake_case)]
+        pub fn $name(&self) -> inkwell::values::BasicValueEnum<'a> {
+            self.call_std(stringify!($name), &[])
+        }
+    };
+    ($name:ident(ctx) -> ptr) => {
+        #[allow(non_snake_case)]
+        pub fn $name(&self) -> inkwell::values::BasicValueEnum<'a> {
             self.call_std(stringify!($name), &[])
         }
     };
     ($name:ident(ctx, $($argsn:ident), + $(,)?) -> u64) => {
-        #[allow(clippy::needless_lifetimes)]
         #[allow(non_snake_case)]
-        pub fn $name<'b>(&'b self, $($argsn: inkwell::values::BasicValueEnum<'b>),+) -> inkwell::values::BasicValueEnum<'b> {
+        pub fn $name(&self, $($argsn: inkwell::values::BasicValueEnum<'a>),+) -> inkwell::values::BasicValueEnum<'a> {
             let arguments = &[$($argsn.into()),+];
             self.call_std(stringify!($name), arguments)
         }
     };
     ($name:ident(ctx) -> void) => {
-        #[allow(clippy::needless_lifetimes)]
         #[allow(non_snake_case)]
-        pub fn $name<'b>(&'b self) -> inkwell::values::InstructionValue<'b> {
+        pub fn $name(&self) -> inkwell::values::InstructionValue<'a> {
             self.call_void_std(stringify!($name), &[])
         }
     };
     ($name:ident(ctx, $($argsn:ident), + $(,)?) -> void) => {
-        #[allow(clippy::needless_lifetimes)]
         #[allow(non_snake_case)]
-        pub fn $name<'b>(&'b self, $($argsn: inkwell::values::BasicValueEnum<'b>),+) -> inkwell::values::InstructionValue<'b> {
+        pub fn $name(&self, $($argsn: inkwell::values::BasicValueEnum<'a>),+) -> inkwell::values::InstructionValue<'a> {
             let arguments = &[$($argsn.into()),+];
             self.call_void_std(stringify!($name), arguments)
         }
     };
     ($name:ident($($argsn:ident), * $(,)?) -> u64) => {
-        #[allow(clippy::needless_lifetimes)]
         #[allow(non_snake_case)]
-        pub fn $name<'b>(&'b self, $($argsn: inkwell::values::BasicValueEnum<'b>),*) -> inkwell::values::BasicValueEnum<'b> {
+        pub fn $name(&self, $($argsn: inkwell::values::BasicValueEnum<'a>),*) -> inkwell::values::BasicValueEnum<'a> {
             let arguments = &[$($argsn.into()),*];
             self.call_direct(stringify!($name), arguments)
         }
     };
+    ($name:ident($($argsn:ident), * $(,)?) -> void) => {
+        #[allow(non_snake_case)]
+        pub fn $name(&self, $($argsn: inkwell::values::BasicValueEnum<'a>),*) -> inkwell::values::InstructionValue<'a> {
+            let arguments = &[$($argsn.into()),*];
+            self.call_void_direct(stringify!($name), arguments)
+        }
+    };
 }
 
 pub(crate) use std_function;
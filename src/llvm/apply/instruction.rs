@@ -1,4 +1,5 @@
-use crate::ir::apply::{Free, Instruction, Let, Link};
+
+use crate::ir::apply::{Collect, Free, Instruction, Let, Link};
 
 use super::Codegen;
 
@@ -12,14 +13,12 @@ impl<'a> Codegen<'a> {
                 self.build_term(term);
             }
 
-            Instruction::Collect(_collect_instruction) => {}
+            Instruction::Collect(collect_instruction) => self.build_collect(collect_instruction),
             Instruction::Free(free_instruction) => self.build_free(free_instruction),
             Instruction::Link(link_instruction) => self.build_link(link_instruction),
             Instruction::Let(let_instruction) => self.build_let(let_instruction),
 
-            Instruction::Println(_message) => {
-                todo!("Instruction::Println")
-            }
+            Instruction::Println(message) => self.build_println(message),
 
             // These should be handled on the [crate::codegen::apply::graph]
             Instruction::Return(..) | Instruction::Metadata(..) | Instruction::If(..) => {
@@ -28,6 +27,12 @@ impl<'a> Codegen<'a> {
         }
     }
 
+    pub fn build_collect(&mut self, instruction: Collect) {
+        let term = self.build_term(instruction.term);
+
+        self.hvm__collect(term);
+    }
+
     pub fn build_free(&mut self, instruction: Free) {
         let position = self.build_term(instruction.position);
         let arity = self.u64(instruction.arity);
@@ -42,13 +47,16 @@ impl<'a> Codegen<'a> {
         self.hvm__link(position, term);
     }
 
-    pub fn build_let(&mut self, instruction: Let) {
-        let name = format!("let.{}", instruction.name);
-        let ptr = self.builder.build_alloca(self.context.i64_type(), &name);
+    /// Prints the message in the runtime, the message is stored as a global
+    /// null-terminated string, like the ones built by [crate::llvm::cstr::cstr].
+    pub fn build_println(&mut self, message: String) {
+        let message = self.builder.build_global_string_ptr(&message, "message");
 
-        self.names.insert(instruction.name, ptr.into());
+        self.hvm__println(message.as_pointer_value().into());
+    }
 
-        self.builder
-            .build_store(ptr, self.build_term(instruction.value));
+    pub fn build_let(&mut self, instruction: Let) {
+        let value = self.build_term(instruction.value);
+        self.names.insert(instruction.name, value);
     }
 }
@@ -2,12 +2,13 @@ use inkwell::AddressSpace;
 
 use crate::ir::apply::ApplyBasicBlock;
 use crate::ir::rule::RuleGroup;
+use crate::llvm::apply::bb::bool_names;
 use crate::llvm::apply::Codegen;
 
 pub type ApplyFn = unsafe extern "C" fn(*mut libc::c_void) -> bool;
 
 impl<'a> Codegen<'a> {
-    pub fn build_apply_function(&mut self, rule: &RuleGroup, bb: ApplyBasicBlock) -> String {
+    pub fn build_apply_function(&mut self, rule: &RuleGroup, bb: ApplyBasicBlock) -> Result<String, String> {
         // Function signature: <<name>>__apply(%ctx: *mut <<reduce_ctx>>) -> i1
         let function_type = self.context.bool_type().fn_type(
             &[self
@@ -25,17 +26,30 @@ impl<'a> Codegen<'a> {
 
         // Build entry
         self.ctx = Some(ctx);
+        self.names.clear();
+        self.blocks.clear();
+        self.exits.clear();
+
+        let bb = bb.into_ssa();
+        self.bools = bool_names(&bb);
         self.build_basic_block(function, bb);
+        self.build_phi_incoming()
+            .map_err(|err| format!("Could not build {}: {err}", rule.name))?;
 
         // Verify the function integrity
         function.verify(true);
 
-        name
+        Ok(name)
     }
 
     pub fn create_mangled_name(&mut self, rule: &RuleGroup) -> String {
-        let hash = format!("{:x}", fxhash::hash64(&rule.name));
-        let hash = hash[0..8].to_string();
-        format!("_HA{}{}{hash}", rule.name.len(), rule.name)
+        mangle_name(&rule.name)
     }
 }
+
+/// Mangles the name of the rule group, to the name of its compiled apply function.
+pub fn mangle_name(name: &str) -> String {
+    let hash = format!("{:x}", fxhash::hash64(&name));
+    let hash = hash[0..8].to_string();
+    format!("_HA{}{name}{hash}", name.len())
+}
@@ -0,0 +1,88 @@
+//! The HVM pointer layout, built as LLVM instructions instead of calls to the
+//! runtime, so LLVM can fold and reuse them.
+//!
+//! A pointer is a 64-bit integer, with the tag in the highest 4 bits, the
+//! extension (the color, the operator or the function id) in the next 28 bits,
+//! and the value (the position in the heap) in the lowest 32 bits:
+//!   TAG (4 bits) | EXT (28 bits) | VAL (32 bits)
+//!
+//! The numbers take the lowest 60 bits, after the tag. It's the same layout of
+//! [hvm::runtime::get_tag], [hvm::runtime::get_ext] and the pointer constructors.
+
+use inkwell::values::{BasicValueEnum, IntValue};
+
+use crate::ir::apply::Tag;
+use crate::llvm::apply::Codegen;
+
+pub const TAG_SHIFT: u64 = 60;
+pub const EXT_SHIFT: u64 = 32;
+pub const EXT_MASK: u64 = 0xFFF_FFFF;
+pub const VAL_MASK: u64 = 0xFFFF_FFFF;
+pub const NUM_MASK: u64 = 0xFFF_FFFF_FFFF_FFFF;
+
+impl<'a> Codegen<'a> {
+    /// Builds `pointer >> TAG_SHIFT`.
+    pub fn build_pointer_tag(&self, pointer: BasicValueEnum<'a>) -> BasicValueEnum<'a> {
+        let shift = self.int(TAG_SHIFT);
+        self.builder
+            .build_right_shift(pointer.into_int_value(), shift, false, "tag")
+            .into()
+    }
+
+    /// Builds `(pointer >> EXT_SHIFT) & EXT_MASK`.
+    pub fn build_pointer_ext(&self, pointer: BasicValueEnum<'a>) -> BasicValueEnum<'a> {
+        let shift = self.int(EXT_SHIFT);
+        let ext = self
+            .builder
+            .build_right_shift(pointer.into_int_value(), shift, false, "");
+        self.builder.build_and(ext, self.int(EXT_MASK), "ext").into()
+    }
+
+    /// Builds `pointer & NUM_MASK`.
+    pub fn build_pointer_number(&self, pointer: BasicValueEnum<'a>) -> BasicValueEnum<'a> {
+        self.builder
+            .build_and(pointer.into_int_value(), self.int(NUM_MASK), "number")
+            .into()
+    }
+
+    /// Builds `(pointer & VAL_MASK) + argument`, the location of the argument
+    /// in the heap.
+    pub fn build_pointer_loc(
+        &self,
+        pointer: BasicValueEnum<'a>,
+        argument: BasicValueEnum<'a>,
+    ) -> BasicValueEnum<'a> {
+        let value = self
+            .builder
+            .build_and(pointer.into_int_value(), self.int(VAL_MASK), "");
+        self.builder
+            .build_int_add(value, argument.into_int_value(), "loc")
+            .into()
+    }
+
+    /// Builds `(tag << TAG_SHIFT) | (ext << EXT_SHIFT) | value`, the value isn't
+    /// masked, like in the HVM constructors.
+    pub fn build_pointer(
+        &self,
+        tag: Tag,
+        ext: Option<BasicValueEnum<'a>>,
+        value: BasicValueEnum<'a>,
+    ) -> BasicValueEnum<'a> {
+        let mut pointer = self.builder.build_or(
+            self.int(tag.id() << TAG_SHIFT),
+            value.into_int_value(),
+            "",
+        );
+        if let Some(ext) = ext {
+            let shift = self.int(EXT_SHIFT);
+            let ext = self.builder.build_left_shift(ext.into_int_value(), shift, "");
+            pointer = self.builder.build_or(pointer, ext, "");
+        }
+
+        pointer.into()
+    }
+
+    fn int(&self, value: u64) -> IntValue<'a> {
+        self.context.i64_type().const_int(value, false)
+    }
+}
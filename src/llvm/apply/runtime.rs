@@ -8,15 +8,8 @@ impl<'a> Codegen<'a> {
     pub fn initialize_std_functions(&self) {
         build_std_functions!(self, {
             // create functions
-            hvm__create_function(u64, u64) -> u64,
-            hvm__create_constructor(u64, u64) -> u64,
-            hvm__create_app(u64) -> u64,
-            hvm__create_var(u64) -> u64,
-            hvm__create_lam(u64) -> u64,
-            hvm__create_f60(u64) -> u64,
-            hvm__create_u60(u64) -> u64,
-            hvm__create_binary(u64, u64) -> u64,
-            hvm__create_erased() -> u64,
+            hvm__create_f60(f64) -> u64,
+            hvm__operate(u64, u64, u64) -> u64,
 
             // std functions
             hvm__get_host_value(ctx) -> u64,
@@ -24,81 +17,109 @@ impl<'a> Codegen<'a> {
             hvm__get_term(ctx) -> u64,
             hvm__link(ctx, u64, u64) -> u64,
             hvm__load_argument(ctx, u64, u64) -> u64,
-            hvm__get_loc(u64, u64) -> u64,
-            hvm__get_ext(u64) -> u64,
-            hvm__get_number(u64) -> u64,
-            hvm__get_tag(u64) -> u64,
+            hvm__take_argument(ctx, u64, u64) -> u64,
+            hvm__arity_of(ctx, u64) -> u64,
             hvm__alloc(ctx, u64) -> u64,
-            hvm__llvm_eq(u64, u64) -> bool,
-            hvm__llvm_or(bool, bool) -> bool,
-            hvm__llvm_and(bool, bool) -> bool,
 
             // instructions
             hvm__free(ctx, u64, u64) -> void,
+            hvm__collect(ctx, u64) -> void,
+            hvm__println(str) -> void,
+
+            // visit functions
+            hvm__create_vbuf(ctx) -> ptr,
+            hvm__increase_vlen(ctx, u64, ptr, u64) -> u64,
+            hvm__insert_redex(ctx, u64) -> u64,
+            hvm__visit(ctx, u64, u64, ptr, u64) -> void,
+            hvm__update_cont(ctx, u64) -> void,
+            hvm__update_host(ctx, ptr, u64) -> void,
+
+            // reduce functions
+            hvm__load_term(ctx) -> u64,
+            hvm__visit_app(ctx) -> bool,
+            hvm__apply_app(ctx) -> bool,
+            hvm__visit_dup(ctx) -> bool,
+            hvm__apply_dup(ctx) -> bool,
+            hvm__visit_op2(ctx) -> bool,
+            hvm__apply_op2(ctx) -> bool,
+            hvm__visit_fun(ctx) -> bool,
+            hvm__apply_fun(ctx) -> bool,
+            hvm__complete_redex(ctx) -> bool,
+            hvm__next_visit(ctx) -> bool,
         });
     }
 
     pub fn register_functions_on_jit(&mut self, engine: &ExecutionEngine) {
         use crate::runtime::*;
 
-        register_jit_function!(self, engine, hvm__create_function);
-        register_jit_function!(self, engine, hvm__create_constructor);
-        register_jit_function!(self, engine, hvm__create_app);
-        register_jit_function!(self, engine, hvm__create_lam);
-        register_jit_function!(self, engine, hvm__create_var);
-        // register_jit_function!(self, engine, hvm__create_f60);
-        register_jit_function!(self, engine, hvm__create_u60);
-        register_jit_function!(self, engine, hvm__create_binary);
-        register_jit_function!(self, engine, hvm__create_erased);
+        register_jit_function!(self, engine, hvm__create_f60);
+        register_jit_function!(self, engine, hvm__operate);
 
         register_jit_function!(self, engine, hvm__get_host_value);
         register_jit_function!(self, engine, hvm__increment_cost);
         register_jit_function!(self, engine, hvm__get_term);
         register_jit_function!(self, engine, hvm__load_argument);
+        register_jit_function!(self, engine, hvm__take_argument);
+        register_jit_function!(self, engine, hvm__arity_of);
         register_jit_function!(self, engine, hvm__link);
-        register_jit_function!(self, engine, hvm__get_loc);
-        register_jit_function!(self, engine, hvm__get_ext);
-        register_jit_function!(self, engine, hvm__get_number);
-        register_jit_function!(self, engine, hvm__get_tag);
         register_jit_function!(self, engine, hvm__alloc);
-        register_jit_function!(self, engine, hvm__llvm_eq);
-        register_jit_function!(self, engine, hvm__llvm_or);
-        register_jit_function!(self, engine, hvm__llvm_and);
 
         register_jit_function!(self, engine, hvm__free);
+        register_jit_function!(self, engine, hvm__collect);
+        register_jit_function!(self, engine, hvm__println);
+
+        register_jit_function!(self, engine, hvm__create_vbuf);
+        register_jit_function!(self, engine, hvm__increase_vlen);
+        register_jit_function!(self, engine, hvm__insert_redex);
+        register_jit_function!(self, engine, hvm__visit);
+        register_jit_function!(self, engine, hvm__update_cont);
+        register_jit_function!(self, engine, hvm__update_host);
+
+        register_jit_function!(self, engine, hvm__load_term);
+        register_jit_function!(self, engine, hvm__visit_app);
+        register_jit_function!(self, engine, hvm__apply_app);
+        register_jit_function!(self, engine, hvm__visit_dup);
+        register_jit_function!(self, engine, hvm__apply_dup);
+        register_jit_function!(self, engine, hvm__visit_op2);
+        register_jit_function!(self, engine, hvm__apply_op2);
+        register_jit_function!(self, engine, hvm__visit_fun);
+        register_jit_function!(self, engine, hvm__apply_fun);
+        register_jit_function!(self, engine, hvm__complete_redex);
+        register_jit_function!(self, engine, hvm__next_visit);
     }
 
-    std_function! { hvm__create_function(fn_id, ptr) -> u64 }
-    std_function! { hvm__create_constructor(fn_id, ptr) -> u64 }
-    std_function! { hvm__create_app(position) -> u64 }
-    std_function! { hvm__create_lam(position) -> u64 }
-    std_function! { hvm__create_var(position) -> u64 }
     std_function! { hvm__create_f60(value) -> u64 }
-    std_function! { hvm__create_u60(value) -> u64 }
-    std_function! { hvm__create_binary(operand, position) -> u64 }
-    std_function! { hvm__create_erased() -> u64 }
+    std_function! { hvm__operate(operand, lhs, rhs) -> u64 }
 
     std_function! { hvm__get_host_value(ctx) -> u64 }
     std_function! { hvm__increment_cost(ctx) -> void }
     std_function! { hvm__get_term(ctx) -> u64 }
     std_function! { hvm__load_argument(ctx, a, b) -> u64 }
+    std_function! { hvm__take_argument(ctx, position, index) -> u64 }
+    std_function! { hvm__arity_of(ctx, a) -> u64 }
     std_function! { hvm__link(ctx, position, ptr) -> u64 }
-    std_function! { hvm__get_loc(a, b) -> u64 }
-    std_function! { hvm__get_ext(a) -> u64 }
-    std_function! { hvm__get_number(a) -> u64 }
-    std_function! { hvm__get_tag(a) -> u64 }
     std_function! { hvm__alloc(ctx, a) -> u64 }
-    std_function! { hvm__llvm_eq(a, b) -> u64 }
-    std_function! { hvm__llvm_or(a, b) -> u64 }
-    std_function! { hvm__llvm_and(a, b) -> u64 }
 
     std_function! { hvm__free(ctx, position, arity) -> void }
+    std_function! { hvm__collect(ctx, term) -> void }
+    std_function! { hvm__println(message) -> void }
 
-    pub fn u64(&self, value: u64) -> BasicValueEnum {
+    std_function! { hvm__create_vbuf(ctx) -> ptr }
+    std_function! { hvm__increase_vlen(ctx, index, vbuf, vlen) -> u64 }
+    std_function! { hvm__insert_redex(ctx, vlen) -> u64 }
+    std_function! { hvm__visit(ctx, slot, goup, vbuf, vlen) -> void }
+    std_function! { hvm__update_cont(ctx, goup) -> void }
+    std_function! { hvm__update_host(ctx, vbuf, vlen) -> void }
+
+    std_function! { hvm__load_term(ctx) -> u64 }
+    std_function! { hvm__complete_redex(ctx) -> bool }
+    std_function! { hvm__next_visit(ctx) -> bool }
+
+    pub fn u64(&self, value: u64) -> BasicValueEnum<'a> {
         self.context.i64_type().const_int(value, false).into()
     }
 
-    pub fn f64(&self, value: f64) -> BasicValueEnum {
+    pub fn f64(&self, value: f64) -> BasicValueEnum<'a> {
         self.context.f64_type().const_float(value).into()
     }
 
@@ -107,15 +128,15 @@ impl<'a> Codegen<'a> {
     ///
     /// # Example
     /// ```
-    /// let term = self.build_term(*get_ext.term);
+    /// let term = self.build_term(*arity_of.term);
     ///
-    /// self.call_std("hvm__get_ext", &[term.into()])
+    /// self.call_std("hvm__arity_of", &[term.into()])
     /// ```
-    pub fn call_std<'b>(
-        &'b self,
+    pub fn call_std(
+        &self,
         name: &str,
-        args: &[BasicMetadataValueEnum<'b>],
-    ) -> BasicValueEnum<'b> {
+        args: &[BasicMetadataValueEnum<'a>],
+    ) -> BasicValueEnum<'a> {
         let mut complete_args: Vec<BasicMetadataValueEnum> = vec![self.ctx.unwrap().into()];
         complete_args.extend_from_slice(args);
 
@@ -140,11 +161,11 @@ impl<'a> Codegen<'a> {
     /// ```
     /// self.call_void_std("hvm__increment_cost", &[])
     /// ```
-    pub fn call_void_std<'b>(
-        &'b self,
+    pub fn call_void_std(
+        &self,
         name: &str,
-        args: &[BasicMetadataValueEnum<'b>],
-    ) -> InstructionValue<'b> {
+        args: &[BasicMetadataValueEnum<'a>],
+    ) -> InstructionValue<'a> {
         let mut complete_args: Vec<BasicMetadataValueEnum> = vec![self.ctx.unwrap().into()];
         complete_args.extend_from_slice(args);
 
@@ -162,15 +183,28 @@ impl<'a> Codegen<'a> {
     }
 
     /// Call a function a function that returns a [BasicValueEnum].
-    pub fn call_direct<'b>(
-        &'b self,
+    pub fn call_direct(
+        &self,
         name: &str,
-        args: &[BasicMetadataValueEnum<'b>],
-    ) -> BasicValueEnum<'b> {
+        args: &[BasicMetadataValueEnum<'a>],
+    ) -> BasicValueEnum<'a> {
         self.builder
             .build_direct_call(self.module.get_function(name).unwrap(), args.as_ref(), "")
             .try_as_basic_value()
             .left()
             .unwrap_or_else(|| panic!("{} should return a BasicValueEnum", name))
     }
+
+    /// Call a function a function that returns nothing(or void, or unit).
+    pub fn call_void_direct(
+        &self,
+        name: &str,
+        args: &[BasicMetadataValueEnum<'a>],
+    ) -> InstructionValue<'a> {
+        self.builder
+            .build_direct_call(self.module.get_function(name).unwrap(), args.as_ref(), "")
+            .try_as_basic_value()
+            .right()
+            .unwrap_or_else(|| panic!("{} should return an InstructionValue", name))
+    }
 }
@@ -1,18 +1,21 @@
 use inkwell::values::BasicValueEnum;
 
-use crate::ir::apply::{GetExt, GetNumber, GetPosition, GetTag, LoadArgument, Term};
+use crate::ir::apply::{
+    build_binary_op, ArityOf, Binary, GetExt, GetNumber, GetPosition, GetTag, LoadArgument, Select,
+    TakeArgument, Term,
+};
 
 use super::Codegen;
 
 impl<'a> Codegen<'a> {
-    pub fn build_term(&self, term: Term) -> BasicValueEnum {
+    pub fn build_term(&self, term: Term) -> BasicValueEnum<'a> {
         match term {
             Term::Current => self.hvm__get_term(),
 
             Term::Tag(tag) => self.context.i64_type().const_int(tag.id(), false).into(),
 
-            Term::ArityOf(_) => todo!(),
-            Term::TakeArgument(_) => todo!(),
+            Term::ArityOf(arity_of) => self.build_arity_of(arity_of),
+            Term::TakeArgument(take_argument) => self.build_take_argument(take_argument),
 
             Term::GetExt(get_ext) => self.build_get_ext(get_ext),
             Term::GetNumber(get_number) => self.build_get_number(get_number),
@@ -23,8 +26,11 @@ impl<'a> Codegen<'a> {
             Term::Create(value) => self.build_value(value),
             Term::Alloc(alloc) => self.hvm__alloc(self.u64(alloc.size)),
             Term::Agent(agent) => self.build_agent(agent),
+            Term::Operate(binary) => self.build_operate(binary),
+            Term::Select(select) => self.build_select(select),
 
             Term::Ext(id, _) => self.context.i64_type().const_int(id, false).into(),
+            Term::Int(value) => self.u64(value),
 
             Term::Equal(box lhs, box rhs) => self.build_equal(lhs, rhs),
             Term::LogicalOr(box lhs, box rhs) => self.build_logical_or(lhs, rhs),
@@ -39,36 +45,48 @@ impl<'a> Codegen<'a> {
         }
     }
 
-    pub fn build_get_ext(&self, term: GetExt) -> BasicValueEnum {
+    pub fn build_arity_of(&self, term: ArityOf) -> BasicValueEnum<'a> {
         let llvm = self.build_term(*term.term);
-        self.hvm__get_ext(llvm)
+        self.hvm__arity_of(llvm)
     }
 
-    pub fn build_get_number(&self, term: GetNumber) -> BasicValueEnum {
+    pub fn build_take_argument(&self, term: TakeArgument) -> BasicValueEnum<'a> {
+        let position = self.build_position(term.position);
+        let argument_index = self.build_term(*term.argument_index);
+
+        self.hvm__take_argument(position, argument_index)
+    }
+
+    pub fn build_get_ext(&self, term: GetExt) -> BasicValueEnum<'a> {
         let llvm = self.build_term(*term.term);
-        self.hvm__get_number(llvm)
+        self.build_pointer_ext(llvm)
     }
 
-    pub fn build_get_tag(&self, term: GetTag) -> BasicValueEnum {
+    pub fn build_get_number(&self, term: GetNumber) -> BasicValueEnum<'a> {
         let llvm = self.build_term(*term.term);
-        self.hvm__get_tag(llvm)
+        self.build_pointer_number(llvm)
     }
 
-    pub fn build_get_position(&self, term: GetPosition) -> BasicValueEnum {
+    pub fn build_get_tag(&self, term: GetTag) -> BasicValueEnum<'a> {
+        let llvm = self.build_term(*term.term);
+        self.build_pointer_tag(llvm)
+    }
+
+    pub fn build_get_position(&self, term: GetPosition) -> BasicValueEnum<'a> {
         let llvm = self.build_term(*term.term);
         let position = self.u64(term.position);
 
-        self.hvm__get_loc(llvm, position)
+        self.build_pointer_loc(llvm, position)
     }
 
-    pub fn build_load_argument(&self, term: LoadArgument) -> BasicValueEnum {
+    pub fn build_load_argument(&self, term: LoadArgument) -> BasicValueEnum<'a> {
         let llvm = self.build_term(*term.term);
         let argument_index = self.u64(term.argument_index);
 
         self.hvm__load_argument(llvm, argument_index)
     }
 
-    pub fn build_equal(&self, lhs: Term, rhs: Term) -> BasicValueEnum {
+    pub fn build_equal(&self, lhs: Term, rhs: Term) -> BasicValueEnum<'a> {
         let lhs = self.build_term(lhs);
         let rhs = self.build_term(rhs);
 
@@ -82,29 +100,87 @@ impl<'a> Codegen<'a> {
             .into()
     }
 
-    /// TODO: use native LLVM constructs, instead of calling a function.
-    pub fn build_logical_or(&self, lhs: Term, rhs: Term) -> BasicValueEnum {
-        let lhs = self.build_term(lhs);
-        let rhs = self.build_term(rhs);
+    /// Builds a short-circuit logical or, the right-hand side is only evaluated
+    /// if the left-hand side is false.
+    pub fn build_logical_or(&self, lhs: Term, rhs: Term) -> BasicValueEnum<'a> {
+        self.build_short_circuit(lhs, rhs, true)
+    }
 
-        self.hvm__llvm_or(lhs, rhs)
+    /// Builds a short-circuit logical and, the right-hand side is only evaluated
+    /// if the left-hand side is true.
+    pub fn build_logical_and(&self, lhs: Term, rhs: Term) -> BasicValueEnum<'a> {
+        self.build_short_circuit(lhs, rhs, false)
     }
 
-    /// TODO: use native LLVM constructs, instead of calling a function.
-    pub fn build_logical_and(&self, lhs: Term, rhs: Term) -> BasicValueEnum {
-        let lhs = self.build_term(lhs);
-        let rhs = self.build_term(rhs);
+    /// Builds the branches of a short-circuit operation, it jumps directly to the
+    /// merge block, with `short_value`, if the left-hand side is `short_value`.
+    fn build_short_circuit(&self, lhs: Term, rhs: Term, short_value: bool) -> BasicValueEnum<'a> {
+        let function = self.builder.get_insert_block().unwrap().get_parent().unwrap();
+
+        let lhs = self.build_term(lhs).into_int_value();
+        let lhs_bb = self.builder.get_insert_block().unwrap();
+        let rhs_bb = self.context.append_basic_block(function, "");
+        let merge_bb = self.context.append_basic_block(function, "");
+
+        if short_value {
+            self.builder.build_conditional_branch(lhs, merge_bb, rhs_bb);
+        } else {
+            self.builder.build_conditional_branch(lhs, rhs_bb, merge_bb);
+        }
 
-        self.hvm__llvm_and(lhs, rhs)
+        self.builder.position_at_end(rhs_bb);
+        let rhs = self.build_term(rhs).into_int_value();
+        let rhs_bb = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb);
+
+        self.builder.position_at_end(merge_bb);
+        let short_value = self.context.bool_type().const_int(short_value as u64, false);
+        let phi = self.builder.build_phi(self.context.bool_type(), "");
+        phi.add_incoming(&[(&short_value, lhs_bb), (&rhs, rhs_bb)]);
+
+        phi.as_basic_value()
+    }
+
+    pub fn build_operate(&self, binary: Binary) -> BasicValueEnum<'a> {
+        let operand = self.u64(build_binary_op(binary.op));
+        let lhs = self.build_term(*binary.lhs);
+        let rhs = self.build_term(*binary.rhs);
+
+        self.hvm__operate(operand, lhs, rhs)
+    }
+
+    /// Builds the branches of a select, only the selected term is evaluated, and
+    /// its value is merged with a phi.
+    pub fn build_select(&self, select: Select) -> BasicValueEnum<'a> {
+        let function = self.builder.get_insert_block().unwrap().get_parent().unwrap();
+
+        let condition = self.build_term(*select.condition).into_int_value();
+        let then_bb = self.context.append_basic_block(function, "");
+        let otherwise_bb = self.context.append_basic_block(function, "");
+        let merge_bb = self.context.append_basic_block(function, "");
+        self.builder.build_conditional_branch(condition, then_bb, otherwise_bb);
+
+        self.builder.position_at_end(then_bb);
+        let then = self.build_term(*select.then);
+        let then_bb = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb);
+
+        self.builder.position_at_end(otherwise_bb);
+        let otherwise = self.build_term(*select.otherwise);
+        let otherwise_bb = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(merge_bb);
+
+        self.builder.position_at_end(merge_bb);
+        let phi = self.builder.build_phi(then.get_type(), "");
+        phi.add_incoming(&[(&then, then_bb), (&otherwise, otherwise_bb)]);
+
+        phi.as_basic_value()
     }
 
-    pub fn build_ref(&self, reference: String) -> BasicValueEnum {
-        let alloca = *self
+    pub fn build_ref(&self, reference: String) -> BasicValueEnum<'a> {
+        *self
             .names
             .get(&reference)
-            .unwrap_or_else(|| panic!("Reference {:?} not found", reference,));
-
-        self.builder
-            .build_load(self.context.i64_type(), alloca.into_pointer_value(), "")
+            .unwrap_or_else(|| panic!("Reference {:?} not found", reference,))
     }
 }
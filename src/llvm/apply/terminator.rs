@@ -1,4 +1,5 @@
 use inkwell::values::FunctionValue;
+use itertools::Itertools;
 
 use crate::ir::{
     apply::ApplyBasicBlock,
@@ -9,28 +10,64 @@ use super::Codegen;
 
 impl<'a> Codegen<'a> {
     pub fn build_terminator(&mut self, bb: ApplyBasicBlock, function: FunctionValue<'a>) {
+        // The current insert block can differ from the [Codegen::bb], if the
+        // instructions have created new blocks, like short-circuit operations.
+        let old_bb = self.builder.get_insert_block().unwrap();
+
+        // Every declared block is created before the blocks are built, because
+        // the branches can jump to the join blocks declared next to them.
+        let declared_blocks = bb
+            .declared_blocks
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect::<Vec<_>>();
+        for (label, _) in &declared_blocks {
+            let llvm_bb = self.context.append_basic_block(function, label);
+            self.blocks.insert(label.clone(), llvm_bb);
+        }
+        for (_, declared) in declared_blocks {
+            self.build_basic_block(function, declared);
+        }
+
+        self.builder.position_at_end(old_bb);
         match bb.terminator {
-            Terminator::Unreachable => {}
-            Terminator::Debug(_) => {}
-            Terminator::Jump(_) => {}
+            Terminator::Unreachable | Terminator::Debug(..) => {
+                self.builder.build_unreachable();
+            }
+            Terminator::Jump(Label(target)) => {
+                self.builder.build_unconditional_branch(self.get_block(&target));
+            }
             Terminator::Return(value) => {
                 self.builder.build_return(Some(&self.build_term(value)));
             }
             Terminator::Cond(cond, Label(then), Label(otherwise)) => {
-                let old_bb = self.bb.unwrap();
-
-                let then = bb.declared_blocks.get(&then).unwrap();
-                let then = self.build_basic_block(function, then.clone());
-                let otherwise = bb.declared_blocks.get(&otherwise).unwrap();
-                let otherwise = self.build_basic_block(function, otherwise.clone());
-
-                self.builder.position_at_end(old_bb);
+                let condition = self.build_term(cond).into_int_value();
                 self.builder.build_conditional_branch(
-                    self.build_term(cond).into_int_value(),
-                    then,
-                    otherwise,
+                    condition,
+                    self.get_block(&then),
+                    self.get_block(&otherwise),
                 );
             }
+            Terminator::Switch(term, cases, Label(default)) => {
+                let value = self.build_term(term).into_int_value();
+                let cases = cases
+                    .into_iter()
+                    .map(|(case, Label(target))| (self.build_term(case).into_int_value(), self.get_block(&target)))
+                    .collect::<Vec<_>>();
+                self.builder.build_switch(value, self.get_block(&default), &cases);
+            }
         }
+
+        // The terms can create new blocks, like short-circuit operations, so the
+        // block where the terminator was built is the incoming block of the phis.
+        let exit = self.builder.get_insert_block().unwrap();
+        self.exits.insert(bb.label, exit);
+    }
+
+    fn get_block(&self, label: &str) -> inkwell::basic_block::BasicBlock<'a> {
+        *self
+            .blocks
+            .get(label)
+            .unwrap_or_else(|| panic!("Block {label:?} not found"))
     }
 }
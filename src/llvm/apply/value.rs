@@ -1,41 +1,55 @@
 use inkwell::values::BasicValueEnum;
 
-use crate::ir::apply::{build_binary_op, Value, F60, U60};
+use crate::ir::apply::{build_binary_op, Color, Tag, Value, F60, U60};
+use crate::llvm::apply::pointer::NUM_MASK;
 
 use super::Codegen;
 
 impl<'a> Codegen<'a> {
-    pub fn build_value(&self, value: Value) -> BasicValueEnum {
+    /// Builds the pointer of the value, following the HVM pointer layout, only
+    /// the [F60] conversion is a call to the runtime.
+    pub fn build_value(&self, value: Value) -> BasicValueEnum<'a> {
         match value {
-            // TODO: super position stuff
-            Value::Dp0(..) => todo!(),
-            Value::Dp1(..) => todo!(),
-            Value::Super(..) => todo!(),
+            Value::Dp0(Color(color), position) => {
+                let color = self.u64(color);
+                let position = self.build_position(position);
+                self.build_pointer(Tag::DUP0, Some(color), position)
+            }
+            Value::Dp1(Color(color), position) => {
+                let color = self.u64(color);
+                let position = self.build_position(position);
+                self.build_pointer(Tag::DUP1, Some(color), position)
+            }
+            Value::Super(Color(color), position) => {
+                let color = self.u64(color);
+                let position = self.build_position(position);
+                self.build_pointer(Tag::SUPER, Some(color), position)
+            }
 
             //
             Value::Argument(..) => todo!(),
-            Value::Atom(position) => self.hvm__create_var(self.build_position(position)),
-            Value::Lam(position) => self.hvm__create_lam(self.build_position(position)),
-            Value::App(position) => self.hvm__create_app(self.build_position(position)),
-            Value::U60(U60(value)) => self.hvm__create_u60(self.u64(value)),
+            Value::Atom(position) => self.build_pointer(Tag::ATOM, None, self.build_position(position)),
+            Value::Lam(position) => self.build_pointer(Tag::LAM, None, self.build_position(position)),
+            Value::App(position) => self.build_pointer(Tag::APP, None, self.build_position(position)),
+            Value::U60(U60(value)) => self.build_pointer(Tag::U60, None, self.u64(value & NUM_MASK)),
             Value::F60(F60(value)) => self.hvm__create_f60(self.f64(value)),
             Value::Binary(binary, position) => {
                 let operand = self.u64(build_binary_op(binary.op));
                 let position = self.build_position(position);
 
-                self.hvm__create_binary(operand, position)
+                self.build_pointer(Tag::BINARY, Some(operand), position)
             }
             Value::Function(fn_id, position) => {
                 let fn_id = self.u64(fn_id.1);
                 let position = self.build_position(position);
-                self.hvm__create_function(fn_id, position)
+                self.build_pointer(Tag::FUNCTION, Some(fn_id), position)
             }
             Value::Constructor(fn_id, position) => {
                 let fn_id = self.u64(fn_id.1);
                 let position = self.build_position(position);
-                self.hvm__create_constructor(fn_id, position)
+                self.build_pointer(Tag::CONSTRUCTOR, Some(fn_id), position)
             }
-            Value::Erased => self.hvm__create_erased(),
+            Value::Erased => self.build_pointer(Tag::ERASED, None, self.u64(0)),
         }
     }
 }
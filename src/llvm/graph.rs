@@ -0,0 +1,80 @@
+//! Lowering of the control flow graphs without phi nodes to LLVM IR, like the
+//! ones of the visit and the reduce functions.
+//!
+//! Every term of their terminators is an integer, so the blocks are built the
+//! same way, only the instructions and the terms differ, see [LowerInstruction].
+
+use inkwell::values::{FunctionValue, IntValue};
+use itertools::Itertools;
+
+use crate::ir::graph::{BasicBlock, HasTerm, Label, Terminator};
+use crate::llvm::apply::Codegen;
+
+/// The instructions of a control flow graph, that are lowered with the state of
+/// the function, like the stack slots of the visit function.
+pub trait LowerInstruction<'a>: HasTerm {
+    type State: Copy;
+
+    fn build_instruction(self, codegen: &mut Codegen<'a>, state: Self::State);
+
+    fn build_term(term: Self::Term, codegen: &Codegen<'a>, state: Self::State) -> IntValue<'a>;
+}
+
+impl<'a> Codegen<'a> {
+    /// Builds the block, and then its declared blocks, the blocks that are
+    /// created before, like the entry block, are reused.
+    pub fn build_graph_block<I>(&mut self, function: FunctionValue<'a>, bb: BasicBlock<I>, state: I::State)
+    where
+        I: LowerInstruction<'a>,
+    {
+        let llvm_bb = match self.blocks.get(&bb.label) {
+            Some(llvm_bb) => *llvm_bb,
+            None => self.context.append_basic_block(function, &bb.label),
+        };
+        self.builder.position_at_end(llvm_bb);
+
+        for instruction in bb.instructions {
+            instruction.build_instruction(self, state);
+        }
+
+        let declared_blocks = bb
+            .declared_blocks
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect::<Vec<_>>();
+        for (label, _) in &declared_blocks {
+            let declared = self.context.append_basic_block(function, label);
+            self.blocks.insert(label.clone(), declared);
+        }
+        for (_, declared) in declared_blocks {
+            self.build_graph_block(function, declared, state);
+        }
+
+        self.builder.position_at_end(llvm_bb);
+        match bb.terminator {
+            Terminator::Unreachable | Terminator::Debug(..) => {
+                self.builder.build_unreachable();
+            }
+            Terminator::Return(value) => {
+                let value = I::build_term(value, self, state);
+                self.builder.build_return(Some(&value));
+            }
+            Terminator::Jump(Label(target)) => {
+                self.builder.build_unconditional_branch(self.blocks[&target]);
+            }
+            Terminator::Cond(condition, Label(then), Label(otherwise)) => {
+                let condition = I::build_term(condition, self, state);
+                self.builder
+                    .build_conditional_branch(condition, self.blocks[&then], self.blocks[&otherwise]);
+            }
+            Terminator::Switch(term, cases, Label(default)) => {
+                let value = I::build_term(term, self, state);
+                let cases = cases
+                    .into_iter()
+                    .map(|(case, Label(target))| (I::build_term(case, self, state), self.blocks[&target]))
+                    .collect::<Vec<_>>();
+                self.builder.build_switch(value, self.blocks[&default], &cases);
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Optimization pipeline of the LLVM module, before it reaches the execution engine.
+//!
+//! The [Optimizer] runs an ordered list of named LLVM passes over the whole module,
+//! so the compiled functions can be inlined in the reduce functions. The passes
+//! are chosen by the optimization level, in the command line with `-O0` to `-O3`,
+//! or by a custom list with `--llvm-passes`, and the module can be dumped before
+//! and after them with `--dump-llvm`.
+//!
+//! The available passes are:
+//!   - `mem2reg`: Promotes the stack slots to SSA values, like the visit slots;
+//!   - `instcombine`: Combines and folds the instructions, like the pointer masks;
+//!   - `reassociate`: Reorders the arithmetic, to fold more constants;
+//!   - `gvn`: Removes the redundant instructions, like the repeated tag checks;
+//!   - `simplifycfg`: Merges and removes the basic blocks;
+//!   - `inline`: Inlines the compiled functions in their callers;
+//!   - `adce`: Removes the dead instructions, aggressively.
+
+use inkwell::module::Module;
+use inkwell::passes::PassManager;
+use inkwell::OptimizationLevel;
+
+/// The available passes, in the order of the `-O3` pipeline.
+pub const PASSES: [&str; 7] = ["inline", "mem2reg", "instcombine", "reassociate", "gvn", "simplifycfg", "adce"];
+
+/// Runs the passes in order, over the LLVM module.
+#[derive(Debug, Clone)]
+pub struct Optimizer {
+    /// The optimization level of the machine code generation.
+    pub level: OptimizationLevel,
+
+    passes: Vec<String>,
+
+    /// Dumps the module before and after the passes, in the standard error.
+    pub dump: bool,
+}
+
+impl Optimizer {
+    /// Creates an optimizer with the passes of the given level, from 0 to 3, or
+    /// with the given passes, in the given order. The `none` name can be used to
+    /// run no passes.
+    pub fn new(level: u8, names: Option<&[String]>) -> Result<Self, String> {
+        let passes = match names {
+            Some(names) => names
+                .iter()
+                .filter(|name| !name.is_empty() && *name != "none")
+                .map(|name| match PASSES.contains(&name.as_str()) {
+                    true => Ok(name.clone()),
+                    false => Err(format!(
+                        "unknown llvm pass `{name}`, the available passes are: {}",
+                        PASSES.join(", ")
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+            None => level_passes(level)?.iter().map(|name| name.to_string()).collect(),
+        };
+
+        let level = match level {
+            0 => OptimizationLevel::None,
+            1 => OptimizationLevel::Less,
+            2 => OptimizationLevel::Default,
+            _ => OptimizationLevel::Aggressive,
+        };
+
+        Ok(Self {
+            level,
+            passes,
+            dump: false,
+        })
+    }
+
+    pub fn with_dump(mut self, dump: bool) -> Self {
+        self.dump = dump;
+        self
+    }
+
+    /// Runs the passes over the module, it must run before getting the addresses
+    /// of the functions, as the execution engine compiles the module then.
+    pub fn run(&self, module: &Module) {
+        if self.dump {
+            eprintln!("; module before the llvm passes\n{}", module.print_to_string().to_string_lossy());
+        }
+
+        let manager = PassManager::create(());
+        for name in &self.passes {
+            add_pass(&manager, name);
+        }
+        manager.run_on(module);
+
+        if self.dump {
+            eprintln!(
+                "; module after the llvm passes: {}\n{}",
+                self.passes.join(", "),
+                module.print_to_string().to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Returns the passes of the optimization level.
+fn level_passes(level: u8) -> Result<&'static [&'static str], String> {
+    match level {
+        0 => Ok(&[]),
+        1 => Ok(&["mem2reg", "instcombine", "simplifycfg"]),
+        2 => Ok(&["mem2reg", "instcombine", "reassociate", "gvn", "simplifycfg"]),
+        3 => Ok(&PASSES),
+        _ => Err(format!("unknown optimization level `{level}`, the levels are from 0 to 3")),
+    }
+}
+
+fn add_pass(manager: &PassManager<Module>, name: &str) {
+    match name {
+        "inline" => manager.add_function_inlining_pass(),
+        "mem2reg" => manager.add_promote_memory_to_register_pass(),
+        "instcombine" => manager.add_instruction_combining_pass(),
+        "reassociate" => manager.add_reassociate_pass(),
+        "gvn" => manager.add_gvn_pass(),
+        "simplifycfg" => manager.add_cfg_simplification_pass(),
+        "adce" => manager.add_aggressive_dce_pass(),
+        _ => unreachable!("the pass `{name}` is checked when the optimizer is created"),
+    }
+}
@@ -0,0 +1,42 @@
+//! Builds every compiled rule group in the same module, with the reduce function
+//! that dispatches to them, it's the module used by the JIT and the AOT compiler.
+
+use fxhash::FxHashMap;
+use hvm::rulebook::RuleBook;
+
+use crate::codegen::reduce;
+use crate::ir::reduce::FunctionId;
+use crate::ir::rule::RuleGroup;
+use crate::llvm::apply::Codegen;
+
+impl<'a> Codegen<'a> {
+    /// Builds the apply and visit functions of every rule group of the book, and
+    /// the reduce function, returning the ids of the compiled functions. The
+    /// rule groups without IR are interpreted.
+    pub fn build_program(
+        &mut self,
+        book: &RuleBook,
+        groups: &FxHashMap<String, RuleGroup>,
+    ) -> Result<Vec<FunctionId>, String> {
+        self.initialize_std_functions();
+
+        let mut functions = Vec::new();
+        for (id, name) in itertools::sorted(book.id_to_name.iter()) {
+            if *id <= 29 {
+                // Skip built-in constructors
+                continue;
+            }
+
+            if let Some(group) = groups.get(name) {
+                self.build_apply_function(group, group.hvm_apply_graph.clone())?;
+                self.build_visit_function(group, group.hvm_visit.clone());
+                functions.push((*id, name.clone()));
+            }
+        }
+
+        let bb = reduce::Codegen::default().build_reduce(&functions);
+        self.build_reduce_function(bb);
+
+        Ok(functions.into_iter().map(|(id, _)| id).collect())
+    }
+}
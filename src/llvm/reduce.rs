@@ -0,0 +1,99 @@
+//! Lowering of the [crate::ir::reduce] control flow graph to LLVM IR.
+//!
+//! The reduce function is the reduction loop of the original HVM code, with the
+//! `FAST_VISIT` and `FAST_APPLY` of the compiled functions, it calls the compiled
+//! visit and apply functions directly, so it must be built in the same module,
+//! after every compiled function. The other terms are reduced by the rules of
+//! the runtime, like `hvm__visit_app` and `hvm__apply_app`.
+
+use inkwell::values::IntValue;
+use inkwell::AddressSpace;
+
+use crate::ir::reduce::{Instruction, ReduceBlock, Stage, Term};
+use crate::llvm::apply::main::mangle_name;
+use crate::llvm::apply::Codegen;
+use crate::llvm::graph::LowerInstruction;
+
+/// The name of the reduce function, that is called by the JIT and by the static
+/// runtime of the AOT compiled programs.
+pub const REDUCE_FUNCTION: &str = "hvm__reduce";
+
+impl<'a> LowerInstruction<'a> for Instruction {
+    type State = ();
+
+    fn build_instruction(self, codegen: &mut Codegen<'a>, _: ()) {
+        match self {
+            Instruction::LoadTerm => {
+                let term = codegen.hvm__load_term();
+                codegen.names.insert("term".into(), term);
+            }
+            Instruction::SetFunctionId => {
+                let term = codegen.hvm__get_term();
+                let fid = codegen.build_pointer_ext(term);
+                codegen.names.insert("fid".into(), fid);
+            }
+        }
+    }
+
+    fn build_term(term: Term, codegen: &Codegen<'a>, _: ()) -> IntValue<'a> {
+        codegen.build_reduce_term(term)
+    }
+}
+
+impl<'a> Codegen<'a> {
+    pub fn build_reduce_function(&mut self, bb: ReduceBlock) -> String {
+        // Function signature: hvm__reduce(%ctx: *mut <<reduce_ctx>>) -> i1
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        let function_type = self.context.bool_type().fn_type(&[ptr_type.into()], false);
+
+        let name = REDUCE_FUNCTION.to_string();
+        let function = self.module.add_function(&name, function_type, None);
+        let ctx = function.get_first_param().expect("No ctx parameter found");
+        ctx.set_name("ctx");
+
+        self.ctx = Some(ctx);
+        self.names.clear();
+        self.blocks.clear();
+
+        self.build_graph_block(function, bb, ());
+
+        // Verify the function integrity
+        function.verify(true);
+
+        name
+    }
+
+    /// Builds the term, the calls are direct calls to the compiled functions of
+    /// the module, or to the runtime, with the context of the reduce function.
+    pub fn build_reduce_term(&self, term: Term) -> IntValue<'a> {
+        match term {
+            Term::True => self.context.bool_type().const_int(1, false),
+            Term::False => self.context.bool_type().const_int(0, false),
+            Term::CurrentTag => self.build_pointer_tag(self.names["term"]).into_int_value(),
+            Term::Tag(tag) => self.context.i64_type().const_int(tag.id(), false),
+            Term::Rule(stage, rule) => self.call_std(&format!("hvm__{stage}_{rule}"), &[]).into_int_value(),
+            Term::CompleteRedex => self.hvm__complete_redex().into_int_value(),
+            Term::NextVisit => self.hvm__next_visit().into_int_value(),
+            Term::Id(id) => self.context.i64_type().const_int(id, false),
+            Term::FunctionId => self.names["fid"].into_int_value(),
+            Term::Call(stage, name) => {
+                let name = match stage {
+                    Stage::Visit => format!("{}__visit", mangle_name(&name)),
+                    Stage::Apply => mangle_name(&name),
+                };
+                let function = self
+                    .module
+                    .get_function(&name)
+                    .unwrap_or_else(|| panic!("Could not find the compiled function {name}"));
+                let ctx = self.ctx.expect("No ctx parameter found");
+
+                self.builder
+                    .build_call(function, &[ctx.into()], "")
+                    .try_as_basic_value()
+                    .left()
+                    .expect("The compiled function has no return value")
+                    .into_int_value()
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Lowering of the [crate::ir::visit] control flow graph to LLVM IR.
+//!
+//! The visit function pushes the strict arguments that aren't in the WHNF form
+//! into the vbuf, and schedules their reduction, through the `hvm__*` functions
+//! of [crate::runtime]. The `vlen`, `vbuf` and `goup` variables are updated by
+//! the instructions of any block, so they're stored in the stack, instead of
+//! being SSA values like in the apply function.
+
+use inkwell::values::{IntValue, PointerValue};
+use inkwell::AddressSpace;
+
+use crate::ir::rule::RuleGroup;
+use crate::ir::visit::{Instruction, Term, VisitBlock};
+use crate::llvm::apply::Codegen;
+use crate::llvm::graph::LowerInstruction;
+
+/// The stack slots of the variables of the visit function, they're all 64-bit
+/// integers.
+#[derive(Clone, Copy)]
+pub struct Slots<'a> {
+    pub vlen: PointerValue<'a>,
+    pub vbuf: PointerValue<'a>,
+    pub goup: PointerValue<'a>,
+}
+
+impl<'a> LowerInstruction<'a> for Instruction {
+    type State = Slots<'a>;
+
+    fn build_instruction(self, codegen: &mut Codegen<'a>, slots: Slots<'a>) {
+        codegen.build_visit_instruction(self, slots);
+    }
+
+    fn build_term(term: Term, codegen: &Codegen<'a>, slots: Slots<'a>) -> IntValue<'a> {
+        codegen.build_visit_term(term, slots)
+    }
+}
+
+impl<'a> Codegen<'a> {
+    pub fn build_visit_function(&mut self, rule: &RuleGroup, bb: VisitBlock) -> String {
+        // Function signature: <<name>>__visit(%ctx: *mut <<reduce_ctx>>) -> i1
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        let function_type = self.context.bool_type().fn_type(&[ptr_type.into()], false);
+
+        let name = format!("{}__visit", self.create_mangled_name(rule));
+        let function = self.module.add_function(&name, function_type, None);
+        let ctx = function.get_first_param().expect("No ctx parameter found");
+        ctx.set_name("ctx");
+
+        self.ctx = Some(ctx);
+        self.blocks.clear();
+
+        // The slots are allocated in the entry block, before its instructions
+        let entry = self.context.append_basic_block(function, &bb.label);
+        self.blocks.insert(bb.label.clone(), entry);
+        self.builder.position_at_end(entry);
+        let slots = Slots {
+            vlen: self.builder.build_alloca(self.context.i64_type(), "vlen"),
+            vbuf: self.builder.build_alloca(self.context.i64_type(), "vbuf"),
+            goup: self.builder.build_alloca(self.context.i64_type(), "goup"),
+        };
+
+        self.build_graph_block(function, bb, slots);
+
+        // Verify the function integrity
+        function.verify(true);
+
+        name
+    }
+
+    pub fn build_visit_instruction(&mut self, instruction: Instruction, slots: Slots<'a>) {
+        match instruction {
+            Instruction::SetVLen => {
+                self.builder.build_store(slots.vlen, self.context.i64_type().const_zero());
+            }
+            Instruction::SetVBuf(vbuf) => {
+                let vbuf = self.build_visit_term(vbuf, slots);
+                self.builder.build_store(slots.vbuf, vbuf);
+            }
+            Instruction::SetGoup(goup) => {
+                let goup = self.build_visit_term(goup, slots);
+                self.builder.build_store(slots.goup, goup);
+            }
+            Instruction::IncreaseLen(index) => {
+                let vlen = self.load_slot(slots.vlen);
+                let increment = self
+                    .hvm__increase_vlen(self.u64(index), self.load_vbuf(slots), vlen.into())
+                    .into_int_value();
+                let vlen = self.builder.build_int_add(vlen, increment, "vlen");
+                self.builder.build_store(slots.vlen, vlen);
+            }
+            Instruction::Visit(slot) => {
+                let goup = self.load_slot(slots.goup);
+                let vlen = self.load_slot(slots.vlen);
+                self.hvm__visit(self.u64(slot), goup.into(), self.load_vbuf(slots), vlen.into());
+            }
+            Instruction::UpdateCont => {
+                let goup = self.load_slot(slots.goup);
+                self.hvm__update_cont(goup.into());
+            }
+            Instruction::UpdateHost => {
+                let vlen = self.load_slot(slots.vlen);
+                self.hvm__update_host(self.load_vbuf(slots), vlen.into());
+            }
+        }
+    }
+
+    /// Builds the term, every visit term is an integer, including the vbuf, that
+    /// is stored as an integer, like the other slots.
+    pub fn build_visit_term(&self, term: Term, slots: Slots<'a>) -> IntValue<'a> {
+        match term {
+            Term::True => self.context.bool_type().const_int(1, false),
+            Term::False => self.context.bool_type().const_int(0, false),
+            Term::CheckVLen => {
+                let vlen = self.load_slot(slots.vlen);
+                let zero = self.context.i64_type().const_zero();
+                self.builder.build_int_compare(inkwell::IntPredicate::NE, vlen, zero, "")
+            }
+            Term::Redex => {
+                let vlen = self.load_slot(slots.vlen);
+                self.hvm__insert_redex(vlen.into()).into_int_value()
+            }
+            Term::CreateVBuf => {
+                let vbuf = self.hvm__create_vbuf().into_pointer_value();
+                self.builder.build_ptr_to_int(vbuf, self.context.i64_type(), "")
+            }
+        }
+    }
+
+    fn load_slot(&self, slot: PointerValue<'a>) -> IntValue<'a> {
+        self.builder
+            .build_load(self.context.i64_type(), slot, "")
+            .into_int_value()
+    }
+
+    fn load_vbuf(&self, slots: Slots<'a>) -> inkwell::values::BasicValueEnum<'a> {
+        let vbuf = self.load_slot(slots.vbuf);
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        self.builder.build_int_to_ptr(vbuf, ptr_type, "vbuf").into()
+    }
+}
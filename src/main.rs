@@ -7,18 +7,23 @@ extern crate core;
 
 pub mod cli;
 pub mod codegen;
+pub mod diagnostic;
 pub mod eval;
 pub mod hvm;
 pub mod ir;
 pub mod llvm;
+pub mod passes;
 pub mod runtime;
 pub mod pretty;
 
 /// The `trazodone` command entrypoint.
 ///
-/// This project has two main subcommands:
+/// This project has the following subcommands:
 ///   - repl
 ///   - eval
+///   - check
+///   - build
+///   - compile
 fn main() {
     cli::run_cli();
 }
@@ -0,0 +1,293 @@
+//! Optimization passes over the apply IR.
+//!
+//! The [PassManager] runs an ordered list of named passes, first over the [Block] of
+//! the apply function, and then over its control flow graph. The passes are chosen
+//! in the command line with `--passes`, and the IR can be dumped after each one of
+//! them with `--dump-passes`, to find which pass miscompiles a rule group. The IR is
+//! verified after every pass.
+//!
+//! The available passes are:
+//!   - `const-fold` [const_fold]: Folds the [Term::Equal], [Term::LogicalAnd],
+//!     [Term::LogicalOr] and [Term::Select] terms, when their operands are constants;
+//!   - `unreachable-blocks` [unreachable]: Removes the branches that can't be taken,
+//!     and the instructions after a return;
+//!   - `dead-let` [dead_let]: Removes the bindings that are never used;
+//!   - `switch-dispatch` [switch]: Merges the chains of constructor extension
+//!     checks into a [Terminator::Switch], it only runs over the graph.
+
+use itertools::Itertools;
+
+use crate::ir::apply::{ApplyBasicBlock, Block, Collect, Free, If, Instruction, Let, Link, Term, Value};
+use crate::ir::graph::Terminator;
+use crate::ir::verify::{Verify, VerifyError};
+
+pub mod const_fold;
+pub mod dead_let;
+pub mod switch;
+pub mod unreachable;
+
+/// The passes that run by default, in this order.
+pub const DEFAULT_PASSES: [&str; 4] = ["const-fold", "unreachable-blocks", "dead-let", "switch-dispatch"];
+
+/// An optimization pass, that runs both over the apply [Block] and over its
+/// control flow graph.
+pub trait Pass {
+    /// The name of the pass, used in the command line and in the dumps.
+    fn name(&self) -> &'static str;
+
+    fn run_block(&self, block: &mut Block);
+
+    fn run_graph(&self, bb: &mut ApplyBasicBlock);
+}
+
+/// Runs the passes in order, over the IR of each rule group.
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+
+    /// Dumps the IR after every pass, in the standard error.
+    pub dump: bool,
+}
+
+impl PassManager {
+    /// Creates a pass manager with the given passes, in the given order. The
+    /// `none` name can be used to run no passes.
+    pub fn new(names: &[String]) -> Result<Self, String> {
+        let passes = names
+            .iter()
+            .filter(|name| !name.is_empty() && *name != "none")
+            .map(|name| {
+                create_pass(name).ok_or_else(|| {
+                    format!("unknown pass `{name}`, the available passes are: {}", DEFAULT_PASSES.join(", "))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { passes, dump: false })
+    }
+
+    pub fn with_dump(mut self, dump: bool) -> Self {
+        self.dump = dump;
+        self
+    }
+
+    /// Returns the names of the passes, in the order that they run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    /// Runs the passes over the apply block, and then over its control flow graph,
+    /// returning the graph. The errors are reported with the pass that broke the IR.
+    pub fn run(&self, group: &str, block: &mut Block) -> Result<ApplyBasicBlock, Vec<VerifyError>> {
+        for pass in &self.passes {
+            pass.run_block(block);
+            if self.dump {
+                eprintln!("; {group}: block after `{}`\n{block:?}", pass.name());
+            }
+            block.verify().map_err(|errors| after(pass.as_ref(), errors))?;
+        }
+
+        let mut bb = block.clone().into_control_flow_graph();
+        bb.verify()?;
+
+        for pass in &self.passes {
+            pass.run_graph(&mut bb);
+            if self.dump {
+                eprintln!("; {group}: graph after `{}`\n{bb}", pass.name());
+            }
+            bb.verify().map_err(|errors| after(pass.as_ref(), errors))?;
+        }
+
+        Ok(bb)
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self {
+            passes: DEFAULT_PASSES.into_iter().filter_map(create_pass).collect(),
+            dump: false,
+        }
+    }
+}
+
+fn create_pass(name: &str) -> Option<Box<dyn Pass>> {
+    match name {
+        "const-fold" => Some(Box::new(const_fold::ConstFold)),
+        "unreachable-blocks" => Some(Box::new(unreachable::UnreachableBlocks)),
+        "dead-let" => Some(Box::new(dead_let::DeadLet)),
+        "switch-dispatch" => Some(Box::new(switch::SwitchDispatch)),
+        _ => None,
+    }
+}
+
+fn after(pass: &dyn Pass, errors: Vec<VerifyError>) -> Vec<VerifyError> {
+    errors
+        .into_iter()
+        .map(|error| VerifyError {
+            location: format!("{}, after the `{}` pass", error.location, pass.name()),
+            ..error
+        })
+        .collect()
+}
+
+/// Returns the terms of the instruction, without the ones in the nested blocks.
+pub fn terms_mut(instruction: &mut Instruction) -> Vec<&mut Term> {
+    match instruction {
+        Instruction::Collect(Collect { term })
+        | Instruction::Link(Link { term, .. })
+        | Instruction::Term(term)
+        | Instruction::Return(term) => vec![term],
+        Instruction::Free(Free { position, .. }) => vec![position],
+        Instruction::Let(Let { value, .. }) => vec![value],
+        Instruction::If(If { condition, .. }) => vec![condition],
+        Instruction::Metadata(..) | Instruction::IncrementCost | Instruction::Println(..) => vec![],
+    }
+}
+
+/// Returns the nested blocks of the instruction.
+pub fn blocks_mut(instruction: &mut Instruction) -> Vec<&mut Vec<Instruction>> {
+    match instruction {
+        Instruction::If(If { then, otherwise, .. }) => {
+            let mut blocks = vec![&mut then.block];
+            blocks.extend(otherwise.as_mut().map(|otherwise| &mut otherwise.block));
+            blocks
+        }
+        Instruction::Metadata(metadata) => vec![&mut metadata.instructions],
+        _ => vec![],
+    }
+}
+
+/// Returns the terms of the terminator.
+pub fn terminator_terms_mut(terminator: &mut Terminator<Instruction>) -> Vec<&mut Term> {
+    match terminator {
+        Terminator::Return(term) | Terminator::Cond(term, ..) => vec![term],
+        Terminator::Switch(term, cases, _) => {
+            let mut terms = vec![term];
+            terms.extend(cases.iter_mut().map(|(value, _)| value));
+            terms
+        }
+        Terminator::Unreachable | Terminator::Debug(..) | Terminator::Jump(..) => vec![],
+    }
+}
+
+/// Calls the function on every instruction, and on the instructions of the
+/// nested blocks.
+pub fn walk_instructions(instructions: &mut [Instruction], f: &mut impl FnMut(&mut Instruction)) {
+    for instruction in instructions {
+        f(instruction);
+        for block in blocks_mut(instruction) {
+            walk_instructions(block, f);
+        }
+    }
+}
+
+/// Calls the function on the basic block, and on every declared block, sorted
+/// by the label.
+pub fn walk_graph(bb: &mut ApplyBasicBlock, f: &mut impl FnMut(&mut ApplyBasicBlock)) {
+    f(bb);
+
+    let labels = bb.declared_blocks.keys().cloned().sorted().collect::<Vec<_>>();
+    for label in labels {
+        if let Some(declared) = bb.declared_blocks.get_mut(&label) {
+            walk_graph(declared, f);
+        }
+    }
+}
+
+/// If evaluating the term has no side effects, so it can be removed. The
+/// allocations aren't pure, as removing them would change the heap.
+pub fn is_pure(term: &Term) -> bool {
+    match term {
+        Term::Alloc(..) | Term::Agent(..) | Term::TakeArgument(..) => false,
+        Term::ArityOf(arity_of) => is_pure(&arity_of.term),
+        Term::LoadArgument(load_argument) => is_pure(&load_argument.term),
+        Term::GetExt(get_ext) => is_pure(&get_ext.term),
+        Term::GetNumber(get_number) => is_pure(&get_number.term),
+        Term::GetTag(get_tag) => is_pure(&get_tag.term),
+        Term::GetPosition(get_position) => is_pure(&get_position.term),
+        Term::Create(Value::Binary(binary, _)) | Term::Operate(binary) => is_pure(&binary.lhs) && is_pure(&binary.rhs),
+        Term::Select(select) => is_pure(&select.condition) && is_pure(&select.then) && is_pure(&select.otherwise),
+        Term::Equal(lhs, rhs) | Term::LogicalOr(lhs, rhs) | Term::LogicalAnd(lhs, rhs) => is_pure(lhs) && is_pure(rhs),
+        Term::Create(..)
+        | Term::Current
+        | Term::Tag(..)
+        | Term::Ext(..)
+        | Term::Int(..)
+        | Term::Ref(..)
+        | Term::True
+        | Term::False
+        | Term::NotFound(..) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  %unused = %arg0/tag
+  if (('U60 == 'F60) && (%arg0/tag == 'U60)):
+    ret true
+  else:
+    link [^host] %arg0
+    ret false
+  end
+  ret false
+";
+
+    const EXPECTED: &str = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  link [^host] %arg0
+  ret false
+";
+
+    #[test]
+    fn it_runs_the_default_passes() {
+        let mut block = INPUT.parse::<Block>().unwrap();
+        let bb = PassManager::default().run("Example", &mut block).unwrap();
+
+        let expected = EXPECTED.parse::<Block>().unwrap();
+        assert_eq!(format!("{block:?}"), format!("{expected:?}"));
+        assert_eq!(bb.to_string(), expected.into_control_flow_graph().to_string());
+        assert!(bb.declared_blocks.is_empty());
+    }
+
+    #[test]
+    fn it_merges_the_constructor_dispatch() {
+        let mut block = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  %ext = %arg0/ext
+  if (%ext == (ext 1 Succ)):
+    ret true
+  end
+  if (%ext == (ext 2 Zero)):
+    ret false
+  end
+  if (%ext == (ext 3 Nil)):
+    ret true
+  end
+  ret false
+"
+        .parse::<Block>()
+        .unwrap();
+        let bb = PassManager::default().run("Example", &mut block).unwrap();
+
+        let text = bb.to_string();
+        assert!(
+            text.contains("switch (%ext) [(ext 1 Succ) @bb_1] [(ext 2 Zero) @bb_3] [(ext 3 Nil) @bb_5] default @bb_6"),
+            "{text}"
+        );
+        assert_eq!(text.parse::<ApplyBasicBlock>().unwrap().to_string(), text);
+        assert_eq!(bb.declared_blocks.len(), 4);
+    }
+
+    #[test]
+    fn it_rejects_unknown_passes() {
+        assert!(PassManager::new(&["const-fold".into(), "inline".into()]).is_err());
+        assert!(PassManager::new(&["none".into()]).unwrap().names().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Constant folding of the conditions.
+//!
+//! The [Term::Equal] terms are folded when both sides are constants of the same
+//! kind, and the [Term::LogicalAnd] and [Term::LogicalOr] terms are folded when
+//! one of their sides is a constant, like [Term::simplify] does in the codegen.
+//! The [Term::Select] terms with a constant condition are replaced by the selected
+//! term.
+//! The constant conditions are then removed by the `unreachable-blocks` pass.
+
+use crate::ir::apply::*;
+use crate::passes::{is_pure, terminator_terms_mut, terms_mut, walk_graph, walk_instructions, Pass};
+
+/// The mask of the number in a HVM pointer, the numbers are truncated to 60 bits.
+const NUM_MASK: u64 = (1 << 60) - 1;
+
+pub struct ConstFold;
+
+impl Pass for ConstFold {
+    fn name(&self) -> &'static str {
+        "const-fold"
+    }
+
+    fn run_block(&self, block: &mut Block) {
+        walk_instructions(&mut block.block, &mut |instruction| {
+            terms_mut(instruction).into_iter().for_each(fold_in_place);
+        });
+    }
+
+    fn run_graph(&self, bb: &mut ApplyBasicBlock) {
+        walk_graph(bb, &mut |bb| {
+            for instruction in &mut bb.instructions {
+                terms_mut(instruction).into_iter().for_each(fold_in_place);
+            }
+            terminator_terms_mut(&mut bb.terminator)
+                .into_iter()
+                .for_each(fold_in_place);
+        });
+    }
+}
+
+fn fold_in_place(term: &mut Term) {
+    *term = fold(std::mem::replace(term, Term::False));
+}
+
+/// Folds the term, and its subterms.
+pub fn fold(term: Term) -> Term {
+    match term {
+        Term::Equal(box lhs, box rhs) => {
+            let (lhs, rhs) = (fold(lhs), fold(rhs));
+            match constant_equal(&lhs, &rhs) {
+                Some(true) => Term::True,
+                Some(false) => Term::False,
+                None => Term::equal(lhs, rhs),
+            }
+        }
+        Term::LogicalAnd(box lhs, box rhs) => match (fold(lhs), fold(rhs)) {
+            (Term::True, term) | (term, Term::True) => term,
+            (Term::False, term) | (term, Term::False) if is_pure(&term) => Term::False,
+            (lhs, rhs) => Term::logical_and(lhs, rhs),
+        },
+        Term::LogicalOr(box lhs, box rhs) => match (fold(lhs), fold(rhs)) {
+            (Term::False, term) | (term, Term::False) => term,
+            (Term::True, term) | (term, Term::True) if is_pure(&term) => Term::True,
+            (lhs, rhs) => Term::logical_or(lhs, rhs),
+        },
+        Term::ArityOf(ArityOf { box term }) => Term::arity_of(fold(term)),
+        Term::TakeArgument(TakeArgument {
+            position,
+            box argument_index,
+        }) => Term::take_arg(position, fold(argument_index)),
+        Term::GetExt(GetExt { box term }) => fold(term).get_ext(),
+        Term::GetNumber(GetNumber { box term }) => fold(term).get_num(),
+        Term::GetTag(GetTag { box term }) => fold(term).get_tag(),
+        Term::GetPosition(GetPosition { box term, position }) => Term::get_position(fold(term), position),
+        Term::LoadArgument(LoadArgument {
+            box term,
+            argument_index,
+        }) => Term::load_arg(fold(term), argument_index),
+        Term::Create(Value::Binary(Binary { box lhs, op, box rhs }, position)) => {
+            Term::create_binary(fold(lhs), op, fold(rhs), position)
+        }
+        Term::Operate(Binary { box lhs, op, box rhs }) => Term::operate(fold(lhs), op, fold(rhs)),
+        Term::Select(Select {
+            box condition,
+            box then,
+            box otherwise,
+        }) => match fold(condition) {
+            Term::True => fold(then),
+            Term::False => fold(otherwise),
+            condition => Term::select(condition, fold(then), fold(otherwise)),
+        },
+        Term::Agent(Agent { arity, arguments }) => Term::Agent(Agent {
+            arity,
+            arguments: arguments.into_iter().map(fold).collect(),
+        }),
+        term => term,
+    }
+}
+
+/// Compares two constants, it returns [None] if they can't be compared in
+/// compile time.
+fn constant_equal(lhs: &Term, rhs: &Term) -> Option<bool> {
+    match (lhs, rhs) {
+        (Term::True | Term::False, Term::True | Term::False) => Some(lhs.is_true() == rhs.is_true()),
+        (Term::Tag(lhs), Term::Tag(rhs)) => Some(lhs.id() == rhs.id()),
+        (Term::Ext(lhs, _), Term::Ext(rhs, _)) => Some(lhs == rhs),
+        (Term::Int(lhs), Term::Int(rhs)) => Some(lhs == rhs),
+        (Term::Create(Value::U60(lhs)), Term::Create(Value::U60(rhs))) => Some(lhs.0 & NUM_MASK == rhs.0 & NUM_MASK),
+        // Different floats can be truncated into the same pointer, so only
+        // the equal ones are folded.
+        (Term::Create(Value::F60(lhs)), Term::Create(Value::F60(rhs))) if lhs.0.to_bits() == rhs.0.to_bits() => {
+            Some(true)
+        }
+        (Term::Ref(lhs), Term::Ref(rhs)) if lhs == rhs => Some(true),
+        _ => None,
+    }
+}
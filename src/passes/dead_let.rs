@@ -0,0 +1,148 @@
+//! Dead binding elimination.
+//!
+//! Removes the [Instruction::Let] bindings whose names are never referenced, by a
+//! [Term::Ref] or by a [Position::Named], if their values are pure. It runs until
+//! no binding is removed, as removing a binding can make the bindings used by it
+//! dead too.
+
+use fxhash::FxHashMap;
+
+use crate::ir::apply::*;
+use crate::passes::{blocks_mut, is_pure, terminator_terms_mut, terms_mut, walk_graph, walk_instructions, Pass};
+
+pub struct DeadLet;
+
+type Uses = FxHashMap<String, usize>;
+
+impl Pass for DeadLet {
+    fn name(&self) -> &'static str {
+        "dead-let"
+    }
+
+    fn run_block(&self, block: &mut Block) {
+        loop {
+            let mut uses = Uses::default();
+            walk_instructions(&mut block.block, &mut |instruction| {
+                count_instruction_uses(instruction, &mut uses);
+            });
+
+            if !remove_dead(&mut block.block, &uses) {
+                break;
+            }
+        }
+    }
+
+    fn run_graph(&self, bb: &mut ApplyBasicBlock) {
+        loop {
+            let mut uses = Uses::default();
+            walk_graph(bb, &mut |bb| {
+                for instruction in &mut bb.instructions {
+                    count_instruction_uses(instruction, &mut uses);
+                }
+                for term in terminator_terms_mut(&mut bb.terminator) {
+                    count_uses(term, &mut uses);
+                }
+            });
+
+            let mut removed = false;
+            walk_graph(bb, &mut |bb| removed |= remove_dead(&mut bb.instructions, &uses));
+
+            if !removed {
+                break;
+            }
+        }
+    }
+}
+
+/// Removes the dead bindings of the instructions, and of the nested blocks,
+/// returning if any binding was removed.
+fn remove_dead(instructions: &mut Vec<Instruction>, uses: &Uses) -> bool {
+    let length = instructions.len();
+    instructions.retain(|instruction| match instruction {
+        Instruction::Let(Let { name, value }) => uses.contains_key(name) || !is_pure(value),
+        _ => true,
+    });
+
+    let mut removed = instructions.len() != length;
+    for instruction in instructions {
+        for block in blocks_mut(instruction) {
+            removed |= remove_dead(block, uses);
+        }
+    }
+
+    removed
+}
+
+fn count_instruction_uses(instruction: &mut Instruction, uses: &mut Uses) {
+    if let Instruction::Link(Link { position, .. }) = instruction {
+        count_position_uses(position, uses);
+    }
+    for term in terms_mut(instruction) {
+        count_uses(term, uses);
+    }
+}
+
+fn count_uses(term: &Term, uses: &mut Uses) {
+    match term {
+        Term::Ref(name) => *uses.entry(name.clone()).or_default() += 1,
+        Term::ArityOf(arity_of) => count_uses(&arity_of.term, uses),
+        Term::TakeArgument(take_argument) => {
+            count_position_uses(&take_argument.position, uses);
+            count_uses(&take_argument.argument_index, uses);
+        }
+        Term::LoadArgument(load_argument) => count_uses(&load_argument.term, uses),
+        Term::GetExt(get_ext) => count_uses(&get_ext.term, uses),
+        Term::GetNumber(get_number) => count_uses(&get_number.term, uses),
+        Term::GetTag(get_tag) => count_uses(&get_tag.term, uses),
+        Term::GetPosition(get_position) => count_uses(&get_position.term, uses),
+        Term::Agent(agent) => {
+            for argument in &agent.arguments {
+                count_uses(argument, uses);
+            }
+        }
+        Term::Equal(lhs, rhs) | Term::LogicalOr(lhs, rhs) | Term::LogicalAnd(lhs, rhs) => {
+            count_uses(lhs, uses);
+            count_uses(rhs, uses);
+        }
+        Term::Operate(binary) => {
+            count_uses(&binary.lhs, uses);
+            count_uses(&binary.rhs, uses);
+        }
+        Term::Select(select) => {
+            count_uses(&select.condition, uses);
+            count_uses(&select.then, uses);
+            count_uses(&select.otherwise, uses);
+        }
+        Term::Create(value) => match value {
+            Value::Dp0(_, position)
+            | Value::Dp1(_, position)
+            | Value::Argument(position)
+            | Value::Atom(position)
+            | Value::Lam(position)
+            | Value::App(position)
+            | Value::Super(_, position)
+            | Value::Function(_, position)
+            | Value::Constructor(_, position) => count_position_uses(position, uses),
+            Value::Binary(binary, position) => {
+                count_uses(&binary.lhs, uses);
+                count_uses(&binary.rhs, uses);
+                count_position_uses(position, uses);
+            }
+            Value::U60(..) | Value::F60(..) | Value::Erased => {}
+        },
+        Term::Current
+        | Term::Tag(..)
+        | Term::Ext(..)
+        | Term::Int(..)
+        | Term::Alloc(..)
+        | Term::True
+        | Term::False
+        | Term::NotFound(..) => {}
+    }
+}
+
+fn count_position_uses(position: &Position, uses: &mut Uses) {
+    if let Position::Named { reference_name, .. } = position {
+        *uses.entry(reference_name.clone()).or_default() += 1;
+    }
+}
@@ -0,0 +1,97 @@
+//! Multi-way constructor dispatch.
+//!
+//! The decision tree compares the extension of an argument with each constructor
+//! of the rule group, in a chain of `if` instructions, that becomes a chain of
+//! [Terminator::Cond] in the control flow graph. The chains that compare the same
+//! variable with constant extensions are merged into a [Terminator::Switch], so
+//! LLVM can build a jump table for the big data types.
+
+use fxhash::FxHashMap;
+
+use crate::ir::apply::*;
+use crate::ir::graph::{Label, Terminator};
+use crate::passes::{walk_graph, Pass};
+
+pub struct SwitchDispatch;
+
+impl Pass for SwitchDispatch {
+    fn name(&self) -> &'static str {
+        "switch-dispatch"
+    }
+
+    fn run_block(&self, _block: &mut Block) {}
+
+    fn run_graph(&self, bb: &mut ApplyBasicBlock) {
+        let mut targets = FxHashMap::<String, usize>::default();
+        walk_graph(bb, &mut |bb| {
+            for Label(label) in bb.terminator.labels() {
+                *targets.entry(label.clone()).or_default() += 1;
+            }
+        });
+
+        walk_graph(bb, &mut |bb| merge_chain(bb, &targets));
+    }
+}
+
+/// Returns the variable and the extension that are compared by the condition.
+fn case(condition: &Term) -> Option<(&String, &Term)> {
+    let Term::Equal(lhs, rhs) = condition else {
+        return None;
+    };
+
+    match (&**lhs, &**rhs) {
+        (Term::Ref(name), ext @ Term::Ext(..)) | (ext @ Term::Ext(..), Term::Ref(name)) => Some((name, ext)),
+        _ => None,
+    }
+}
+
+/// Merges the comparisons of the chain that starts in the terminator of the block
+/// into a switch. The next comparison is only merged if its block does nothing
+/// else, and if it's only reached by the chain.
+fn merge_chain(bb: &mut ApplyBasicBlock, targets: &FxHashMap<String, usize>) {
+    let Terminator::Cond(condition, then, otherwise) = &bb.terminator else {
+        return;
+    };
+    let Some((scrutinee, ext)) = case(condition) else {
+        return;
+    };
+
+    let scrutinee = scrutinee.clone();
+    let mut cases = vec![(ext.clone(), then.clone())];
+    let mut default = otherwise.clone();
+
+    while let Some(next) = bb.declared_blocks.get(&default.0) {
+        if !next.instructions.is_empty() || !next.phis.is_empty() || targets.get(&default.0) != Some(&1) {
+            break;
+        }
+        let Terminator::Cond(condition, then, otherwise) = &next.terminator else {
+            break;
+        };
+        let Some((name, ext)) = case(condition) else {
+            break;
+        };
+
+        // The repeated extensions are never taken, but LLVM rejects them
+        if *name != scrutinee || cases.iter().any(|(other, _)| id(other) == id(ext)) {
+            break;
+        }
+
+        cases.push((ext.clone(), then.clone()));
+        let otherwise = otherwise.clone();
+
+        let next = bb.declared_blocks.remove(&default.0).unwrap();
+        bb.declared_blocks.extend(next.declared_blocks);
+        default = otherwise;
+    }
+
+    if cases.len() > 1 {
+        bb.terminator = Terminator::Switch(Term::reference(&scrutinee), cases, default);
+    }
+}
+
+fn id(ext: &Term) -> Option<u64> {
+    match ext {
+        Term::Ext(id, _) => Some(*id),
+        _ => None,
+    }
+}
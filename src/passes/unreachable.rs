@@ -0,0 +1,101 @@
+//! Unreachable code removal.
+//!
+//! In the apply [Block], the `if` instructions with a constant condition are
+//! replaced by the branch that is taken, and the instructions after a return are
+//! removed. In the control flow graph, the block taken by a constant
+//! [Terminator::Cond] is merged into its predecessor, and the declared blocks that
+//! aren't the target of any terminator are removed.
+
+use fxhash::FxHashSet;
+
+use crate::ir::apply::*;
+use crate::ir::graph::{Label, Terminator};
+use crate::passes::{walk_graph, Pass};
+
+pub struct UnreachableBlocks;
+
+impl Pass for UnreachableBlocks {
+    fn name(&self) -> &'static str {
+        "unreachable-blocks"
+    }
+
+    fn run_block(&self, block: &mut Block) {
+        prune(&mut block.block);
+    }
+
+    fn run_graph(&self, bb: &mut ApplyBasicBlock) {
+        walk_graph(bb, &mut merge_constant_branches);
+
+        let mut targets = FxHashSet::default();
+        walk_graph(bb, &mut |bb| {
+            for Label(label) in bb.terminator.labels() {
+                targets.insert(label.clone());
+            }
+        });
+        walk_graph(bb, &mut |bb| {
+            bb.declared_blocks.retain(|label, _| targets.contains(label));
+        });
+    }
+}
+
+fn prune(instructions: &mut Vec<Instruction>) {
+    for instruction in std::mem::take(instructions) {
+        match instruction {
+            Instruction::If(If {
+                condition: Term::True,
+                then,
+                ..
+            }) => {
+                let mut then = then.block;
+                prune(&mut then);
+                instructions.extend(then);
+            }
+            Instruction::If(If {
+                condition: Term::False,
+                otherwise,
+                ..
+            }) => {
+                let mut otherwise = otherwise.map(|otherwise| otherwise.block).unwrap_or_default();
+                prune(&mut otherwise);
+                instructions.extend(otherwise);
+            }
+            Instruction::If(mut if_instruction) => {
+                prune(&mut if_instruction.then.block);
+                if let Some(otherwise) = &mut if_instruction.otherwise {
+                    prune(&mut otherwise.block);
+                }
+                instructions.push(Instruction::If(if_instruction));
+            }
+            Instruction::Metadata(mut metadata) => {
+                prune(&mut metadata.instructions);
+                instructions.push(Instruction::Metadata(metadata));
+            }
+            instruction => instructions.push(instruction),
+        }
+
+        // Nothing runs after a return
+        if let Some(Instruction::Return(..)) = instructions.last() {
+            break;
+        }
+    }
+}
+
+/// Merges the blocks that are always taken by the terminator, into the
+/// current block.
+fn merge_constant_branches(bb: &mut ApplyBasicBlock) {
+    loop {
+        let target = match &bb.terminator {
+            Terminator::Cond(Term::True, Label(then), _) => then.clone(),
+            Terminator::Cond(Term::False, _, Label(otherwise)) => otherwise.clone(),
+            _ => break,
+        };
+        let Some(taken) = bb.declared_blocks.remove(&target) else {
+            break;
+        };
+
+        bb.variables.extend(taken.variables);
+        bb.instructions.extend(taken.instructions);
+        bb.terminator = taken.terminator;
+        bb.declared_blocks.extend(taken.declared_blocks);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod apply;
 pub mod graph;
+pub mod parse;
+pub mod reduce;
 pub mod syntax;
 pub mod visit;
@@ -95,7 +95,7 @@ impl Display for F60 {
 
 impl Display for FunctionId {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f, "{}", self.0.clone().unwrap_or("<<unknown>>".into()))
+        write!(f, "{}:{}", self.0.clone().unwrap_or("_".into()), self.1)
     }
 }
 
@@ -138,11 +138,12 @@ impl Display for Term {
             Term::False => write!(f, "false"),
             Term::Current => write!(f, "^current-term"),
             Term::Tag(tag) => write!(f, "'{tag}"),
-            Term::Ext(_, tag) => write!(f, "'{tag}"),
-            Term::Equal(lhs, rhs) => write!(f, "{lhs} == {rhs}"),
-            Term::LogicalOr(lhs, rhs) => write!(f, "{lhs} || {rhs}"),
-            Term::LogicalAnd(lhs, rhs) => write!(f, "{lhs} && {rhs}"),
+            Term::Ext(id, name) => write!(f, "(ext {id} {name})"),
+            Term::Equal(lhs, rhs) => write!(f, "({lhs} == {rhs})"),
+            Term::LogicalOr(lhs, rhs) => write!(f, "({lhs} || {rhs})"),
+            Term::LogicalAnd(lhs, rhs) => write!(f, "({lhs} && {rhs})"),
             Term::Ref(name) => write!(f, "%{name}"),
+            Term::Int(value) => write!(f, "#{value}"),
             Term::Create(value) => write!(f, "{value}"),
             Term::ArityOf(ArityOf { term }) => write!(f, "{term}/arity"),
             Term::GetNumber(GetNumber { term }) => write!(f, "{term}/num"),
@@ -150,20 +151,27 @@ impl Display for Term {
             Term::GetTag(GetTag { term }) => write!(f, "{term}/tag"),
             Term::Alloc(Alloc { size }) => write!(f, "(alloc ~arity: {size})"),
             Term::Agent(Agent { arguments, .. }) => {
-                write!(f, "make-agent")?;
-
-                if arguments.is_empty() {
-                    return write!(f, "");
-                }
+                write!(f, "(make-agent")?;
 
                 for argument in arguments {
                     write!(f, " {argument}")?;
                 }
 
-                Ok(())
+                write!(f, ")")
             }
+            Term::Operate(binary) => write!(f, "(operate {binary})"),
+            Term::Select(Select {
+                condition,
+                then,
+                otherwise,
+            }) => write!(f, "(select {condition} {then} {otherwise})"),
             Term::NotFound(atom) => {
-                write!(f, "(! not-found {atom:?} !)")
+                let fields = match atom.fields.is_empty() {
+                    true => "_".into(),
+                    false => atom.fields.iter().map(u64::to_string).collect::<Vec<_>>().join(" "),
+                };
+
+                write!(f, "(! not-found {} {} {fields} !)", atom.name, atom.index)
             }
             Term::GetPosition(GetPosition { term, position }) => {
                 write!(f, "(get-position {term} {position})")
@@ -248,15 +256,14 @@ impl Pretty for Instruction {
                     instruction.pretty(n + 2, f)?;
                     writeln!(f)?;
                 }
-                indented!(f, n, "")?;
                 if let Some(otherwise) = otherwise {
-                    writeln!(f, "else")?;
+                    indentedln!(f, n, "else:")?;
                     for instruction in &otherwise.block {
                         instruction.pretty(n + 2, f)?;
                         writeln!(f)?;
                     }
                 }
-                Ok(())
+                indented!(f, n, "end")
             }
             Instruction::Println(message) => {
                 indented!(f, n, "println {message:?}")
@@ -1,5 +1,7 @@
 use std::fmt::{Display, Formatter};
 
+use itertools::Itertools;
+
 use crate::ir::graph::{BasicBlock, HasTerm, Terminator};
 
 impl<I: HasTerm> Display for BasicBlock<I>
@@ -8,14 +10,23 @@ where
     I::Term: Display,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for declared_block in self.declared_blocks.values() {
+        // Sorted by the label, so the output is stable
+        for (_, declared_block) in self.declared_blocks.iter().sorted_by_key(|(label, _)| *label) {
             writeln!(f, "{declared_block}")?;
         }
 
         writeln!(f, "{}:", self.label)?;
 
         for variable in self.variables.iter() {
-            writeln!(f, "  using {}", variable.name)?;
+            writeln!(f, "  using {} {:?}", variable.name, variable.declared_block)?;
+        }
+
+        for phi in self.phis.iter() {
+            write!(f, "  %{} = phi", phi.name)?;
+            for (label, name) in phi.incoming.iter() {
+                write!(f, " [{label:?} %{name}]")?;
+            }
+            writeln!(f)?;
         }
 
         for instruction in self.instructions.iter() {
@@ -39,6 +50,13 @@ where
             Terminator::Cond(cond, then, otherwise) => {
                 write!(f, "cond ({cond}) {then:?} {otherwise:?}")
             }
+            Terminator::Switch(term, cases, default) => {
+                write!(f, "switch ({term})")?;
+                for (value, label) in cases {
+                    write!(f, " [{value} {label:?}]")?;
+                }
+                write!(f, " default {default:?}")
+            }
         }
     }
 }
@@ -0,0 +1,1097 @@
+//! Parser of the textual IR, it reads back the output of the [crate::pretty] printers.
+//!
+//! The IR can be dumped, modified or written by hand, and then fed again to the
+//! eval and LLVM backends:
+//!   - A [Block] is parsed from its [Debug] output, with the optional `tags:` and
+//!     `extensions:` sections, and the `entry:` section;
+//!   - A [BasicBlock] is parsed from its [Display] output, both with the apply and
+//!     the visit instructions. The declared blocks are printed before the blocks
+//!     that jump to them, so the last block is the entry one.
+//!
+//! The comments, starting with `;`, are skipped. So an [Instruction::Metadata] is
+//! read back as the instructions that it holds, and printing the parsed IR gives
+//! the same text, without the comments.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use fxhash::FxHashMap;
+use hvm::syntax::Oper;
+use itertools::Itertools;
+
+use crate::ir::apply::*;
+use crate::ir::graph::{BasicBlock, HasTerm, Label, Phi, Terminator, Variable};
+use crate::ir::syntax::Atom;
+use crate::ir::{reduce, visit};
+
+/// The tags, in the order of their ids.
+const TAGS: [Tag; 14] = [
+    Tag::DUP0,
+    Tag::DUP1,
+    Tag::ATOM,
+    Tag::ARGUMENT,
+    Tag::ERASED,
+    Tag::LAM,
+    Tag::APP,
+    Tag::SUPER,
+    Tag::CONSTRUCTOR,
+    Tag::FUNCTION,
+    Tag::BINARY,
+    Tag::U60,
+    Tag::F60,
+    Tag::NIL,
+];
+
+/// A syntax error in the textual IR, with the line and the column where it
+/// was found, both starting in 1.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// Parses the instructions and the terms of a [BasicBlock].
+pub trait ParseInstruction: HasTerm + Sized {
+    fn parse_instruction(parser: &mut Parser) -> Result<Self>;
+
+    fn parse_term(parser: &mut Parser) -> Result<Self::Term>;
+}
+
+/// A recursive descent parser, over the text of the IR.
+pub struct Parser<'a> {
+    input: &'a str,
+    index: usize,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, index: 0 }
+    }
+
+    /// Parses a [Block], in the format printed by its [Debug] implementation.
+    pub fn block(&mut self) -> Result<Block> {
+        let mut block = Block::default();
+        if self.eat("tags:") {
+            block.tags = self.table("extensions:")?;
+        }
+        if self.eat("extensions:") {
+            block.extensions = self.table("entry:")?;
+        }
+
+        self.expect("entry:")?;
+        block.block = self.instructions()?.block;
+        if !self.is_eof() {
+            return self.error("expected the end of the block");
+        }
+
+        Ok(block)
+    }
+
+    /// Parses a control flow graph, and returns its entry block, with the other
+    /// blocks declared in the blocks that jump to them.
+    pub fn graph<I: ParseInstruction>(&mut self) -> Result<BasicBlock<I>> {
+        let mut blocks = Vec::new();
+        while !self.is_eof() {
+            blocks.push(self.basic_block::<I>()?);
+        }
+
+        let Some(entry) = blocks.pop() else {
+            return self.error("expected a basic block");
+        };
+
+        let mut declared = FxHashMap::default();
+        for bb in blocks {
+            let label = bb.label.clone();
+            if label == entry.label || declared.insert(label.clone(), bb).is_some() {
+                return self.error(format!("the label `@{label}` is declared twice"));
+            }
+        }
+
+        let mut children = FxHashMap::<String, Vec<String>>::default();
+        let mut parents = FxHashMap::default();
+        for label in declared.keys().sorted() {
+            let parent = parent(label, &entry, &declared, &mut parents);
+            children.entry(parent).or_default().push(label.clone());
+        }
+
+        Ok(declare(entry, &mut declared, &children))
+    }
+
+    pub fn instruction(&mut self) -> Result<Instruction> {
+        if self.eat_keyword("increment-cost") {
+            return Ok(Instruction::IncrementCost);
+        }
+        if self.eat_keyword("collect") {
+            return Ok(Instruction::collect(self.term()?));
+        }
+        if self.eat_keyword("free") {
+            let position = self.term()?;
+            let arity = self.u64()?;
+            return Ok(Instruction::Free(Free { position, arity }));
+        }
+        if self.eat_keyword("ret") {
+            return Ok(Instruction::ret(self.term()?));
+        }
+        if self.eat_keyword("link") {
+            let position = self.position()?;
+            return Ok(Instruction::link(position, self.term()?));
+        }
+        if self.eat_keyword("println") {
+            return Ok(Instruction::Println(self.string()?));
+        }
+        if self.eat_keyword("if") {
+            let condition = self.term()?;
+            self.expect(":")?;
+            let then = self.instructions()?;
+            let otherwise = match self.eat("else:") {
+                true => Some(self.instructions()?),
+                false => None,
+            };
+            self.expect_keyword("end")?;
+
+            return Ok(Instruction::cond(condition, then, otherwise));
+        }
+
+        // A binding, or a term that starts with a reference
+        let start = self.index;
+        if self.eat("%") {
+            let name = self.name()?;
+            if self.peek("=") && !self.peek("==") {
+                self.index += 1;
+                return Ok(Instruction::binding(&name, self.term()?));
+            }
+            self.index = start;
+        }
+
+        Ok(Instruction::Term(self.term()?))
+    }
+
+    pub fn term(&mut self) -> Result<Term> {
+        let mut term = self.primary()?;
+
+        // The accessors are printed right after the term, without spaces
+        while self.rest().starts_with('/') {
+            self.index += 1;
+            term = match self.name()?.as_str() {
+                "arity" => Term::arity_of(term),
+                "num" => term.get_num(),
+                "ext" => term.get_ext(),
+                "tag" => term.get_tag(),
+                accessor => return self.error(format!("unknown accessor `/{accessor}`")),
+            };
+        }
+
+        Ok(term)
+    }
+
+    fn primary(&mut self) -> Result<Term> {
+        if self.eat_keyword("true") {
+            return Ok(Term::True);
+        }
+        if self.eat_keyword("false") {
+            return Ok(Term::False);
+        }
+        if self.eat_keyword("Erased") {
+            return Ok(Term::erased());
+        }
+        if self.eat("^current-term") {
+            return Ok(Term::Current);
+        }
+        if self.eat("'") {
+            let name = self.name()?;
+            return match TAGS.iter().find(|tag| tag.to_string() == name) {
+                Some(tag) => Ok(Term::Tag(tag.clone())),
+                None => self.error(format!("unknown tag `'{name}`")),
+            };
+        }
+        if self.eat("%") {
+            return Ok(Term::Ref(self.name()?));
+        }
+        if self.eat("#") {
+            return Ok(Term::Int(self.u64()?));
+        }
+
+        self.expect("(")?;
+        if self.eat("!") {
+            self.expect_keyword("not-found")?;
+            let name = self.name()?;
+            let index = self.u64()?;
+            let mut fields = Vec::new();
+            if !self.eat_keyword("_") {
+                while !self.peek("!") {
+                    fields.push(self.u64()?);
+                }
+            }
+            self.expect("!")?;
+            self.expect(")")?;
+
+            return Ok(Term::NotFound(Atom {
+                name,
+                index,
+                fields,
+            }));
+        }
+
+        let start = self.index;
+        let keyword = self.name().unwrap_or_default();
+        let term = match keyword.as_str() {
+            "ext" => {
+                let id = self.u64()?;
+                Term::ext(id, &self.name()?)
+            }
+            "alloc" => {
+                self.expect("~arity:")?;
+                Term::alloc(self.u64()?)
+            }
+            "make-agent" => {
+                let mut arguments = Vec::new();
+                while !self.peek(")") {
+                    arguments.push(self.term()?);
+                }
+
+                Term::Agent(Agent {
+                    arity: arguments.len() as u64,
+                    arguments,
+                })
+            }
+            "operate" => {
+                self.expect("(")?;
+                let op = self.oper()?;
+                let lhs = self.term()?;
+                let rhs = self.term()?;
+                self.expect(")")?;
+
+                Term::operate(lhs, op, rhs)
+            }
+            "select" => {
+                let condition = self.term()?;
+                let then = self.term()?;
+                Term::select(condition, then, self.term()?)
+            }
+            "get-position" => {
+                let term = self.term()?;
+                Term::get_position(term, self.u64()?)
+            }
+            "load-argument" => {
+                let term = self.term()?;
+                Term::load_arg(term, self.u64()?)
+            }
+            "take-argument" => {
+                let position = self.position()?;
+                Term::take_arg(position, self.term()?)
+            }
+            _ => match self.value(&keyword)? {
+                Some(value) => Term::Create(value),
+                None => {
+                    self.index = start;
+                    self.infix()?
+                }
+            },
+        };
+        self.expect(")")?;
+
+        Ok(term)
+    }
+
+    /// Parses the `lhs == rhs`, `lhs || rhs` and `lhs && rhs` terms, without
+    /// the parenthesis.
+    fn infix(&mut self) -> Result<Term> {
+        let lhs = self.term()?;
+        if self.eat("==") {
+            Ok(Term::equal(lhs, self.term()?))
+        } else if self.eat("||") {
+            Ok(Term::logical_or(lhs, self.term()?))
+        } else if self.eat("&&") {
+            Ok(Term::logical_and(lhs, self.term()?))
+        } else {
+            self.error("expected `==`, `||` or `&&`")
+        }
+    }
+
+    /// Parses the arguments of a value, like the `%lam` of `(Lam.new %lam)`, if the
+    /// name is a value constructor.
+    fn value(&mut self, name: &str) -> Result<Option<Value>> {
+        let value = match name {
+            "Lam.new" => Value::Lam(self.position()?),
+            "App.new" => Value::App(self.position()?),
+            "Argument.new" => Value::Argument(self.position()?),
+            "Atom.new" => Value::Atom(self.position()?),
+            "U60.new" => Value::u60(self.u64()?),
+            "F60.new" => Value::f60(self.f64()?),
+            "Dp0.new" => {
+                let color = self.color()?;
+                Value::Dp0(color, self.position()?)
+            }
+            "Dp1.new" => {
+                let color = self.color()?;
+                Value::Dp1(color, self.position()?)
+            }
+            "Super.new" => {
+                let color = self.color()?;
+                Value::Super(color, self.position()?)
+            }
+            "Binary.new" => {
+                self.expect("(")?;
+                let op = self.oper()?;
+                let lhs = self.term()?;
+                let rhs = self.term()?;
+                self.expect(")")?;
+
+                let binary = Binary {
+                    lhs: lhs.into(),
+                    op,
+                    rhs: rhs.into(),
+                };
+                Value::Binary(binary, self.position()?)
+            }
+            "Function.new" => {
+                let function_id = self.function_id()?;
+                Value::Function(function_id, self.position()?)
+            }
+            "Constructor.new" => {
+                let function_id = self.function_id()?;
+                Value::Constructor(function_id, self.position()?)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(value))
+    }
+
+    fn position(&mut self) -> Result<Position> {
+        if self.eat("[^host]") {
+            return Ok(Position::Host);
+        }
+
+        self.expect("%")?;
+        let name = self.name()?;
+        let gate_index = match self.rest().starts_with('/') {
+            true => {
+                self.index += 1;
+                self.u64()?
+            }
+            false => 0,
+        };
+
+        Ok(Position::new(&name, gate_index))
+    }
+
+    fn color(&mut self) -> Result<Color> {
+        self.expect("&color")?;
+        Ok(Color(self.u64()?))
+    }
+
+    /// Parses a `Name:id` function id, the name `_` is used when the function
+    /// has no debug name.
+    fn function_id(&mut self) -> Result<FunctionId> {
+        let name = self.name()?;
+        self.expect(":")?;
+        let id = self.u64()?;
+
+        Ok(match name.as_str() {
+            "_" => FunctionId(None, id),
+            _ => FunctionId::new(&name, id),
+        })
+    }
+
+    fn oper(&mut self) -> Result<Oper> {
+        self.skip();
+
+        // The longest operators are tried first
+        let operators = [
+            "<<", ">>", "<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "&", "|", "^", "<", ">",
+        ];
+        let Some(symbol) = operators.into_iter().find(|symbol| self.rest().starts_with(symbol)) else {
+            return self.error("expected a binary operator");
+        };
+        self.index += symbol.len();
+
+        Ok(match symbol {
+            "<<" => Oper::Shl,
+            ">>" => Oper::Shr,
+            "<=" => Oper::Lte,
+            ">=" => Oper::Gte,
+            "==" => Oper::Eql,
+            "!=" => Oper::Neq,
+            "+" => Oper::Add,
+            "-" => Oper::Sub,
+            "*" => Oper::Mul,
+            "/" => Oper::Div,
+            "%" => Oper::Mod,
+            "&" => Oper::And,
+            "|" => Oper::Or,
+            "^" => Oper::Xor,
+            "<" => Oper::Ltn,
+            _ => Oper::Gtn,
+        })
+    }
+
+    /// Parses the instructions of a block, until its end.
+    fn instructions(&mut self) -> Result<Block> {
+        let mut block = Block::default();
+        while !self.is_eof() && !self.peek("else:") && !self.peek_keyword("end") {
+            block.push(self.instruction()?);
+        }
+
+        Ok(block)
+    }
+
+    /// Parses the `name: u64 = id` entries of a table, until the next section.
+    fn table(&mut self, next: &str) -> Result<Vec<(String, u64)>> {
+        let mut entries = Vec::new();
+        while !self.is_eof() && !self.peek(next) {
+            let name = self.name()?;
+            self.expect(":")?;
+            self.expect_keyword("u64")?;
+            self.expect("=")?;
+            entries.push((name, self.u64()?));
+        }
+
+        Ok(entries)
+    }
+
+    fn basic_block<I: ParseInstruction>(&mut self) -> Result<BasicBlock<I>> {
+        let label = self.name()?;
+        self.expect(":")?;
+
+        let mut bb = BasicBlock::new(&label);
+        while self.eat_keyword("using") {
+            let name = self.name()?;
+            let declared_block = self.label()?;
+            bb.variables.push(Variable { declared_block, name });
+        }
+        while let Some(phi) = self.phi()? {
+            bb.phis.push(phi);
+        }
+
+        loop {
+            if let Some(terminator) = self.terminator::<I>()? {
+                bb.terminator = terminator;
+                break;
+            }
+            if self.is_eof() {
+                return self.error(format!("expected a terminator in the block `@{label}`"));
+            }
+
+            bb.instructions.push(I::parse_instruction(self)?);
+        }
+
+        Ok(bb)
+    }
+
+    /// Parses a phi node, like `%x.2 = phi [@bb_1 %x] [@bb_2 %x.1]`, going back
+    /// if the line is another instruction.
+    fn phi(&mut self) -> Result<Option<Phi>> {
+        let start = self.index;
+        if !self.eat("%") {
+            return Ok(None);
+        }
+        let name = self.name()?;
+        if !self.eat("=") || !self.eat_keyword("phi") {
+            self.index = start;
+            return Ok(None);
+        }
+
+        let mut incoming = Vec::new();
+        while self.eat("[") {
+            let label = self.label()?;
+            self.expect("%")?;
+            incoming.push((label, self.name()?));
+            self.expect("]")?;
+        }
+
+        Ok(Some(Phi { name, incoming }))
+    }
+
+    fn terminator<I: ParseInstruction>(&mut self) -> Result<Option<Terminator<I>>> {
+        let terminator = if self.eat_keyword("unreachable") {
+            Terminator::Unreachable
+        } else if self.eat_keyword("dbg") {
+            Terminator::Debug(self.line())
+        } else if self.eat_keyword("ret") {
+            Terminator::Return(I::parse_term(self)?)
+        } else if self.eat_keyword("jmp") {
+            Terminator::Jump(self.label()?)
+        } else if self.eat_keyword("cond") {
+            self.expect("(")?;
+            let condition = I::parse_term(self)?;
+            self.expect(")")?;
+            let then = self.label()?;
+            let otherwise = self.label()?;
+
+            Terminator::Cond(condition, then, otherwise)
+        } else if self.eat_keyword("switch") {
+            self.expect("(")?;
+            let term = I::parse_term(self)?;
+            self.expect(")")?;
+
+            let mut cases = Vec::new();
+            while self.eat("[") {
+                let value = I::parse_term(self)?;
+                let label = self.label()?;
+                self.expect("]")?;
+                cases.push((value, label));
+            }
+            self.expect("default")?;
+
+            Terminator::Switch(term, cases, self.label()?)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(terminator))
+    }
+
+    fn label(&mut self) -> Result<Label> {
+        self.expect("@")?;
+
+        // The variables can be declared in a block without label
+        match self.rest().starts_with(is_name_char) {
+            true => Ok(Label(self.name()?)),
+            false => Ok(Label::default()),
+        }
+    }
+
+    /// Parses a string, in the format printed by [Debug].
+    fn string(&mut self) -> Result<String> {
+        self.expect("\"")?;
+
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((index, c)) = chars.next() {
+            let c = match c {
+                '"' => {
+                    self.index += index + 1;
+                    return Ok(string);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('0') => '\0',
+                    Some(c @ ('\\' | '"' | '\'')) => c,
+                    Some('u') => {
+                        let digits = chars
+                            .by_ref()
+                            .map(|(_, c)| c)
+                            .skip_while(|c| *c == '{')
+                            .take_while(|c| *c != '}')
+                            .collect::<String>();
+
+                        match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                            Some(c) => c,
+                            None => return self.error(format!("invalid unicode escape `\\u{{{digits}}}`")),
+                        }
+                    }
+                    _ => return self.error("invalid escape in the string"),
+                },
+                c => c,
+            };
+            string.push(c);
+        }
+
+        self.error("unterminated string")
+    }
+
+    /// Takes the rest of the current line.
+    fn line(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest.find('\n').unwrap_or(rest.len());
+        self.index += end;
+
+        rest[..end].strip_prefix(' ').unwrap_or(&rest[..end]).into()
+    }
+
+    /// Parses a number, in the decimal or in the `0x` hexadecimal form.
+    fn u64(&mut self) -> Result<u64> {
+        let token = self.token();
+        let number = match token.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => token.parse(),
+        };
+
+        match number {
+            Ok(number) => Ok(self.advance(token, number)),
+            Err(_) => self.error(format!("expected a number, found `{token}`")),
+        }
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let token = self.token();
+        match token.parse() {
+            Ok(number) => Ok(self.advance(token, number)),
+            Err(_) => self.error(format!("expected a float, found `{token}`")),
+        }
+    }
+
+    /// Peeks the next number-like token, it's only consumed by [Parser::advance],
+    /// so the errors point to its start.
+    fn token(&mut self) -> &'a str {
+        self.skip();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !c.is_alphanumeric() && !matches!(c, '.' | '_' | '-' | '+'))
+            .unwrap_or(rest.len());
+
+        &rest[..length]
+    }
+
+    fn advance<T>(&mut self, token: &str, value: T) -> T {
+        self.index += token.len();
+        value
+    }
+
+    /// Parses a name, like a reference, a label, or a constructor name.
+    fn name(&mut self) -> Result<String> {
+        self.skip();
+        let rest = self.rest();
+        let length = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if length == 0 {
+            return self.error("expected a name");
+        }
+        self.index += length;
+
+        Ok(rest[..length].into())
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.index..]
+    }
+
+    /// Skips the whitespaces and the comments.
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.index += rest.len() - trimmed.len();
+
+            match trimmed.starts_with(';') {
+                true => self.index += trimmed.find('\n').unwrap_or(trimmed.len()),
+                false => break,
+            }
+        }
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.skip();
+        self.rest().is_empty()
+    }
+
+    fn peek(&mut self, text: &str) -> bool {
+        self.skip();
+        self.rest().starts_with(text)
+    }
+
+    /// Peeks a keyword, that isn't the start of a longer name.
+    fn peek_keyword(&mut self, keyword: &str) -> bool {
+        self.peek(keyword) && !self.rest()[keyword.len()..].starts_with(is_name_char)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.peek(text);
+        if found {
+            self.index += text.len();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.index += keyword.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, text: &str) -> Result<()> {
+        match self.eat(text) {
+            true => Ok(()),
+            false => self.error(format!("expected `{text}`")),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => self.error(format!("expected `{keyword}`")),
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        let before = &self.input[..self.index];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+
+        Err(ParseError {
+            line,
+            column,
+            message: message.into(),
+        })
+    }
+}
+
+/// Moves the children blocks into the declared blocks of the given block.
+fn declare<I: HasTerm>(
+    mut bb: BasicBlock<I>,
+    blocks: &mut FxHashMap<String, BasicBlock<I>>,
+    children: &FxHashMap<String, Vec<String>>,
+) -> BasicBlock<I> {
+    for label in children.get(&bb.label).into_iter().flatten() {
+        if let Some(child) = blocks.remove(label) {
+            let child = declare(child, blocks, children);
+            bb.declared_blocks.insert(label.clone(), child);
+        }
+    }
+
+    bb
+}
+
+/// Finds the block where the block with the label is declared: the only block that
+/// jumps to it, or the closest common ancestor of the blocks that jump to it, like
+/// for the join blocks. The blocks that aren't the target of any terminator, are
+/// declared in the entry block.
+fn parent<I: HasTerm>(
+    label: &str,
+    entry: &BasicBlock<I>,
+    blocks: &FxHashMap<String, BasicBlock<I>>,
+    parents: &mut FxHashMap<String, String>,
+) -> String {
+    if let Some(parent) = parents.get(label) {
+        return parent.clone();
+    }
+
+    // Marks the block as declared in the entry, while its predecessors are
+    // resolved, so a malformed graph with cycles still terminates.
+    parents.insert(label.into(), entry.label.clone());
+
+    let predecessors = blocks
+        .values()
+        .chain([entry])
+        .filter(|bb| bb.label != label && bb.terminator.labels().iter().any(|target| target.0 == label))
+        .map(|bb| bb.label.clone())
+        .sorted()
+        .collect::<Vec<_>>();
+
+    let mut common: Option<Vec<String>> = None;
+    for predecessor in predecessors {
+        let chain = ancestors(&predecessor, entry, blocks, parents);
+        common = Some(match common {
+            None => chain,
+            Some(common) => {
+                let start = common.iter().position(|ancestor| chain.contains(ancestor));
+                common[start.unwrap_or(common.len() - 1)..].to_vec()
+            }
+        });
+    }
+
+    let parent = common
+        .and_then(|common| common.into_iter().find(|ancestor| ancestor != label))
+        .unwrap_or_else(|| entry.label.clone());
+    parents.insert(label.into(), parent.clone());
+    parent
+}
+
+/// Returns the block with the label, and its ancestors, up to the entry block.
+fn ancestors<I: HasTerm>(
+    label: &str,
+    entry: &BasicBlock<I>,
+    blocks: &FxHashMap<String, BasicBlock<I>>,
+    parents: &mut FxHashMap<String, String>,
+) -> Vec<String> {
+    let mut chain = vec![label.to_string()];
+    while chain.last().unwrap() != &entry.label && chain.len() <= blocks.len() + 1 {
+        let parent = parent(chain.last().unwrap(), entry, blocks, parents);
+        chain.push(parent);
+    }
+
+    chain
+}
+
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '$')
+}
+
+impl ParseInstruction for Instruction {
+    fn parse_instruction(parser: &mut Parser) -> Result<Self> {
+        parser.instruction()
+    }
+
+    fn parse_term(parser: &mut Parser) -> Result<Term> {
+        parser.term()
+    }
+}
+
+impl ParseInstruction for visit::Instruction {
+    fn parse_instruction(parser: &mut Parser) -> Result<Self> {
+        use visit::Instruction::*;
+
+        if parser.eat("%vlen") {
+            parser.expect("=")?;
+            if parser.eat("+") {
+                parser.expect("%vlen")?;
+                parser.expect("(")?;
+                parser.expect_keyword("int-is-whnf")?;
+                let index = parser.u64()?;
+                parser.expect(")")?;
+
+                return Ok(IncreaseLen(index));
+            }
+            parser.expect("0")?;
+
+            return Ok(SetVLen);
+        }
+        if parser.eat("%vbuf") {
+            parser.expect("=")?;
+            return Ok(SetVBuf(Self::parse_term(parser)?));
+        }
+        if parser.eat("%go-up") {
+            parser.expect("=")?;
+            return Ok(SetGoup(Self::parse_term(parser)?));
+        }
+        if parser.eat("ctx.cont") {
+            parser.expect("=")?;
+            parser.expect("%go-up")?;
+            return Ok(UpdateCont);
+        }
+        if parser.eat("ctx.host") {
+            parser.expect("=")?;
+            parser.expect("$updated-host")?;
+            return Ok(UpdateHost);
+        }
+
+        parser.expect("(")?;
+        parser.expect_keyword("visit-argument")?;
+        let index = parser.u64()?;
+        parser.expect(")")?;
+
+        Ok(Visit(index))
+    }
+
+    fn parse_term(parser: &mut Parser) -> Result<visit::Term> {
+        if parser.eat_keyword("new_redex") {
+            Ok(visit::Term::Redex)
+        } else if parser.eat_keyword("new_vbuf") {
+            Ok(visit::Term::CreateVBuf)
+        } else if parser.eat_keyword("vlen") {
+            parser.expect("==")?;
+            parser.expect("0")?;
+            Ok(visit::Term::CheckVLen)
+        } else if parser.eat_keyword("true") {
+            Ok(visit::Term::True)
+        } else if parser.eat_keyword("false") {
+            Ok(visit::Term::False)
+        } else {
+            parser.error("expected a visit term")
+        }
+    }
+}
+
+impl ParseInstruction for reduce::Instruction {
+    fn parse_instruction(parser: &mut Parser) -> Result<Self> {
+        if parser.eat("ctx.term") {
+            parser.expect("=")?;
+            parser.expect("(")?;
+            parser.expect_keyword("load")?;
+            parser.expect("ctx.host")?;
+            parser.expect(")")?;
+
+            return Ok(reduce::Instruction::LoadTerm);
+        }
+
+        parser.expect("%fid")?;
+        parser.expect("=")?;
+        parser.expect("(")?;
+        parser.expect_keyword("get-ext")?;
+        parser.expect("ctx.term")?;
+        parser.expect(")")?;
+
+        Ok(reduce::Instruction::SetFunctionId)
+    }
+
+    fn parse_term(parser: &mut Parser) -> Result<reduce::Term> {
+        if parser.eat("%tag") {
+            Ok(reduce::Term::CurrentTag)
+        } else if parser.eat("'") {
+            let name = parser.name()?;
+            match TAGS.iter().find(|tag| tag.to_string() == name) {
+                Some(tag) => Ok(reduce::Term::Tag(tag.clone())),
+                None => parser.error(format!("unknown tag `'{name}`")),
+            }
+        } else if parser.eat("%fid") {
+            Ok(reduce::Term::FunctionId)
+        } else if parser.eat("#") {
+            Ok(reduce::Term::Id(parser.u64()?))
+        } else if parser.eat("(") {
+            let is_rule = parser.eat_keyword("rule");
+            let stage = if parser.eat_keyword("visit") {
+                reduce::Stage::Visit
+            } else {
+                parser.expect_keyword("apply")?;
+                reduce::Stage::Apply
+            };
+            let term = match is_rule {
+                true => reduce::Term::Rule(stage, parse_rule(parser)?),
+                false => reduce::Term::Call(stage, parser.name()?),
+            };
+            parser.expect(")")?;
+            Ok(term)
+        } else if parser.eat_keyword("complete-redex") {
+            Ok(reduce::Term::CompleteRedex)
+        } else if parser.eat_keyword("next-visit") {
+            Ok(reduce::Term::NextVisit)
+        } else if parser.eat_keyword("true") {
+            Ok(reduce::Term::True)
+        } else if parser.eat_keyword("false") {
+            Ok(reduce::Term::False)
+        } else {
+            parser.error("expected a reduce term")
+        }
+    }
+}
+
+/// Parses a rule of the runtime, of the reduce terms.
+fn parse_rule(parser: &mut Parser) -> Result<reduce::Rule> {
+    if parser.eat_keyword("app") {
+        Ok(reduce::Rule::App)
+    } else if parser.eat_keyword("dup") {
+        Ok(reduce::Rule::Dup)
+    } else if parser.eat_keyword("op2") {
+        Ok(reduce::Rule::Op2)
+    } else if parser.eat_keyword("fun") {
+        Ok(reduce::Rule::Fun)
+    } else {
+        parser.error("expected a rule of the runtime")
+    }
+}
+
+impl FromStr for Block {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Parser::new(s).block()
+    }
+}
+
+impl<I: ParseInstruction> FromStr for BasicBlock<I> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Parser::new(s).graph()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Block {
+        let mut block = Block::new(vec![
+            Instruction::binding("arg0", Term::load_arg(Term::Current, 0)),
+            Instruction::binding("tag", Term::reference("arg0").get_tag()),
+            Instruction::cond(
+                Term::logical_and(
+                    Term::equal(Term::reference("tag"), Term::Tag(Tag::CONSTRUCTOR)),
+                    Term::equal(Term::reference("arg0").get_ext(), Term::ext(31, "Succ")),
+                ),
+                Block::new(vec![
+                    Instruction::IncrementCost,
+                    Instruction::binding("ctr", Term::alloc(2)),
+                    Instruction::link(
+                        Position::new("ctr", 1),
+                        Term::create_binary(
+                            Term::reference("arg0").get_num(),
+                            Oper::Sub,
+                            Term::create_f60(1.5),
+                            Position::initial("ctr"),
+                        ),
+                    ),
+                    Instruction::link(
+                        Position::Host,
+                        Term::create_constructor(FunctionId::new("Succ", 31), Position::initial("ctr")),
+                    ),
+                    Instruction::collect(Term::create_dp0(3, Position::new("ctr", 1))),
+                    Instruction::binding(
+                        "sum",
+                        Term::select(
+                            Term::equal(Term::reference("tag"), Term::Tag(Tag::U60)),
+                            Term::operate(Term::reference("arg0"), Oper::Add, Term::create_u60(1)),
+                            Term::erased(),
+                        ),
+                    ),
+                    Instruction::Free(Free {
+                        position: Term::get_position(Term::reference("arg0"), 0),
+                        arity: 1,
+                    }),
+                    Instruction::Println("done \"ok\"\n".into()),
+                    Instruction::ret(Term::True),
+                ]),
+                Some(Block::with(Instruction::ret(Term::False))),
+            ),
+            Instruction::ret(Term::False),
+        ]);
+        block.tags = vec![("Constructor".into(), Tag::CONSTRUCTOR.id())];
+        block.extensions = vec![("Succ".into(), 31)];
+        block
+    }
+
+    #[test]
+    fn it_round_trips_blocks() {
+        let text = format!("{:?}", example());
+        let block = text.parse::<Block>().unwrap();
+
+        assert_eq!(format!("{block:?}"), text);
+    }
+
+    #[test]
+    fn it_round_trips_graphs() {
+        let text = example().into_control_flow_graph().to_string();
+        let bb = text.parse::<BasicBlock<Instruction>>().unwrap();
+
+        assert_eq!(bb.to_string(), text);
+    }
+
+    #[test]
+    fn it_declares_the_join_blocks() {
+        use crate::ir::verify::Verify;
+
+        let block = "
+entry:
+  %arg0 = (load-argument ^current-term 0)
+  if (%arg0/tag == 'U60):
+    if (%arg0/num == #0):
+      ret true
+    end
+    link [^host] %arg0
+  end
+  ret false
+";
+        let bb = block.parse::<Block>().unwrap().into_control_flow_graph();
+        assert!(bb.verify().is_ok());
+
+        // The continuation of each `if` is declared next to its branches, once
+        let text = bb.to_string();
+        let bb = text.parse::<BasicBlock<Instruction>>().unwrap();
+        assert_eq!(bb.to_string(), text);
+        assert_eq!(text.matches("ret false").count(), 1);
+        assert!(bb.declared_blocks.contains_key("bb_2"));
+        assert!(bb.declared_blocks["bb_1"].declared_blocks.contains_key("bb_4"));
+        assert!(text.contains("jmp @bb_2"));
+    }
+
+    #[test]
+    fn it_reports_the_error_location() {
+        let error = "entry:\n  link %ctr (U60.new x)".parse::<Block>().unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 22));
+    }
+}
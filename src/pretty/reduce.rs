@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+
+use crate::ir::reduce::{Instruction, Rule, Stage, Term};
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Visit => write!(f, "visit"),
+            Stage::Apply => write!(f, "apply"),
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::App => write!(f, "app"),
+            Rule::Dup => write!(f, "dup"),
+            Rule::Op2 => write!(f, "op2"),
+            Rule::Fun => write!(f, "fun"),
+        }
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::CurrentTag => write!(f, "%tag"),
+            Term::Tag(tag) => write!(f, "'{tag}"),
+            Term::FunctionId => write!(f, "%fid"),
+            Term::Id(id) => write!(f, "#{id}"),
+            Term::Call(stage, name) => write!(f, "({stage} {name})"),
+            Term::Rule(stage, rule) => write!(f, "(rule {stage} {rule})"),
+            Term::CompleteRedex => write!(f, "complete-redex"),
+            Term::NextVisit => write!(f, "next-visit"),
+            Term::True => write!(f, "true"),
+            Term::False => write!(f, "false"),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::LoadTerm => write!(f, "ctx.term = (load ctx.host)"),
+            Instruction::SetFunctionId => write!(f, "%fid = (get-ext ctx.term)"),
+        }
+    }
+}
@@ -11,6 +11,7 @@ impl Display for Term {
                 name,
                 box value,
                 box body,
+                ..
             }) => write!(f, "let {name} = {value} in {body}"),
             Term::App(App {
                 callee, arguments, ..
@@ -48,10 +49,11 @@ impl Display for Term {
                 to,
                 box value,
                 box body,
+                ..
             }) => {
                 write!(f, "dup {from} {to} = {value} in {body}")
             }
-            Term::Super(Super { first, second }) => {
+            Term::Super(Super { first, second, .. }) => {
                 write!(f, "({first}, {second})")
             }
         }
@@ -1,6 +1,7 @@
 use core::slice::SlicePattern;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use hvm::runtime::Function;
 use hvm::{Ptr, ReduceCtx};
 
 pub type ReduceContext = *mut ReduceCtx<'static>;
@@ -9,24 +10,6 @@ pub type Tag = u64;
 pub type Position = u64;
 pub type Host = *mut u64;
 
-#[no_mangle]
-#[allow(clippy::missing_safety_doc)]
-pub unsafe extern "C" fn hvm__llvm_eq(a: u64, b: u64) -> bool {
-    a == b
-}
-
-#[no_mangle]
-#[allow(clippy::missing_safety_doc)]
-pub unsafe extern "C" fn hvm__llvm_or(a: bool, b: bool) -> bool {
-    a || b
-}
-
-#[no_mangle]
-#[allow(clippy::missing_safety_doc)]
-pub unsafe extern "C" fn hvm__llvm_and(a: bool, b: bool) -> bool {
-    a && b
-}
-
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__increment_cost(ctx: ReduceContext) {
@@ -79,6 +62,22 @@ pub unsafe extern "C" fn hvm__free(ctx: ReduceContext, position: Position, arity
     hvm::runtime::free(ctx.heap, ctx.tid, position, arity)
 }
 
+/// Collects the erased term, freeing its nodes, with the HVM's garbage collector.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__collect(ctx: ReduceContext, term: Pointer) {
+    let ctx = get_context(ctx);
+
+    hvm::runtime::collect(ctx.heap, &ctx.prog.aris, ctx.tid, term)
+}
+
+/// Prints the message, that is a null-terminated string constant of the module.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__println(message: *const std::ffi::c_char) {
+    println!("{}", std::ffi::CStr::from_ptr(message).to_string_lossy())
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__insert_redex(ctx: ReduceContext, vlen: u64) -> u64 {
@@ -100,7 +99,7 @@ pub unsafe extern "C" fn hvm__update_cont(ctx: ReduceContext, goup: u64) {
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__update_host(ctx: ReduceContext, vbuf: *mut AtomicU64, vlen: u64) {
     let ctx = get_context(ctx);
-    let host = vbuf.add((vlen - 1) as usize).read().load(Ordering::Relaxed);
+    let host = (*vbuf.add((vlen - 1) as usize)).load(Ordering::Relaxed);
 
     *ctx.host = host;
 }
@@ -117,10 +116,7 @@ pub unsafe extern "C" fn hvm__visit(
     let ctx = get_context(ctx);
 
     if parameter_index < vlen - 1 {
-        let vbuf = vbuf
-            .add(parameter_index as usize)
-            .read()
-            .load(Ordering::Relaxed);
+        let vbuf = (*vbuf.add(parameter_index as usize)).load(Ordering::Relaxed);
         let visit = hvm::runtime::new_visit(vbuf, ctx.hold, goup);
         ctx.visit.push(visit);
     }
@@ -139,21 +135,22 @@ pub unsafe extern "C" fn hvm__increase_vlen(
     if hvm::runtime::is_whnf(hvm::runtime::load_arg(ctx.heap, ctx.term, parameter_index)) {
         0
     } else {
-        let atomic = vbuf.add(vlen as usize).read();
-        let position = hvm::runtime::get_loc(ctx.term, 0);
-        atomic.store(position, Ordering::Relaxed);
+        let position = hvm::runtime::get_loc(ctx.term, parameter_index);
+        (*vbuf.add(vlen as usize)).store(position, Ordering::Relaxed);
 
         1
     }
 }
 
+/// Returns the visit buffer of the current thread, as a pointer to its first
+/// element, so it can be passed to the native code.
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
-pub unsafe extern "C" fn hvm__create_vbuf(ctx: ReduceContext) -> *mut &'static [AtomicU64] {
+pub unsafe extern "C" fn hvm__create_vbuf(ctx: ReduceContext) -> *mut AtomicU64 {
     let ctx = get_context(ctx);
 
     let vbuf = ctx.heap.vbuf.get_unchecked(ctx.tid);
-    Box::into_raw(Box::new(vbuf.as_slice()))
+    vbuf.as_slice().as_ptr() as *mut AtomicU64
 }
 
 #[no_mangle]
@@ -176,6 +173,30 @@ pub unsafe extern "C" fn hvm__load_argument(
     hvm::runtime::load_arg(ctx.heap, term, index)
 }
 
+/// Takes the argument of the node in the position, with an index that is only
+/// known in runtime.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__take_argument(
+    ctx: ReduceContext,
+    position: Position,
+    index: u64,
+) -> Pointer {
+    let ctx = get_context(ctx);
+
+    hvm::runtime::load_ptr(ctx.heap, position + index)
+}
+
+/// Gets the arity of the node of the pointer, the arity of the functions and
+/// constructors is looked up in the arity table of the program.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__arity_of(ctx: ReduceContext, pointer: Pointer) -> u64 {
+    let ctx = get_context(ctx);
+
+    hvm::runtime::arity_of(&ctx.prog.aris, pointer)
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__get_loc(pointer: Pointer, argument: Position) -> Pointer {
@@ -206,6 +227,12 @@ pub unsafe extern "C" fn hvm__create_u60(value: u64) -> Pointer {
     hvm::runtime::U6O(value)
 }
 
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__create_f60(value: f64) -> Pointer {
+    hvm::runtime::F6O(hvm::runtime::f60::new(value))
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__create_constructor(fun: u64, position: Position) -> Pointer {
@@ -224,6 +251,63 @@ pub unsafe extern "C" fn hvm__create_binary(operand: Position, position: Positio
     hvm::runtime::Op2(operand, position)
 }
 
+/// Computes the binary operation inline, like the `Op2` rule does, the operands
+/// must be both U60, or both F60.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__operate(operand: u64, lhs: Pointer, rhs: Pointer) -> Pointer {
+    use hvm::runtime::{f60, u60, ADD, AND, DIV, EQL, GTE, GTN, LTE, LTN, MOD, MUL, NEQ, OR, SHL, SHR, SUB, XOR};
+
+    let a = hvm::runtime::get_num(lhs);
+    let b = hvm::runtime::get_num(rhs);
+
+    if hvm::runtime::get_tag(lhs) == hvm::F60 {
+        let c = match operand {
+            ADD => f60::add(a, b),
+            SUB => f60::sub(a, b),
+            MUL => f60::mul(a, b),
+            DIV => f60::div(a, b),
+            MOD => f60::mdl(a, b),
+            AND => f60::and(a, b),
+            OR => f60::or(a, b),
+            XOR => f60::xor(a, b),
+            SHL => f60::shl(a, b),
+            SHR => f60::shr(a, b),
+            LTN => f60::ltn(a, b),
+            LTE => f60::lte(a, b),
+            EQL => f60::eql(a, b),
+            GTE => f60::gte(a, b),
+            GTN => f60::gtn(a, b),
+            NEQ => f60::neq(a, b),
+            _ => unreachable!("unknown binary operator {operand}"),
+        };
+
+        return hvm::runtime::F6O(c);
+    }
+
+    let c = match operand {
+        ADD => u60::add(a, b),
+        SUB => u60::sub(a, b),
+        MUL => u60::mul(a, b),
+        DIV => u60::div(a, b),
+        MOD => u60::mdl(a, b),
+        AND => u60::and(a, b),
+        OR => u60::or(a, b),
+        XOR => u60::xor(a, b),
+        SHL => u60::shl(a, b),
+        SHR => u60::shr(a, b),
+        LTN => u60::ltn(a, b),
+        LTE => u60::lte(a, b),
+        EQL => u60::eql(a, b),
+        GTE => u60::gte(a, b),
+        GTN => u60::gtn(a, b),
+        NEQ => u60::neq(a, b),
+        _ => unreachable!("unknown binary operator {operand}"),
+    };
+
+    hvm::runtime::U6O(c)
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__create_app(position: Position) -> Pointer {
@@ -242,12 +326,167 @@ pub unsafe extern "C" fn hvm__create_lam(position: Position) -> Pointer {
     hvm::runtime::Lam(position)
 }
 
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__create_dp0(color: u64, position: Position) -> Pointer {
+    hvm::runtime::Dp0(color, position)
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__create_dp1(color: u64, position: Position) -> Pointer {
+    hvm::runtime::Dp1(color, position)
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__create_super(color: u64, position: Position) -> Pointer {
+    hvm::runtime::Sup(color, position)
+}
+
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn hvm__create_function(fun: u64, position: Position) -> Pointer {
     hvm::runtime::Fun(fun, position)
 }
 
+/// Loads the term of the host to the current term of the context, and returns
+/// it, the next rule, or compiled function, reduces it.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__load_term(ctx: ReduceContext) -> Pointer {
+    let context = get_context(ctx);
+    let term = hvm::runtime::load_ptr(context.heap, *context.host);
+    (*ctx).term = term;
+
+    term
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__visit_app(ctx: ReduceContext) -> bool {
+    hvm::runtime::app::visit(get_context(ctx))
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__apply_app(ctx: ReduceContext) -> bool {
+    hvm::runtime::app::apply(get_context(ctx))
+}
+
+/// Visits the duplication, with its lock acquired, that is released when it's
+/// applied. It's visited again when the lock can't be acquired, or when the
+/// term of the host changed in the meantime.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__visit_dup(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+    let (heap, tid, term) = (ctx.heap, ctx.tid, ctx.term);
+
+    if hvm::runtime::acquire_lock(heap, tid, term).is_err() {
+        return true;
+    }
+    if term != hvm::runtime::load_ptr(heap, *ctx.host) {
+        hvm::runtime::release_lock(heap, tid, term);
+        return true;
+    }
+
+    hvm::runtime::dup::visit(ctx)
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__apply_dup(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+    let (heap, tid, term) = (ctx.heap, ctx.tid, ctx.term);
+
+    let done = hvm::runtime::dup::apply(ctx);
+    hvm::runtime::release_lock(heap, tid, term);
+
+    done
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__visit_op2(ctx: ReduceContext) -> bool {
+    hvm::runtime::op2::visit(get_context(ctx))
+}
+
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__apply_op2(ctx: ReduceContext) -> bool {
+    hvm::runtime::op2::apply(get_context(ctx))
+}
+
+/// Visits the function application that isn't compiled, like the built-in
+/// functions, with the function of the program.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__visit_fun(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+    let prog = ctx.prog;
+
+    match prog.funs.get(&hvm::runtime::get_ext(ctx.term)) {
+        Some(Function::Interpreted { visit, .. }) => hvm::runtime::fun::visit(ctx, &visit.strict_idx),
+        Some(Function::Compiled { visit, .. }) => visit(ctx),
+        None => false,
+    }
+}
+
+/// Applies the function application that isn't compiled, like the built-in
+/// functions, with the function of the program.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__apply_fun(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+    let prog = ctx.prog;
+    let fid = hvm::runtime::get_ext(ctx.term);
+
+    match prog.funs.get(&fid) {
+        Some(Function::Interpreted { visit, apply, .. }) => hvm::runtime::fun::apply(ctx, fid, visit, apply),
+        Some(Function::Compiled { apply, .. }) => apply(ctx),
+        None => false,
+    }
+}
+
+/// Completes the redex of the continuation, and moves the host to its function
+/// application, when all of its strict arguments are reduced. It returns false
+/// when the continuation is the root, that is already in the WHNF.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__complete_redex(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+    if *ctx.cont == hvm::runtime::REDEX_CONT_RET {
+        return false;
+    }
+
+    match ctx.redex.complete(*ctx.cont) {
+        Some((cont, host)) => {
+            *ctx.cont = cont;
+            *ctx.host = host;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Moves the host to the next location of the visit queue, returning false
+/// when there are no more locations to visit.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn hvm__next_visit(ctx: ReduceContext) -> bool {
+    let ctx = get_context(ctx);
+
+    match ctx.visit.pop() {
+        Some((cont, host)) => {
+            *ctx.cont = cont;
+            *ctx.host = host;
+            true
+        }
+        None => false,
+    }
+}
+
 fn get_context<'a>(ctx: ReduceContext) -> ReduceCtx<'a> {
     unsafe {
         if ctx.is_null() {